@@ -2,7 +2,9 @@
 
 use anyhow::Result;
 use clap::Args;
-use rustash_core::{models::SnippetWithTags, storage::StorageBackend};
+use rustash_core::{
+    alias_tag, models::SnippetWithTags, storage::StorageBackend, SnippetService, DANGEROUS_TAG,
+};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -23,6 +25,14 @@ pub struct AddCommand {
     #[arg(short, long, value_delimiter = ',')]
     pub tags: Vec<String>,
 
+    /// Aliases the snippet can be referred to by instead of its UUID
+    #[arg(long, value_delimiter = ',')]
+    pub alias: Vec<String>,
+
+    /// Always ask for confirmation before `snippets run` executes this snippet
+    #[arg(long)]
+    pub dangerous: bool,
+
     /// Read content from stdin instead of command line
     #[arg(long)]
     pub stdin: bool,
@@ -59,8 +69,17 @@ impl AddCommand {
             "Content cannot be empty for CLI usage."
         );
 
-        let new_snippet =
-            SnippetWithTags::with_uuid(Uuid::new_v4(), title.clone(), content, self.tags.clone());
+        let mut tags = self.tags.clone();
+        for alias in &self.alias {
+            tags.push(alias_tag(alias)?);
+        }
+        if self.dangerous && !tags.iter().any(|tag| tag == DANGEROUS_TAG) {
+            tags.push(DANGEROUS_TAG.to_string());
+        }
+        let new_snippet = SnippetWithTags::with_uuid(Uuid::new_v4(), title.clone(), content, tags);
+        SnippetService::new(backend.clone())
+            .check_aliases(&new_snippet.id, &new_snippet.tags)
+            .await?;
         backend.save(&new_snippet).await?;
         println!("\u{2713} Added snippet '{}' to stash.", new_snippet.title);
         Ok(())
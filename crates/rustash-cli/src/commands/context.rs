@@ -0,0 +1,46 @@
+//! Project context command
+
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use rustash_core::{storage::StorageBackend, ProjectContext};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct ContextCommand {
+    /// Print the context as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl ContextCommand {
+    pub async fn execute(self, _backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let context = ProjectContext::current()?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&context)?);
+            return Ok(());
+        }
+        let Some(context) = context else {
+            println!("Not in a project.");
+            return Ok(());
+        };
+
+        let field = |name: &str, value: &str| {
+            let label = format!("{:<10}", format!("{}:", name));
+            println!("{} {}", style(label).bold().cyan(), value);
+        };
+        field("Project", &context.name);
+        field("Root", &context.root.display().to_string());
+        if let Some(remote) = &context.remote {
+            field("Remote", remote);
+        }
+        if !context.languages.is_empty() {
+            field("Languages", &context.languages.join(", "));
+        }
+        field(
+            "Boosted",
+            &style(context.boost_tags().join(", ")).yellow().to_string(),
+        );
+        Ok(())
+    }
+}
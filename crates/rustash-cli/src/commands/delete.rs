@@ -0,0 +1,42 @@
+//! Snippet delete command
+
+use anyhow::Result;
+use clap::Args;
+use dialoguer::Confirm;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct DeleteCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// Delete without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl DeleteCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
+
+        if !self.yes {
+            let confirmed = Confirm::new()
+                .with_prompt(format!("Move '{}' to the trash?", snippet.title))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                println!("Aborted.");
+                return Ok(());
+            }
+        }
+
+        service.delete_snippet(&snippet.id).await?;
+        println!("\u{2713} Moved snippet '{}' to the trash.", snippet.title);
+        println!(
+            "  Undo with: rustash snippets trash restore {}",
+            snippet.uuid
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+//! Snippet diff command
+
+use crate::utils::format_version_diff;
+use anyhow::Result;
+use clap::Args;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct DiffCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// The older version number
+    pub from: u32,
+    /// The newer version number
+    pub to: u32,
+}
+
+impl DiffCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet_uuid = service.resolve_id(&self.snippet).await?;
+
+        let diff = service.diff(&snippet_uuid, self.from, self.to).await?;
+        if diff.is_empty() {
+            println!("Versions {} and {} are identical.", self.from, self.to);
+            return Ok(());
+        }
+
+        format_version_diff(&diff)
+    }
+}
@@ -0,0 +1,71 @@
+//! Edit snippet command
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use rustash_core::{parse_front_matter, storage::StorageBackend, to_front_matter, SnippetService};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct EditCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+}
+
+impl EditCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
+
+        let document = to_front_matter(&snippet)?;
+        let path = std::env::temp_dir().join(format!("rustash-{}.md", snippet.uuid));
+        std::fs::write(&path, &document)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        open_editor(&path)?;
+        let edited = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        if edited == document {
+            std::fs::remove_file(&path)?;
+            println!("No changes made.");
+            return Ok(());
+        }
+
+        // Keep the file around on failure so the edits are not lost.
+        let update = parse_front_matter(&edited)
+            .with_context(|| format!("Your edits were kept in {}", path.display()))?;
+        let snippet = service
+            .update_snippet(&snippet.id, &update)
+            .await
+            .with_context(|| format!("Your edits were kept in {}", path.display()))?;
+
+        std::fs::remove_file(&path)?;
+        println!("\u{2713} Updated snippet '{}'.", snippet.title);
+        Ok(())
+    }
+}
+
+/// Open `path` in `$VISUAL`, `$EDITOR` or `vi`, and wait for it to close.
+fn open_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // Allow editors configured with arguments, such as `code --wait`.
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("No editor configured")?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", editor))?;
+
+    if !status.success() {
+        bail!("Editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Export snippets command
+
+use anyhow::{Context, Result};
+use clap::Args;
+use console::style;
+use rustash_core::{
+    export::{exporter, EXPORT_FORMATS},
+    models::{Query, TagFilter},
+    storage::StorageBackend,
+    SnippetService,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct ExportCommand {
+    /// Format to write
+    #[arg(long, value_parser = EXPORT_FORMATS)]
+    pub to: String,
+    /// Only export snippets with this tag; repeat to require several (`lang/*` matches by prefix)
+    #[arg(short, long)]
+    pub tag: Vec<String>,
+    /// Only export snippets with at least one of these tags
+    #[arg(long)]
+    pub any_tag: Vec<String>,
+    /// Leave out snippets with this tag
+    #[arg(long)]
+    pub not_tag: Vec<String>,
+    /// Write to this file instead of standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+impl ExportCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let exporter = exporter(&self.to)?;
+        let query = Query {
+            tag_filter: TagFilter {
+                all: self.tag,
+                any: self.any_tag,
+                none: self.not_tag,
+            },
+            ..Default::default()
+        };
+        let export = SnippetService::new(backend)
+            .export(&query, exporter.as_ref())
+            .await?;
+
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, &export.text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!(
+                    "\u{2713} Exported {} snippets to {}",
+                    export.count,
+                    path.display()
+                );
+            }
+            None => print!("{}", export.text),
+        }
+
+        // Standard output may hold the export itself
+        if !export.skipped.is_empty() {
+            eprintln!("Skipped {}:", export.skipped.len());
+            for skipped in &export.skipped {
+                eprintln!(
+                    "  {} {}: {}",
+                    style("-").dim(),
+                    skipped.title,
+                    skipped.reason
+                );
+            }
+        }
+        Ok(())
+    }
+}
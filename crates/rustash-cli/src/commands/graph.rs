@@ -1,6 +1,7 @@
+use crate::output::{OutputArgs, Record};
 use anyhow::Result;
 use clap::{Args, Subcommand};
-use rustash_core::{models::Snippet, storage::StorageBackend, Uuid};
+use rustash_core::{models::SnippetWithTags, storage::StorageBackend, SnippetService};
 use std::sync::Arc;
 
 #[derive(Args)]
@@ -13,35 +14,56 @@ pub struct GraphCommand {
 pub enum GraphSubcommand {
     /// Link two items in the knowledge graph
     Link {
-        from: Uuid,
-        to: Uuid,
+        /// UUID of the source item, or the UUID prefix, title or alias of a snippet
+        from: String,
+        /// UUID of the target item, or the UUID prefix, title or alias of a snippet
+        to: String,
         #[arg(short, long, default_value = "RELATED_TO")]
         relation: String,
     },
     /// Find items related to a given item
     Neighbors {
-        id: Uuid,
+        /// UUID of the item, or the UUID prefix, title or alias of a snippet
+        id: String,
         #[arg(short, long)]
         relation: Option<String>,
+        #[command(flatten)]
+        output: OutputArgs,
     },
 }
 
 impl GraphCommand {
     pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend.clone());
         match self.command {
             GraphSubcommand::Link { from, to, relation } => {
+                let from = service.resolve_id(&from).await?;
+                let to = service.resolve_id(&to).await?;
                 backend.add_relation(&from, &to, &relation).await?;
                 println!("\u{2713} Linked {} -[{}]-> {}", from, relation, to);
             }
-            GraphSubcommand::Neighbors { id, relation } => {
+            GraphSubcommand::Neighbors {
+                id,
+                relation,
+                output,
+            } => {
+                let machine_format = output.machine_format(&["list"])?;
+                let id = service.resolve_id(&id).await?;
                 let results = backend.get_related(&id, relation.as_deref()).await?;
-                if results.is_empty() {
+                if let Some(format) = machine_format {
+                    let records: Vec<_> = results
+                        .iter()
+                        .map(|item| Record::from_item(item.as_ref()))
+                        .collect();
+                    output.print(format, &records)?;
+                } else if results.is_empty() {
                     println!("No related items found for {}.", id);
                 } else {
                     println!("Found {} related items for {}:", results.len(), id);
                     for item in results {
-                        if let Some(snippet) = item.as_any().downcast_ref::<Snippet>() {
-                            println!("  - {} ({})", snippet.uuid, snippet.title);
+                        match item.as_any().downcast_ref::<SnippetWithTags>() {
+                            Some(snippet) => println!("  - {} ({})", snippet.uuid, snippet.title),
+                            None => println!("  - {} [{}]", item.id(), item.item_type()),
                         }
                     }
                 }
@@ -0,0 +1,36 @@
+//! Snippet history command
+
+use crate::output::{OutputArgs, Record};
+use crate::utils::format_version_history;
+use anyhow::Result;
+use clap::Args;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct HistoryCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+impl HistoryCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let machine_format = self.output.machine_format(&["table"])?;
+        let service = SnippetService::new(backend);
+        let snippet_uuid = service.resolve_id(&self.snippet).await?;
+
+        let versions = service.history(&snippet_uuid).await?;
+        if let Some(format) = machine_format {
+            let records: Vec<_> = versions.iter().map(Record::from_version).collect();
+            return self.output.print(format, &records);
+        }
+        if versions.is_empty() {
+            println!("No history found for snippet {}.", snippet_uuid);
+            return Ok(());
+        }
+
+        format_version_history(&versions)
+    }
+}
@@ -0,0 +1,79 @@
+//! Import snippets command
+
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use rustash_core::{
+    import::{importer, read_snippets, IMPORT_FORMATS},
+    storage::StorageBackend,
+    SnippetService,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct ImportCommand {
+    /// Format of the snippets to import
+    #[arg(long, value_parser = IMPORT_FORMATS)]
+    pub from: String,
+    /// A file, or a directory to search for files in that format
+    pub path: PathBuf,
+    /// Tag every imported snippet; repeat or separate with commas to add several
+    #[arg(short, long, value_delimiter = ',')]
+    pub tags: Vec<String>,
+    /// Show what would be imported without changing the stash
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl ImportCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let importer = importer(&self.from)?;
+        let mut snippets = read_snippets(importer.as_ref(), &self.path)?;
+        for snippet in &mut snippets {
+            snippet.tags.extend(self.tags.iter().cloned());
+        }
+
+        let service = SnippetService::new(backend);
+        let plan = service.plan_import(snippets).await?;
+        if self.dry_run {
+            println!("Would import {} snippets:", plan.new.len());
+            for snippet in &plan.new {
+                let tags = if snippet.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", snippet.tags.join(", "))
+                };
+                println!(
+                    "  {} {}{}",
+                    style("+").green(),
+                    snippet.title,
+                    style(tags).yellow()
+                );
+            }
+        } else {
+            service.import(&plan).await?;
+            println!("\u{2713} Imported {} snippets.", plan.new.len());
+        }
+
+        if !plan.skipped.is_empty() {
+            println!("Skipped {}:", plan.skipped.len());
+            for skipped in &plan.skipped {
+                println!(
+                    "  {} {} ({}): {}",
+                    style("-").dim(),
+                    skipped.title,
+                    skipped.source.display(),
+                    skipped.reason
+                );
+            }
+        }
+        if !plan.dropped_aliases.is_empty() {
+            println!(
+                "Left out aliases already in use: {}",
+                plan.dropped_aliases.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
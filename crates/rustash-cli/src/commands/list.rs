@@ -1,36 +1,114 @@
 //! List snippets command
 
 use crate::fuzzy::fuzzy_select_snippet;
+use crate::output::{OutputArgs, Record};
 use crate::utils::format_snippet_list;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Args;
-use rustash_core::{models::Query, storage::StorageBackend};
+use rustash_core::{
+    models::{Query, TagFilter},
+    storage::{SortSpec, StorageBackend},
+    usage::FRECENCY_SORT,
+    ProjectContext,
+};
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 #[derive(Args)]
 pub struct ListCommand {
+    /// Full-text search expression (supports phrases, `prefix*`, AND/OR/NOT and `column:term`)
     #[arg(short, long)]
     pub filter: Option<String>,
+    /// Only show snippets with this tag; repeat to require several (`lang/*` matches by prefix)
     #[arg(short, long)]
-    pub tag: Option<String>,
+    pub tag: Vec<String>,
+    /// Only show snippets with at least one of these tags
+    #[arg(long)]
+    pub any_tag: Vec<String>,
+    /// Hide snippets with this tag
+    #[arg(long)]
+    pub not_tag: Vec<String>,
     #[arg(short, long, default_value = "50")]
     pub limit: usize,
+    /// Sort keys such as `title` or `updated_at:desc,title` (fields: title, created_at,
+    /// updated_at), or `frecency` for the most used first
+    #[arg(long)]
+    pub sort: Option<String>,
+    /// Reverse the sort order
+    #[arg(long)]
+    pub reverse: bool,
+    /// Page to show, starting at 1; pages are `--limit` snippets long
+    #[arg(long, conflicts_with = "after")]
+    pub page: Option<NonZeroUsize>,
+    /// Continue after the cursor printed at the end of a previous page
+    #[arg(long)]
+    pub after: Option<String>,
     #[arg(long)]
     pub interactive: bool,
-    #[arg(long, default_value = "table")]
-    pub format: String,
+    /// Rank snippets for the current project first and hide other projects' snippets;
+    /// on by default with --interactive
+    #[arg(long, overrides_with = "no_context")]
+    pub context: bool,
+    /// Ignore the current project, even with --interactive
+    #[arg(long)]
+    pub no_context: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
+/// Human-readable views of the list, the first being the default
+const VIEWS: [&str; 3] = ["table", "compact", "detailed"];
+
 impl ListCommand {
     pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
-        let query = Query {
+        let machine_format = self.output.machine_format(&VIEWS)?;
+        let sort_by = match (self.sort, self.reverse) {
+            (Some(sort), true) if sort.trim() == FRECENCY_SORT => {
+                bail!("--reverse cannot be combined with --sort {}", FRECENCY_SORT)
+            }
+            // The picker puts the most used snippets within reach
+            (None, false) if self.interactive && self.filter.is_none() => {
+                Some(FRECENCY_SORT.to_string())
+            }
+            (sort, false) => sort,
+            (Some(sort), true) => Some(SortSpec::parse(&sort)?.reversed().to_string()),
+            (None, true) if self.filter.is_some() => {
+                bail!("--reverse needs --sort when searching with --filter")
+            }
+            (None, true) => Some(SortSpec::default().reversed().to_string()),
+        };
+
+        let mut query = Query {
             text_filter: self.filter,
-            tags: self.tag.map(|t| vec![t]),
+            tag_filter: TagFilter {
+                all: self.tag,
+                any: self.any_tag,
+                none: self.not_tag,
+            },
             limit: Some(self.limit),
+            sort_by,
+            offset: self.page.map(|page| (page.get() - 1) * self.limit),
+            after: self.after,
             ..Default::default()
         };
+        if !self.no_context && (self.context || self.interactive) {
+            if let Some(context) = ProjectContext::current()? {
+                context.apply(&mut query);
+            }
+        }
 
-        let snippets_dyn = backend.query(&query).await?;
+        // A text filter without an explicit sort or ranking is a full-text
+        // search, so results come back most relevant first and the picker
+        // keeps that order.
+        let searching =
+            query.text_filter.is_some() && query.sort_by.is_none() && !query.needs_ranking();
+        let (snippets_dyn, next_cursor) = if searching {
+            let hits = backend.search(&query).await?;
+            (hits.into_iter().map(|hit| hit.item).collect(), None)
+        } else {
+            let page = backend.query_page(&query).await?;
+            (page.items, page.next_cursor)
+        };
         let snippets: Vec<_> = snippets_dyn
             .iter()
             .filter_map(|item| {
@@ -40,7 +118,7 @@ impl ListCommand {
             })
             .collect();
 
-        if snippets.is_empty() {
+        if snippets.is_empty() && machine_format.is_none() {
             println!("No snippets found.");
             return Ok(());
         }
@@ -50,7 +128,19 @@ impl ListCommand {
                 format_snippet_list(&[selected], "detailed")?;
             }
         } else {
-            format_snippet_list(&snippets, &self.format)?;
+            match machine_format {
+                Some(format) => {
+                    let records: Vec<_> = snippets
+                        .iter()
+                        .map(|snippet| Record::from_item(snippet))
+                        .collect();
+                    self.output.print(format, &records)?;
+                }
+                None => format_snippet_list(&snippets, self.output.view(&VIEWS))?,
+            }
+            if let Some(cursor) = next_cursor {
+                eprintln!("More snippets available; continue with --after {}", cursor);
+            }
         }
 
         Ok(())
@@ -2,8 +2,21 @@
 
 // Command-line argument definitions
 pub mod add;
+pub mod context;
+pub mod delete;
+pub mod diff;
+pub mod edit;
+pub mod export;
+pub mod history;
+pub mod import;
 pub mod list;
+pub mod recent;
+pub mod restore;
+pub mod run;
+pub mod show;
 pub mod stash_cmds;
+pub mod trash;
+pub mod update;
 pub mod use_snippet;
 
 pub mod graph;
@@ -11,3 +24,8 @@ pub mod rag;
 
 // Command logic/execution modules
 pub mod snippets;
+
+pub use graph::GraphCommand;
+pub use rag::RagCommand;
+pub use snippets::{SnippetCommand, SnippetCommands};
+pub use stash_cmds::StashCommand;
@@ -1,3 +1,4 @@
+use crate::output::{OutputArgs, Record};
 use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
 use rustash_core::{models::SnippetWithTags, storage::StorageBackend};
@@ -27,6 +28,8 @@ pub enum RagSubcommand {
         /// Number of results to return
         #[arg(short, long, default_value = "5")]
         limit: usize,
+        #[command(flatten)]
+        output: OutputArgs,
     },
 }
 
@@ -56,8 +59,15 @@ impl RagCommand {
                 backend.save(&snippet).await?;
                 println!("\u{2713} Document '{}' added to RAG stash.", snippet.title);
             }
-            RagSubcommand::Query { text, limit } => {
-                println!("Querying RAG stash for: '{}'", text);
+            RagSubcommand::Query {
+                text,
+                limit,
+                output,
+            } => {
+                let machine_format = output.machine_format(&["list"])?;
+                if machine_format.is_none() {
+                    println!("Querying RAG stash for: '{}'", text);
+                }
 
                 // --- Placeholder for Embedding Generation ---
                 let query_embedding: Vec<f32> = vec![0.1; 384]; // Must match dimension
@@ -65,7 +75,15 @@ impl RagCommand {
 
                 let results = backend.vector_search(&query_embedding, limit).await?;
 
-                if results.is_empty() {
+                if let Some(format) = machine_format {
+                    let records: Vec<_> = results
+                        .iter()
+                        .map(|(item, distance)| {
+                            Record::from_item(item.as_ref()).with("distance", *distance)
+                        })
+                        .collect();
+                    output.print(format, &records)?;
+                } else if results.is_empty() {
                     println!("No similar documents found.");
                 } else {
                     println!("Found {} similar documents:", results.len());
@@ -0,0 +1,47 @@
+//! Recently used snippets command
+
+use crate::output::{OutputArgs, Record};
+use crate::utils::format_recent;
+use anyhow::{Context, Result};
+use clap::Args;
+use rustash_core::{storage::StorageBackend, usage::current_place, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct RecentCommand {
+    /// Maximum number of snippets to show
+    #[arg(short, long, default_value = "10")]
+    pub limit: usize,
+    /// Only count uses in the current git repository, or this directory outside one
+    #[arg(long)]
+    pub here: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+impl RecentCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let machine_format = self.output.machine_format(&["table"])?;
+        let place = if self.here {
+            Some(current_place().context("Cannot determine the current directory")?)
+        } else {
+            None
+        };
+
+        let service = SnippetService::new(backend);
+        let recent = service.recent(self.limit, place.as_deref()).await?;
+        if let Some(format) = machine_format {
+            let records: Vec<_> = recent
+                .iter()
+                .map(|(snippet, stats)| Record::from_usage(snippet, stats))
+                .collect();
+            return self.output.print(format, &records);
+        }
+        if recent.is_empty() {
+            println!("No snippets used yet.");
+            return Ok(());
+        }
+
+        format_recent(&recent)
+    }
+}
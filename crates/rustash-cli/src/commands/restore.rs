@@ -0,0 +1,28 @@
+//! Snippet restore command
+
+use anyhow::Result;
+use clap::Args;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct RestoreCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// The version to bring back
+    pub version: u32,
+}
+
+impl RestoreCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet_uuid = service.resolve_id(&self.snippet).await?;
+
+        let snippet = service.restore_version(&snippet_uuid, self.version).await?;
+        println!(
+            "\u{2713} Restored snippet '{}' to version {}.",
+            snippet.title, self.version
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Run snippet command
+
+use super::use_snippet::{parse_variable, prompt_for};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use console::style;
+use dialoguer::Confirm;
+use rustash_core::{models::Run, storage::StorageBackend, SnippetService, DANGEROUS_TAG};
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct RunCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// Set a placeholder, e.g. `--var host=example.com`
+    #[arg(short, long, value_parser = parse_variable)]
+    pub var: Vec<(String, String)>,
+    /// Run without asking for confirmation, unless the snippet is marked dangerous
+    #[arg(short, long)]
+    pub yes: bool,
+    /// Shell to run the command with; defaults to $SHELL, or sh (cmd on Windows)
+    #[arg(long, env = "RUSTASH_SHELL")]
+    pub shell: Option<String>,
+    /// Record when and where the snippet ran, and its exit code, in the stash
+    #[arg(long)]
+    pub record: bool,
+}
+
+impl RunCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
+
+        let template = service.compose(&snippet).await?;
+        let mut variables: HashMap<String, String> = self.var.into_iter().collect();
+        let interactive = std::io::stdin().is_terminal();
+        if interactive {
+            for placeholder in template.placeholders() {
+                let missing = !variables.contains_key(&placeholder.name)
+                    && placeholder.default_value().is_none();
+                if missing {
+                    let value = prompt_for(placeholder)?;
+                    variables.insert(placeholder.name.clone(), value);
+                }
+            }
+        }
+        let command = template.render(&variables)?;
+        let (shell, flag) = find_shell(self.shell.as_deref())?;
+
+        // Everything but the command's own output goes to stderr, so the
+        // output can be piped.
+        let dangerous = snippet.tags.iter().any(|tag| tag == DANGEROUS_TAG);
+        eprintln!("{}", style(&snippet.title).bold());
+        for line in command.lines() {
+            eprintln!("  {}", style(line).cyan());
+        }
+        if dangerous {
+            eprintln!(
+                "{} This snippet is marked dangerous.",
+                style("!").red().bold()
+            );
+        }
+
+        if dangerous || !self.yes {
+            if !interactive {
+                if dangerous {
+                    bail!(
+                        "Snippet '{}' is marked dangerous and must be confirmed interactively.",
+                        snippet.title
+                    );
+                }
+                bail!("Cannot ask for confirmation without a terminal. Pass --yes to run anyway.");
+            }
+            let confirmed = Confirm::new()
+                .with_prompt("Run this command?")
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                eprintln!("Aborted.");
+                return Ok(());
+            }
+        }
+
+        service.record_use(&snippet.id).await?;
+        let mut run = Run::start(snippet.id);
+        let status = Command::new(&shell)
+            .arg(flag)
+            .arg(&command)
+            .status()
+            .with_context(|| format!("Failed to launch shell {}", shell.display()))?;
+
+        if self.record {
+            run.exit_code = status.code();
+            service.record_run(&run).await?;
+        }
+        if !status.success() {
+            std::process::exit(exit_code(&status));
+        }
+        Ok(())
+    }
+}
+
+/// Locate the shell to run commands with, and the flag that passes it a
+/// command string.
+fn find_shell(shell: Option<&str>) -> Result<(PathBuf, &'static str)> {
+    let default = if cfg!(windows) { "cmd" } else { "sh" };
+    let shell = match shell {
+        Some(shell) => shell.to_string(),
+        None => std::env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.trim().is_empty())
+            .unwrap_or_else(|| default.to_string()),
+    };
+    let path = which::which(&shell).with_context(|| format!("Shell '{}' not found", shell))?;
+
+    let name = Path::new(&shell)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let flag = match name.as_str() {
+        "cmd" => "/C",
+        "powershell" | "pwsh" => "-Command",
+        _ => "-c",
+    };
+    Ok((path, flag))
+}
+
+/// The exit code to leave with; like shells, death by signal N becomes 128 + N.
+fn exit_code(status: &ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
@@ -0,0 +1,29 @@
+//! Show snippet command
+
+use crate::utils::format_snippet;
+use anyhow::Result;
+use clap::Args;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct ShowCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// Print only the content, for piping into other tools
+    #[arg(long)]
+    pub raw: bool,
+}
+
+impl ShowCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
+
+        if self.raw {
+            println!("{}", snippet.content);
+            return Ok(());
+        }
+        format_snippet(&snippet)
+    }
+}
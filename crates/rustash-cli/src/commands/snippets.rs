@@ -1,4 +1,9 @@
-use super::{add::AddCommand, list::ListCommand, use_snippet::UseCommand};
+use super::{
+    add::AddCommand, context::ContextCommand, delete::DeleteCommand, diff::DiffCommand,
+    edit::EditCommand, export::ExportCommand, history::HistoryCommand, import::ImportCommand,
+    list::ListCommand, recent::RecentCommand, restore::RestoreCommand, run::RunCommand,
+    show::ShowCommand, trash::TrashCommand, update::UpdateCommand, use_snippet::UseCommand,
+};
 use anyhow::Result;
 use clap::{Args, Subcommand};
 use rustash_core::storage::StorageBackend;
@@ -16,8 +21,34 @@ pub enum SnippetCommands {
     Add(AddCommand),
     /// List and search snippets
     List(ListCommand),
+    /// List recently used snippets
+    Recent(RecentCommand),
+    /// Show the project detected in the current directory
+    Context(ContextCommand),
     /// Use a snippet (expand and copy to clipboard)
     Use(UseCommand),
+    /// Run a snippet as a shell command after confirmation
+    Run(RunCommand),
+    /// Show a snippet in full
+    Show(ShowCommand),
+    /// Edit a snippet in $EDITOR
+    Edit(EditCommand),
+    /// Change a snippet's title, content or tags
+    Update(UpdateCommand),
+    /// List the saved versions of a snippet
+    History(HistoryCommand),
+    /// Show the changes between two versions of a snippet
+    Diff(DiffCommand),
+    /// Restore a snippet to an earlier version
+    Restore(RestoreCommand),
+    /// Move a snippet to the trash
+    Delete(DeleteCommand),
+    /// Import snippets from pet, navi, VS Code or tldr
+    Import(ImportCommand),
+    /// Export snippets for VS Code, espanso, navi or pet
+    Export(ExportCommand),
+    /// List, restore or permanently remove deleted snippets
+    Trash(TrashCommand),
 }
 
 impl SnippetCommand {
@@ -25,7 +56,20 @@ impl SnippetCommand {
         match self.command {
             SnippetCommands::Add(cmd) => cmd.execute(backend).await,
             SnippetCommands::List(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Recent(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Context(cmd) => cmd.execute(backend).await,
             SnippetCommands::Use(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Run(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Show(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Edit(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Update(cmd) => cmd.execute(backend).await,
+            SnippetCommands::History(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Diff(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Restore(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Delete(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Import(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Export(cmd) => cmd.execute(backend).await,
+            SnippetCommands::Trash(cmd) => cmd.execute(backend).await,
         }
     }
 }
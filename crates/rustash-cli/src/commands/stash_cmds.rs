@@ -1,11 +1,18 @@
 // crates/rustash-cli/src/commands/stash_cmds.rs
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
 use rustash_core::{
-    config::{load_config, save_config, Config},
-    ServiceType, StashConfig,
+    config::{copy_checkpoint_path, load_config, save_config, sync_state_path, Config},
+    copy::{self, CopyCheckpoint, CopyOptions, DEFAULT_BATCH_SIZE},
+    dump::{self, DumpFormat, LoadMode},
+    sync::{self, ConflictKind, ConflictStrategy, SyncState},
+    ServiceType, Stash, StashConfig,
 };
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Args)]
 pub struct StashCommand {
@@ -23,6 +30,14 @@ pub enum StashCommands {
     Remove(RemoveArgs),
     /// Set the default stash
     SetDefault(SetDefaultArgs),
+    /// Write everything in a stash to a dump
+    Export(ExportArgs),
+    /// Load a dump into a stash
+    Import(ImportArgs),
+    /// Copy everything in one stash into another, e.g. from SQLite to Postgres
+    Copy(CopyArgs),
+    /// Sync two stashes in both directions, e.g. a local SQLite stash with a remote Postgres one
+    Sync(SyncArgs),
 }
 
 #[derive(Args)]
@@ -35,6 +50,9 @@ pub struct AddArgs {
     /// The database connection URL for this stash
     #[arg(long)]
     pub database_url: String,
+    /// Permanently remove deleted items after this many days in the trash
+    #[arg(long, value_name = "DAYS")]
+    pub trash_retention_days: Option<u32>,
 }
 
 #[derive(Args)]
@@ -49,6 +67,65 @@ pub struct SetDefaultArgs {
     pub name: String,
 }
 
+#[derive(Args)]
+pub struct ExportArgs {
+    /// The name of the stash to export
+    pub name: String,
+    /// How to write the dump
+    #[arg(long, value_enum, default_value_t)]
+    pub format: DumpFormat,
+    /// Write to this file instead of standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// The name of the stash to import into
+    pub name: String,
+    /// The dump to read, or `-` for standard input
+    pub path: PathBuf,
+    /// What to do with items the stash already has
+    #[arg(long, value_enum, default_value_t)]
+    pub mode: LoadMode,
+}
+
+#[derive(Args)]
+pub struct CopyArgs {
+    /// The stash to copy from
+    pub source: String,
+    /// The stash to copy into
+    pub target: String,
+    /// Items to read and write at a time
+    #[arg(long, default_value_t = DEFAULT_BATCH_SIZE)]
+    pub batch_size: usize,
+    /// What to do with items the target already has
+    #[arg(long, value_enum, default_value_t)]
+    pub mode: LoadMode,
+    /// Start over instead of resuming an interrupted copy
+    #[arg(long)]
+    pub restart: bool,
+}
+
+#[derive(Args)]
+pub struct SyncArgs {
+    /// The local stash
+    pub local: String,
+    /// The remote stash
+    pub remote: String,
+    /// How to settle items changed on both sides since the last sync
+    #[arg(long, value_enum, default_value_t)]
+    pub on_conflict: ConflictStrategy,
+}
+
+/// Open the configured stash called `name`.
+async fn open_stash(config: &Config, name: &str) -> Result<Stash> {
+    let Some(conf) = config.stashes.get(name) else {
+        bail!("Stash '{}' not found.", name);
+    };
+    Ok(Stash::new(name, conf.clone()).await?)
+}
+
 pub async fn execute_stash_command(command: StashCommands, mut config: Config) -> Result<()> {
     match command {
         StashCommands::List => {
@@ -80,6 +157,7 @@ pub async fn execute_stash_command(command: StashCommands, mut config: Config) -
             let new_config = StashConfig {
                 service_type: args.service_type,
                 database_url: args.database_url,
+                trash_retention_days: args.trash_retention_days,
             };
             config.stashes.insert(args.name.clone(), new_config);
             println!("✓ Stash '{}' added.", args.name);
@@ -110,6 +188,188 @@ pub async fn execute_stash_command(command: StashCommands, mut config: Config) -
             println!("✓ Default stash set to '{}'.", args.name);
             save_config(&config)?;
         }
+        StashCommands::Export(args) => {
+            let stash = open_stash(&config, &args.name).await?;
+            let records = dump::dump(stash.backend.as_ref().as_ref()).await?;
+            let text = dump::write(&records, args.format)?;
+            match &args.output {
+                Some(path) => {
+                    std::fs::write(path, text)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    println!("✓ Exported stash '{}' to {}.", args.name, path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+        StashCommands::Import(args) => {
+            let stash = open_stash(&config, &args.name).await?;
+            let text = if args.path.as_os_str() == "-" {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .context("Failed to read standard input")?;
+                text
+            } else {
+                std::fs::read_to_string(&args.path)
+                    .with_context(|| format!("Failed to read {}", args.path.display()))?
+            };
+            let records = dump::read(&text)?;
+            let summary = dump::load(stash.backend.as_ref().as_ref(), records, args.mode).await?;
+            println!(
+                "✓ Imported into '{}': {} added, {} updated, {} unchanged; {} relations.",
+                args.name, summary.added, summary.updated, summary.unchanged, summary.relations
+            );
+            if summary.skipped_relations > 0 {
+                println!(
+                    "! Skipped {} relations to items missing from the stash.",
+                    summary.skipped_relations
+                );
+            }
+        }
+        StashCommands::Copy(args) => {
+            if args.source == args.target {
+                bail!("Cannot copy stash '{}' into itself.", args.source);
+            }
+            let source = open_stash(&config, &args.source).await?;
+            let target = open_stash(&config, &args.target).await?;
+
+            let checkpoint_path = copy_checkpoint_path(&args.source, &args.target)?;
+            let checkpoint: CopyCheckpoint = if !args.restart && checkpoint_path.exists() {
+                let text = std::fs::read_to_string(&checkpoint_path)?;
+                let checkpoint: CopyCheckpoint = serde_json::from_str(&text)
+                    .with_context(|| format!("Invalid checkpoint {}", checkpoint_path.display()))?;
+                let summary = &checkpoint.summary;
+                println!(
+                    "! Resuming an interrupted copy after {} items (use --restart to start over).",
+                    summary.added + summary.updated + summary.unchanged
+                );
+                checkpoint
+            } else {
+                std::fs::remove_file(&checkpoint_path).ok();
+                CopyCheckpoint::default()
+            };
+            if let Some(parent) = checkpoint_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let progress = ProgressBar::new_spinner();
+            progress.set_style(
+                ProgressStyle::with_template("{spinner} {pos} items copied [{elapsed}]")
+                    .expect("valid progress template"),
+            );
+            progress.enable_steady_tick(Duration::from_millis(100));
+            let options = CopyOptions {
+                batch_size: args.batch_size,
+                mode: args.mode,
+            };
+            let result = copy::copy(
+                source.backend.as_ref().as_ref(),
+                target.backend.as_ref().as_ref(),
+                options,
+                checkpoint,
+                |checkpoint| {
+                    let summary = &checkpoint.summary;
+                    progress
+                        .set_position((summary.added + summary.updated + summary.unchanged) as u64);
+                    std::fs::write(&checkpoint_path, serde_json::to_string(checkpoint)?)?;
+                    Ok(())
+                },
+            )
+            .await;
+            progress.finish_and_clear();
+            let summary = match result {
+                Ok(summary) => summary,
+                Err(e) if checkpoint_path.exists() => {
+                    return Err(anyhow::Error::new(e)
+                        .context("The copy stopped; run the same command to resume it"))
+                }
+                Err(e) => return Err(e.into()),
+            };
+            std::fs::remove_file(&checkpoint_path).ok();
+
+            println!(
+                "✓ Copied '{}' into '{}': {} added, {} updated, {} unchanged; {} relations.",
+                args.source,
+                args.target,
+                summary.added,
+                summary.updated,
+                summary.unchanged,
+                summary.relations
+            );
+            if summary.skipped_relations > 0 {
+                println!(
+                    "! Skipped {} relations to items missing from '{}'.",
+                    summary.skipped_relations, args.target
+                );
+            }
+        }
+        StashCommands::Sync(args) => {
+            if args.local == args.remote {
+                bail!("Cannot sync stash '{}' with itself.", args.local);
+            }
+            let local = open_stash(&config, &args.local).await?;
+            let remote = open_stash(&config, &args.remote).await?;
+
+            let state_path = sync_state_path(&args.local, &args.remote)?;
+            let mut state: SyncState = if state_path.exists() {
+                let text = std::fs::read_to_string(&state_path)?;
+                serde_json::from_str(&text)
+                    .with_context(|| format!("Invalid sync state {}", state_path.display()))?
+            } else {
+                SyncState::default()
+            };
+            let report = sync::sync(
+                local.backend.as_ref().as_ref(),
+                remote.backend.as_ref().as_ref(),
+                &mut state,
+                args.on_conflict,
+            )
+            .await?;
+            if let Some(parent) = state_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&state_path, serde_json::to_string(&state)?)
+                .with_context(|| format!("Failed to write {}", state_path.display()))?;
+
+            println!(
+                "✓ Synced '{}' with '{}': {} pulled, {} pushed, {} deleted locally, {} deleted remotely; {} relations.",
+                args.local,
+                args.remote,
+                report.pulled,
+                report.pushed,
+                report.deleted_local,
+                report.deleted_remote,
+                report.relations
+            );
+            if !report.conflicts.is_empty() {
+                println!("! {} conflicts:", report.conflicts.len());
+                for conflict in &report.conflicts {
+                    let kind = match conflict.kind {
+                        ConflictKind::BothChanged => "changed on both sides".to_string(),
+                        ConflictKind::ChangedAndDeleted(side) => {
+                            format!("changed on one side, deleted on the {}", side)
+                        }
+                    };
+                    let outcome = match conflict.kept {
+                        Some(side) => format!("kept the {} version", side),
+                        None => "left as is".to_string(),
+                    };
+                    println!(
+                        "  - {} ({}): {}; {}",
+                        conflict.title, conflict.id, kind, outcome
+                    );
+                }
+                if report
+                    .conflicts
+                    .iter()
+                    .any(|conflict| conflict.kept.is_none())
+                {
+                    println!(
+                        "Settle them by making both sides match, or run again with --on-conflict newest, local or remote."
+                    );
+                }
+            }
+        }
     }
     Ok(())
 }
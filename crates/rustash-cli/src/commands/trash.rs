@@ -0,0 +1,92 @@
+//! Snippet trash commands
+
+use crate::output::{OutputArgs, Record};
+use crate::utils::format_trash;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use dialoguer::Confirm;
+use rustash_core::{storage::StorageBackend, SnippetService};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Args)]
+pub struct TrashCommand {
+    #[command(subcommand)]
+    pub command: TrashSubcommand,
+}
+
+#[derive(Subcommand)]
+pub enum TrashSubcommand {
+    /// List deleted snippets
+    List {
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Take a deleted snippet back out of the trash
+    Restore {
+        /// UUID of the snippet
+        uuid: String,
+    },
+    /// Permanently remove deleted snippets
+    Empty {
+        /// Only remove snippets deleted more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+        /// Empty the trash without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+impl TrashCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+
+        match self.command {
+            TrashSubcommand::List { output } => {
+                let machine_format = output.machine_format(&["table"])?;
+                let trashed = service.trash().await?;
+                if let Some(format) = machine_format {
+                    let records: Vec<_> = trashed.iter().map(Record::from_trashed).collect();
+                    return output.print(format, &records);
+                }
+                if trashed.is_empty() {
+                    println!("The trash is empty.");
+                    return Ok(());
+                }
+                format_trash(&trashed)?;
+            }
+            TrashSubcommand::Restore { uuid } => {
+                let snippet_uuid = uuid.parse::<Uuid>().context("Invalid UUID format")?;
+                let snippet = service.restore_snippet(&snippet_uuid).await?;
+                println!(
+                    "\u{2713} Restored snippet '{}' from the trash.",
+                    snippet.title
+                );
+            }
+            TrashSubcommand::Empty { older_than, yes } => {
+                if !yes {
+                    let prompt = match older_than {
+                        Some(days) => format!(
+                            "Permanently remove snippets deleted more than {} days ago?",
+                            days
+                        ),
+                        None => "Permanently remove every snippet in the trash?".to_string(),
+                    };
+                    let confirmed = Confirm::new()
+                        .with_prompt(prompt)
+                        .default(false)
+                        .interact()?;
+                    if !confirmed {
+                        println!("Aborted.");
+                        return Ok(());
+                    }
+                }
+
+                let purged = service.empty_trash(older_than).await?;
+                println!("\u{2713} Permanently removed {} item(s).", purged);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+//! Update snippet command
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use rustash_core::{alias_tag, models::UpdateSnippet, storage::StorageBackend, SnippetService};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Args)]
+pub struct UpdateCommand {
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
+    /// New title
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Replace the content with the contents of this file (`-` reads stdin)
+    #[arg(long, value_name = "PATH")]
+    pub content_file: Option<PathBuf>,
+    /// Add a tag; repeat or separate with commas to add several
+    #[arg(long, value_delimiter = ',')]
+    pub add_tag: Vec<String>,
+    /// Remove a tag; repeat or separate with commas to remove several
+    #[arg(long, value_delimiter = ',')]
+    pub remove_tag: Vec<String>,
+    /// Add an alias the snippet can be referred to by; repeat or separate with commas
+    #[arg(long, value_delimiter = ',')]
+    pub alias: Vec<String>,
+    /// Remove an alias; repeat or separate with commas to remove several
+    #[arg(long, value_delimiter = ',')]
+    pub remove_alias: Vec<String>,
+}
+
+impl UpdateCommand {
+    pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
+
+        let mut update = UpdateSnippet::new();
+        if let Some(title) = self.title {
+            update = update.with_title(title);
+        }
+        if let Some(path) = &self.content_file {
+            update = update.with_content(read_content(path)?);
+        }
+
+        let mut add_tags = self.add_tag;
+        let mut remove_tags = self.remove_tag;
+        for alias in &self.alias {
+            add_tags.push(alias_tag(alias)?);
+        }
+        for alias in &self.remove_alias {
+            remove_tags.push(alias_tag(alias)?);
+        }
+        if !add_tags.is_empty() || !remove_tags.is_empty() {
+            let mut tags = snippet.tags.clone();
+            tags.retain(|tag| !remove_tags.contains(tag));
+            for tag in add_tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            update = update.with_tags(tags);
+        }
+        if update.is_empty() {
+            bail!(
+                "Nothing to update. Pass --title, --content-file, --add-tag, --remove-tag, \
+                 --alias or --remove-alias."
+            );
+        }
+
+        let snippet = service.update_snippet(&snippet.id, &update).await?;
+        println!("\u{2713} Updated snippet '{}'.", snippet.title);
+        Ok(())
+    }
+}
+
+/// Read new content from `path`, or from stdin when it is `-`.
+fn read_content(path: &Path) -> Result<String> {
+    let content = if path.as_os_str() == "-" {
+        use std::io::Read;
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    Ok(content.trim_end().to_string())
+}
@@ -1,19 +1,18 @@
 //! Use snippet command
 
 use crate::utils::copy_to_clipboard;
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::Args;
-use dialoguer::Input;
-use regex::Regex;
-use rustash_core::{expand_placeholders, models::SnippetWithTags, storage::StorageBackend};
+use dialoguer::{Confirm, Input, Select};
+use rustash_core::{storage::StorageBackend, Placeholder, SnippetService, ValueKind};
 use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::sync::Arc;
-use uuid::Uuid;
 
 #[derive(Args)]
 pub struct UseCommand {
-    /// UUID of the snippet to use
-    pub uuid: String,
+    /// UUID, UUID prefix, title or alias of the snippet
+    pub snippet: String,
     #[arg(short, long, value_parser = parse_variable)]
     pub var: Vec<(String, String)>,
     #[arg(short, long, default_value = "true")]
@@ -26,33 +25,46 @@ pub struct UseCommand {
 
 impl UseCommand {
     pub async fn execute(self, backend: Arc<Box<dyn StorageBackend>>) -> Result<()> {
-        let snippet_uuid = self.uuid.parse::<Uuid>().context("Invalid UUID format")?;
-
-        let snippet_dyn = backend
-            .get(&snippet_uuid)
-            .await?
-            .context("Snippet not found")?;
-        let snippet = snippet_dyn
-            .as_any()
-            .downcast_ref::<SnippetWithTags>()
-            .context("Internal error: Could not downcast to SnippetWithTags")?
-            .clone();
+        let service = SnippetService::new(backend);
+        let snippet = service.resolve(&self.snippet).await?;
 
+        let template = service.compose(&snippet).await?;
         let mut variables: HashMap<String, String> = self.var.into_iter().collect();
-        let placeholders = extract_placeholders(&snippet.content);
 
-        if self.interactive {
-            for placeholder in &placeholders {
-                if !variables.contains_key(placeholder) {
-                    let value: String = Input::new()
-                        .with_prompt(format!("Enter value for '{}'", placeholder))
-                        .interact_text()?;
-                    variables.insert(placeholder.clone(), value);
+        // `--interactive` asks for every value not given; on a terminal,
+        // values without a default are asked for anyway, as `run` does.
+        if self.interactive || std::io::stdin().is_terminal() {
+            for placeholder in template.placeholders() {
+                let missing = !variables.contains_key(&placeholder.name)
+                    && (self.interactive || placeholder.default_value().is_none());
+                if missing {
+                    let value = prompt_for(placeholder)?;
+                    variables.insert(placeholder.name.clone(), value);
                 }
             }
         }
 
-        let expanded_content = expand_placeholders(&snippet.content, &variables);
+        for placeholder in template.placeholders() {
+            if let Some(value) = variables.get_mut(&placeholder.name) {
+                *value = placeholder.validate(value)?;
+            }
+        }
+        // Without a value for every placeholder the snippet is still used,
+        // with the missing ones left as written and reported.
+        let (expanded_content, missing) = template.expand_with_missing(&variables);
+        let expanded_content = if missing.is_empty() {
+            template.render(&variables)?
+        } else {
+            eprintln!(
+                "No value given for {}; pass them with --var name=value",
+                missing
+                    .iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            expanded_content
+        };
 
         if self.print_only {
             println!("{}", expanded_content);
@@ -61,13 +73,13 @@ impl UseCommand {
             copy_to_clipboard(&expanded_content)?;
             println!("\n\u{2713} Copied to clipboard");
         }
+        service.record_use(&snippet.id).await?;
 
         Ok(())
     }
 }
 
-// Helper functions (parse_variable, extract_placeholders) remain the same
-fn parse_variable(s: &str) -> Result<(String, String), String> {
+pub(crate) fn parse_variable(s: &str) -> Result<(String, String), String> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 {
         return Err(format!("Invalid variable format '{}'. Use key=value", s));
@@ -75,13 +87,38 @@ fn parse_variable(s: &str) -> Result<(String, String), String> {
     Ok((parts[0].to_string(), parts[1].to_string()))
 }
 
-fn extract_placeholders(content: &str) -> Vec<String> {
-    let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
-    let mut placeholders: Vec<String> = re
-        .captures_iter(content)
-        .map(|cap| cap[1].to_string())
-        .collect();
-    placeholders.sort();
-    placeholders.dedup();
-    placeholders
+/// Ask for a placeholder's value, offering its default and choices.
+pub(crate) fn prompt_for(placeholder: &Placeholder) -> Result<String> {
+    let prompt = format!("Enter value for '{}'", placeholder.name);
+    let default = placeholder.default_value();
+
+    if !placeholder.choices.is_empty() {
+        let selected = default
+            .and_then(|default| placeholder.choices.iter().position(|c| *c == default))
+            .unwrap_or(0);
+        let index = Select::new()
+            .with_prompt(prompt)
+            .items(&placeholder.choices)
+            .default(selected)
+            .interact()?;
+        return Ok(placeholder.choices[index].clone());
+    }
+
+    if placeholder.kind == ValueKind::Bool {
+        let default = default.and_then(|default| ValueKind::Bool.validate(&default).ok());
+        let value = Confirm::new()
+            .with_prompt(prompt)
+            .default(default.as_deref() == Some("true"))
+            .interact()?;
+        return Ok(value.to_string());
+    }
+
+    let mut input = Input::<String>::new().with_prompt(prompt);
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    let value = input
+        .validate_with(|value: &String| placeholder.validate(value).map(|_| ()))
+        .interact_text()?;
+    Ok(value)
 }
@@ -4,6 +4,7 @@ mod commands;
 mod fuzzy;
 #[cfg(feature = "gui")]
 mod gui;
+mod output;
 mod utils;
 
 use anyhow::{bail, Context, Result};
@@ -56,7 +57,7 @@ async fn main() -> Result<()> {
         return commands::stash_cmds::execute_stash_command(cmd.command, config).await;
     }
 
-    let stash_name = cli.stash.or(config.default_stash).context(
+    let stash_name = cli.stash.or(config.default_stash.clone()).context(
         "No stash specified and no default_stash is set. Use `rustash stash list` to see options.",
     )?;
 
@@ -0,0 +1,338 @@
+//! Machine-readable output for listing commands
+//!
+//! Every listing command turns its results into [`Record`]s, which keep a
+//! fixed set of fields in a fixed order so scripts can rely on them:
+//!
+//! - items (snippets, graph neighbours, RAG hits, trash entries): `id`,
+//!   `type`, `title`, `tags`, `content`, `created_at`, `updated_at`, plus
+//!   `distance` for RAG hits, `deleted_at` for trash entries and `uses` and
+//!   `last_used_at` for recently used snippets
+//! - versions: `id`, `version`, `title`, `tags`, `content`, `author`,
+//!   `created_at`
+//!
+//! Timestamps are RFC 3339 strings in UTC.
+
+use anyhow::{bail, Result};
+use clap::Args;
+use rustash_core::{history::Version, models::TrashedItem, MemoryItem, UsageStats};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Output options shared by every listing command
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Output format: one of the command's views, or json, ndjson, yaml, csv or ids
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Fields to include in machine-readable output, e.g. `id,title,tags`
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+}
+
+impl OutputArgs {
+    /// Resolve `--format` against the human-readable `views` a command
+    /// offers, the first of which is its default.
+    ///
+    /// Returns `Ok(None)` when a human-readable view was picked; see
+    /// [`view`](Self::view) for which one.
+    pub fn machine_format(&self, views: &[&str]) -> Result<Option<Format>> {
+        match self.format.as_deref() {
+            None => Ok(None),
+            Some(view) if views.contains(&view) => Ok(None),
+            Some(format) => format.parse().map(Some).map_err(|_| {
+                anyhow::anyhow!(
+                    "Unknown format '{}'. Use: {}, {}",
+                    format,
+                    views.join(", "),
+                    Format::NAMES.join(", ")
+                )
+            }),
+        }
+    }
+
+    /// The human-readable view to show, falling back to the first of `views`.
+    pub fn view<'a>(&'a self, views: &[&'a str]) -> &'a str {
+        self.format.as_deref().unwrap_or(views[0])
+    }
+
+    /// Write `records` to stdout in `format`, keeping only the `--fields`
+    /// that were asked for.
+    pub fn print(&self, format: Format, records: &[Record]) -> Result<()> {
+        let mut out = std::io::stdout().lock();
+        if format == Format::Ids {
+            return format.write(&mut out, records);
+        }
+
+        let records = records
+            .iter()
+            .map(|record| record.select(&self.fields))
+            .collect::<Result<Vec<_>>>()?;
+        format.write(&mut out, &records)
+    }
+}
+
+/// A machine-readable output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// A YAML sequence of mappings
+    Yaml,
+    /// RFC 4180 CSV with a header row
+    Csv,
+    /// Only the ids, one per line
+    Ids,
+}
+
+impl Format {
+    const NAMES: [&'static str; 5] = ["json", "ndjson", "yaml", "csv", "ids"];
+
+    fn write(self, out: &mut impl Write, records: &[Record]) -> Result<()> {
+        match self {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *out, records)?;
+                writeln!(out)?;
+            }
+            Format::Ndjson => {
+                for record in records {
+                    serde_json::to_writer(&mut *out, record)?;
+                    writeln!(out)?;
+                }
+            }
+            Format::Yaml => write_yaml(out, records)?,
+            Format::Csv => write_csv(out, records)?,
+            Format::Ids => {
+                for record in records {
+                    if let Some(Value::String(id)) = record.get("id") {
+                        writeln!(out, "{}", id)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(name: &str) -> std::result::Result<Self, ()> {
+        match name {
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "yaml" => Ok(Format::Yaml),
+            "csv" => Ok(Format::Csv),
+            "ids" => Ok(Format::Ids),
+            _ => Err(()),
+        }
+    }
+}
+
+/// One result, as named fields in a stable order
+#[derive(Debug, Clone)]
+pub struct Record(Vec<(&'static str, Value)>);
+
+impl Record {
+    /// Describe a stored item
+    pub fn from_item(item: &dyn MemoryItem) -> Self {
+        let metadata = item.metadata();
+        let title = metadata.get("title").cloned().unwrap_or(Value::Null);
+        let tags = match metadata.get("tags") {
+            Some(tags @ Value::Array(_)) => tags.clone(),
+            _ => Value::Array(Vec::new()),
+        };
+        Self(vec![
+            ("id", Value::String(item.id().to_string())),
+            ("type", Value::String(item.item_type().to_string())),
+            ("title", title),
+            ("tags", tags),
+            ("content", Value::String(item.content().to_string())),
+            ("created_at", timestamp(item.created_at())),
+            ("updated_at", timestamp(item.updated_at())),
+        ])
+    }
+
+    /// Describe an item in the trash
+    pub fn from_trashed(entry: &TrashedItem) -> Self {
+        Self::from_item(entry.item.as_ref()).with("deleted_at", timestamp(entry.deleted_at))
+    }
+
+    /// Describe a used item along with its usage
+    pub fn from_usage(item: &dyn MemoryItem, stats: &UsageStats) -> Self {
+        Self::from_item(item)
+            .with("uses", stats.count)
+            .with("last_used_at", timestamp(stats.last_used_at))
+    }
+
+    /// Describe one saved version of an item
+    pub fn from_version(version: &Version) -> Self {
+        Self(vec![
+            ("id", Value::String(version.item_id.to_string())),
+            ("version", Value::from(version.number)),
+            ("title", Value::String(version.title.clone())),
+            ("tags", Value::from(version.tags.clone())),
+            ("content", Value::String(version.content.clone())),
+            ("author", Value::from(version.author.clone())),
+            ("created_at", timestamp(version.created_at)),
+        ])
+    }
+
+    /// Append a field
+    pub fn with(mut self, name: &'static str, value: impl Into<Value>) -> Self {
+        self.0.push((name, value.into()));
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Keep only `fields`, in the order given; all fields when empty.
+    fn select(&self, fields: &[String]) -> Result<Record> {
+        if fields.is_empty() {
+            return Ok(self.clone());
+        }
+        let mut selected = Vec::with_capacity(fields.len());
+        for name in fields {
+            match self.0.iter().find(|(field, _)| field == name) {
+                Some(entry) => selected.push(entry.clone()),
+                None => {
+                    let available: Vec<_> = self.0.iter().map(|(field, _)| *field).collect();
+                    bail!(
+                        "Unknown field '{}'. Available fields: {}",
+                        name,
+                        available.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(Record(selected))
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Timestamps serialize as RFC 3339 strings.
+fn timestamp(at: impl Serialize) -> Value {
+    serde_json::to_value(at).unwrap_or(Value::Null)
+}
+
+/// YAML 1.2 is a superset of JSON, so every value is written as a JSON
+/// scalar or flow sequence, which sidesteps YAML's quoting rules.
+fn write_yaml(out: &mut impl Write, records: &[Record]) -> Result<()> {
+    if records.is_empty() {
+        writeln!(out, "[]")?;
+    }
+    for record in records {
+        for (i, (name, value)) in record.0.iter().enumerate() {
+            let indent = if i == 0 { "- " } else { "  " };
+            writeln!(out, "{}{}: {}", indent, name, serde_json::to_string(value)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_csv(out: &mut impl Write, records: &[Record]) -> Result<()> {
+    let Some(first) = records.first() else {
+        return Ok(());
+    };
+    let header: Vec<String> = first.0.iter().map(|(name, _)| csv_cell(name)).collect();
+    writeln!(out, "{}", header.join(","))?;
+
+    for record in records {
+        let row: Vec<String> = record
+            .0
+            .iter()
+            .map(|(_, value)| csv_cell(&csv_text(value)))
+            .collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Flatten a value into one CSV cell; lists such as tags are comma-separated.
+fn csv_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(csv_text).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustash_core::SnippetWithTags;
+    use uuid::Uuid;
+
+    fn render(format: Format, records: &[Record]) -> String {
+        let mut out = Vec::new();
+        format.write(&mut out, records).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn record() -> Record {
+        let snippet = SnippetWithTags::with_uuid(
+            Uuid::nil(),
+            "Say \"hi\", twice".to_string(),
+            "echo hi\necho hi".to_string(),
+            vec!["shell".to_string(), "demo".to_string()],
+        );
+        Record::from_item(&snippet)
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let fields = vec!["title".to_string(), "id".to_string()];
+        let selected = record().select(&fields).unwrap();
+        let json = serde_json::to_string(&selected).unwrap();
+        assert_eq!(
+            json,
+            r#"{"title":"Say \"hi\", twice","id":"00000000-0000-0000-0000-000000000000"}"#
+        );
+
+        let error = record().select(&["nope".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("Available fields: id, type"));
+    }
+
+    #[test]
+    fn test_csv_and_yaml() {
+        let fields = vec!["title".to_string(), "tags".to_string()];
+        let records = vec![record().select(&fields).unwrap()];
+
+        assert_eq!(
+            render(Format::Csv, &records),
+            "title,tags\n\"Say \"\"hi\"\", twice\",\"shell,demo\"\n"
+        );
+        assert_eq!(
+            render(Format::Yaml, &records),
+            "- title: \"Say \\\"hi\\\", twice\"\n  tags: [\"shell\",\"demo\"]\n"
+        );
+        assert_eq!(render(Format::Yaml, &[]), "[]\n");
+        assert_eq!(render(Format::Csv, &[]), "");
+    }
+}
@@ -3,7 +3,11 @@
 use anyhow::Result;
 use arboard::Clipboard;
 use console::{style, Term};
-use rustash_core::models::SnippetWithTags;
+use rustash_core::{
+    history::{DiffLine, Version, VersionDiff},
+    models::{SnippetWithTags, TrashedItem},
+    UsageStats,
+};
 use std::io::Write;
 
 /// Copy text to clipboard
@@ -24,10 +28,7 @@ pub fn format_snippet_list(snippets: &[SnippetWithTags], format: &str) -> Result
         "table" => format_table(snippets),
         "compact" => format_compact(snippets),
         "detailed" => format_detailed(snippets),
-        _ => anyhow::bail!(
-            "Unknown format '{}'. Use: table, compact, detailed, json, ids",
-            format
-        ),
+        _ => anyhow::bail!("Unknown format '{}'. Use: table, compact, detailed", format),
     }
 }
 
@@ -109,31 +110,7 @@ fn format_detailed(snippets: &[SnippetWithTags]) -> Result<()> {
             writeln!(term, "{}", "─".repeat(80))?;
         }
 
-        writeln!(term, "{}: {}", style("ID").bold(), style(&snippet.uuid).green())?;
-        writeln!(term, "{}: {}", style("Title").bold(), snippet.title)?;
-
-        if !snippet.tags.is_empty() {
-            writeln!(
-                term,
-                "{}: {}",
-                style("Tags").bold(),
-                style(snippet.tags.join(", ")).yellow()
-            )?;
-        }
-
-        writeln!(
-            term,
-            "{}: {}",
-            style("Created").bold(),
-            snippet.created_at.format("%Y-%m-%d %H:%M:%S").to_string()
-        )?;
-        writeln!(
-            term,
-            "{}: {}",
-            style("Updated").bold(),
-            snippet.updated_at.format("%Y-%m-%d %H:%M:%S").to_string()
-        )?;
-
+        write_snippet_header(&mut term, snippet)?;
         writeln!(term, "{}:", style("Content").bold())?;
 
         // Display content with proper indentation
@@ -155,3 +132,189 @@ fn format_detailed(snippets: &[SnippetWithTags]) -> Result<()> {
 
     Ok(())
 }
+
+/// Display one snippet in full
+pub fn format_snippet(snippet: &SnippetWithTags) -> Result<()> {
+    let mut term = Term::stdout();
+
+    write_snippet_header(&mut term, snippet)?;
+    writeln!(term, "{}:", style("Content").bold())?;
+    for line in snippet.content.lines() {
+        writeln!(term, "  {}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Write the ID, title, tags and timestamps of a snippet
+fn write_snippet_header(term: &mut Term, snippet: &SnippetWithTags) -> Result<()> {
+    writeln!(
+        term,
+        "{}: {}",
+        style("ID").bold(),
+        style(&snippet.uuid).green()
+    )?;
+    writeln!(term, "{}: {}", style("Title").bold(), snippet.title)?;
+
+    if !snippet.tags.is_empty() {
+        writeln!(
+            term,
+            "{}: {}",
+            style("Tags").bold(),
+            style(snippet.tags.join(", ")).yellow()
+        )?;
+    }
+
+    writeln!(
+        term,
+        "{}: {}",
+        style("Created").bold(),
+        snippet.created_at.format("%Y-%m-%d %H:%M:%S")
+    )?;
+    writeln!(
+        term,
+        "{}: {}",
+        style("Updated").bold(),
+        snippet.updated_at.format("%Y-%m-%d %H:%M:%S")
+    )?;
+
+    Ok(())
+}
+
+/// Display the versions of a snippet, newest first
+pub fn format_version_history(versions: &[Version]) -> Result<()> {
+    let mut term = Term::stdout();
+
+    writeln!(
+        term,
+        "{:<8} {:<17} {:<16} {}",
+        style("Version").bold().cyan(),
+        style("Saved").bold().cyan(),
+        style("Author").bold().cyan(),
+        style("Title").bold().cyan()
+    )?;
+    writeln!(term, "{}", "─".repeat(80))?;
+
+    let latest = versions.iter().map(|version| version.number).max();
+    for version in versions.iter().rev() {
+        let marker = if Some(version.number) == latest {
+            style(" (current)").green().to_string()
+        } else {
+            String::new()
+        };
+
+        writeln!(
+            term,
+            "{:<8} {:<17} {:<16} {}{}",
+            format!("v{}", version.number),
+            style(version.created_at.format("%Y-%m-%d %H:%M")).dim(),
+            version.author.as_deref().unwrap_or("-"),
+            version.title,
+            marker
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Display the changes between two versions of a snippet
+pub fn format_version_diff(diff: &VersionDiff) -> Result<()> {
+    let mut term = Term::stdout();
+
+    writeln!(
+        term,
+        "{}",
+        style(format!("--- v{}", diff.from)).red().bold()
+    )?;
+    writeln!(
+        term,
+        "{}",
+        style(format!("+++ v{}", diff.to)).green().bold()
+    )?;
+
+    if let Some((old, new)) = &diff.title {
+        writeln!(term, "{}: {} -> {}", style("Title").bold(), old, new)?;
+    }
+    if !diff.added_tags.is_empty() || !diff.removed_tags.is_empty() {
+        let changes: Vec<String> = diff
+            .removed_tags
+            .iter()
+            .map(|tag| style(format!("-{}", tag)).red().to_string())
+            .chain(
+                diff.added_tags
+                    .iter()
+                    .map(|tag| style(format!("+{}", tag)).green().to_string()),
+            )
+            .collect();
+        writeln!(term, "{}: {}", style("Tags").bold(), changes.join(" "))?;
+    }
+
+    writeln!(term, "{}:", style("Content").bold())?;
+    for line in &diff.content {
+        match line {
+            DiffLine::Unchanged(text) => writeln!(term, "  {}", text)?,
+            DiffLine::Removed(text) => writeln!(term, "{}", style(format!("- {}", text)).red())?,
+            DiffLine::Added(text) => writeln!(term, "{}", style(format!("+ {}", text)).green())?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Display the contents of the trash, most recently deleted first
+pub fn format_trash(trashed: &[TrashedItem]) -> Result<()> {
+    let mut term = Term::stdout();
+
+    writeln!(
+        term,
+        "{:<36} {:<17} {}",
+        style("ID").bold().cyan(),
+        style("Deleted").bold().cyan(),
+        style("Title").bold().cyan()
+    )?;
+    writeln!(term, "{}", "─".repeat(80))?;
+
+    for entry in trashed {
+        let title = match entry.item.as_any().downcast_ref::<SnippetWithTags>() {
+            Some(snippet) => snippet.title.clone(),
+            None => format!("[{}]", entry.item.item_type()),
+        };
+        writeln!(
+            term,
+            "{:<36} {:<17} {}",
+            entry.item.id(),
+            style(entry.deleted_at.format("%Y-%m-%d %H:%M")).dim(),
+            title
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Display recently used snippets, most recent first
+pub fn format_recent(recent: &[(SnippetWithTags, UsageStats)]) -> Result<()> {
+    let mut term = Term::stdout();
+
+    writeln!(
+        term,
+        "{:<8} {:<17} {:<6} {}",
+        style("ID").bold().cyan(),
+        style("Last used").bold().cyan(),
+        style("Uses").bold().cyan(),
+        style("Title").bold().cyan()
+    )?;
+    writeln!(term, "{}", "─".repeat(80))?;
+
+    for (snippet, stats) in recent {
+        writeln!(
+            term,
+            "{:<8} {:<17} {:<6} {}",
+            &snippet.uuid[..8],
+            style(stats.last_used_at.format("%Y-%m-%d %H:%M")).dim(),
+            stats.count,
+            snippet.title
+        )?;
+    }
+
+    Ok(())
+}
@@ -122,6 +122,49 @@ fn test_snippets_add_and_list() {
         .stdout(predicate::str::contains("rust, cli"));
 }
 
+#[test]
+fn test_snippets_use_reports_missing_variables() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("snippets.db");
+    let db_url = format!("sqlite://{}", db_path.to_str().unwrap());
+
+    rustash_cmd()
+        .env("HOME", dir.path())
+        .args([
+            "stash",
+            "add",
+            "default",
+            "--service-type",
+            "snippet",
+            "--database-url",
+            &db_url,
+        ])
+        .assert()
+        .success();
+    rustash_cmd()
+        .env("HOME", dir.path())
+        .args([
+            "snippets",
+            "add",
+            "--title",
+            "Connect",
+            "--content",
+            "ssh {{user}}@{{host}} -p {{port:22}}",
+        ])
+        .assert()
+        .success();
+
+    // stdin is not a terminal here, so nothing is prompted for
+    rustash_cmd()
+        .env("HOME", dir.path())
+        .args(["snippets", "use", "Connect", "--print-only"])
+        .args(["--var", "user=root"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ssh root@{{host}} -p 22"))
+        .stderr(predicate::str::contains("No value given for 'host'"));
+}
+
 #[test]
 fn test_cli_version() {
     let mut cmd = rustash_cmd();
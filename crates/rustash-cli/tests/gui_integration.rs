@@ -1,3 +1,4 @@
+#![cfg(feature = "gui")]
 //! Integration tests for the GUI functionality using egui_test
 
 use anyhow::Result;
@@ -11,4 +11,4 @@ CREATE TABLE IF NOT EXISTS vss_snippets (
 -- For PostgreSQL: Initialize the graph for AGE.
 -- This is a good place to ensure the graph exists.
 -- This statement will fail harmlessly on SQLite and be ignored.
-SELECT create_graph('rustash_graph');
+
@@ -0,0 +1,7 @@
+-- Store any MemoryItem type in the snippets table.
+-- `item_type` selects the codec used to rebuild the concrete type and
+-- `metadata` holds whatever type-specific state that codec serialized.
+ALTER TABLE snippets ADD COLUMN item_type TEXT NOT NULL DEFAULT 'snippet';
+ALTER TABLE snippets ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_snippets_item_type ON snippets(item_type);
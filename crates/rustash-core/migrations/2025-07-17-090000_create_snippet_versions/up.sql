@@ -0,0 +1,17 @@
+-- Every saved state of an item, numbered from 1 per item.
+-- Backends append a row whenever a save creates an item or changes its
+-- title, content or tags.
+CREATE TABLE snippet_versions (
+    snippet_uuid TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    tags TEXT NOT NULL DEFAULT '[]', -- JSON array of tags
+    author TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (snippet_uuid, version)
+);
+
+-- Existing items start their history at their current state.
+INSERT INTO snippet_versions (snippet_uuid, version, title, content, tags, created_at)
+SELECT uuid, 1, title, content, tags, updated_at FROM snippets;
@@ -0,0 +1,6 @@
+-- Deleting an item moves it to the trash by setting `deleted_at`.
+-- Trashed items are hidden from reads until restored or purged, and the
+-- tombstone gives replication something to propagate.
+ALTER TABLE snippets ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX idx_snippets_deleted_at ON snippets(deleted_at);
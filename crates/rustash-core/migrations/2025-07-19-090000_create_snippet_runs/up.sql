@@ -0,0 +1,9 @@
+-- Executions of an item through `snippets run --record`.
+-- Purging an item drops its runs along with its history.
+CREATE TABLE snippet_runs (
+    snippet_uuid TEXT NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    cwd TEXT,
+    exit_code INTEGER, -- NULL when the process was killed by a signal
+    PRIMARY KEY (snippet_uuid, started_at)
+);
@@ -0,0 +1,10 @@
+-- One row per use of an item by `snippets use` or `snippets run`, which
+-- feeds the frecency sort and `snippets recent`.
+-- Purging an item drops its uses along with its history.
+CREATE TABLE snippet_usage (
+    snippet_uuid TEXT NOT NULL,
+    used_at TIMESTAMP NOT NULL,
+    cwd TEXT,
+    repo TEXT, -- root of the git repository containing cwd
+    PRIMARY KEY (snippet_uuid, used_at)
+);
@@ -0,0 +1,17 @@
+-- Put back the triggers from fix_fts_table
+DROP TRIGGER IF EXISTS snippets_au;
+DROP TRIGGER IF EXISTS snippets_ad;
+
+CREATE TRIGGER snippets_au AFTER UPDATE ON snippets
+BEGIN
+    UPDATE snippets_fts
+    SET title = new.title,
+        content = new.content,
+        tags = new.tags
+    WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER snippets_ad AFTER DELETE ON snippets
+BEGIN
+    DELETE FROM snippets_fts WHERE rowid = old.rowid;
+END;
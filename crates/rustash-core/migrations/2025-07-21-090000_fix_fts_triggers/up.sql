@@ -0,0 +1,22 @@
+-- snippets_fts is an external-content table, so its rows cannot be changed
+-- with plain UPDATE or DELETE: FTS5 needs the old values to remove their
+-- terms from the index. Recreate the triggers with the 'delete' command and
+-- rebuild the index to drop any terms the old triggers left behind.
+DROP TRIGGER IF EXISTS snippets_au;
+DROP TRIGGER IF EXISTS snippets_ad;
+
+CREATE TRIGGER snippets_au AFTER UPDATE ON snippets
+BEGIN
+    INSERT INTO snippets_fts (snippets_fts, rowid, title, content, tags)
+    VALUES ('delete', old.rowid, old.title, old.content, old.tags);
+    INSERT INTO snippets_fts (rowid, title, content, tags)
+    VALUES (new.rowid, new.title, new.content, new.tags);
+END;
+
+CREATE TRIGGER snippets_ad AFTER DELETE ON snippets
+BEGIN
+    INSERT INTO snippets_fts (snippets_fts, rowid, title, content, tags)
+    VALUES ('delete', old.rowid, old.title, old.content, old.tags);
+END;
+
+INSERT INTO snippets_fts (snippets_fts) VALUES ('rebuild');
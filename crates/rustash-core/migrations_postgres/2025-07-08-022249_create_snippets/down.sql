@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_snippets_title;
+DROP INDEX IF EXISTS idx_snippets_created_at;
+DROP INDEX IF EXISTS idx_snippets_updated_at;
+
+DROP TABLE IF EXISTS snippets;
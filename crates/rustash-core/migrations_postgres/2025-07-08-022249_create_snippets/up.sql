@@ -0,0 +1,26 @@
+-- Create the snippets table with UUID as primary key.
+-- Full-text search comes from add_search_vector rather than FTS5, and the
+-- AGE graph, when the extension is installed, is created by the backend.
+CREATE TABLE snippets (
+    uuid TEXT PRIMARY KEY NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    tags TEXT NOT NULL DEFAULT '[]', -- JSON array of tags
+    embedding BYTEA, -- Vector embedding for similarity search
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX idx_snippets_title ON snippets(title);
+CREATE INDEX idx_snippets_created_at ON snippets(created_at);
+CREATE INDEX idx_snippets_updated_at ON snippets(updated_at);
+
+-- Insert a test snippet
+INSERT INTO snippets (uuid, title, content, tags)
+VALUES (
+    '00000000-0000-0000-0000-000000000000',
+    'Welcome to Rustash',
+    'This is your first snippet. Edit or delete it, then start creating your own!',
+    '["welcome", "getting-started"]'
+);
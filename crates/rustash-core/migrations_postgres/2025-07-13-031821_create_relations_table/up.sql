@@ -0,0 +1,9 @@
+-- Relations between items. The backend mirrors edges into AGE when the
+-- extension is installed, but this table is always the source of truth.
+CREATE TABLE IF NOT EXISTS relations (
+    from_uuid TEXT NOT NULL,
+    to_uuid TEXT NOT NULL,
+    relation_type TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (from_uuid, to_uuid, relation_type)
+);
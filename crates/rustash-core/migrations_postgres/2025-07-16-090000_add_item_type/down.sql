@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_snippets_item_type;
+
+ALTER TABLE snippets DROP COLUMN metadata;
+ALTER TABLE snippets DROP COLUMN item_type;
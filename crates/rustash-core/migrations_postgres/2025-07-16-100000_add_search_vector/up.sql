@@ -0,0 +1,11 @@
+-- Full-text search for PostgreSQL stashes.
+-- The column is generated, so it stays in sync with title, tags and content
+-- without triggers. It is deliberately absent from schema.rs, which is shared
+-- with SQLite, and is only referenced from raw SQL in the Postgres backend.
+ALTER TABLE snippets ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('english', coalesce(tags::text, '')), 'B') ||
+    setweight(to_tsvector('english', coalesce(content, '')), 'C')
+) STORED;
+
+CREATE INDEX idx_snippets_search_vector ON snippets USING GIN (search_vector);
@@ -53,6 +53,42 @@ pub fn save_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// A stash name as part of a file name.
+///
+/// Everything but lowercase ASCII letters, digits and underscores is
+/// percent-encoded, so no two names share a file, even on case-insensitive
+/// file systems, and no name reaches outside its directory.
+fn file_name_part(name: &str) -> String {
+    let mut part = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_' {
+            part.push(byte as char);
+        } else {
+            part.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    part
+}
+
+/// Where an interrupted copy from the stash `source` to `target` keeps its
+/// checkpoint.
+pub fn copy_checkpoint_path(source: &str, target: &str) -> Result<PathBuf> {
+    Ok(get_config_path()?.with_file_name(format!(
+        "copies/{}-to-{}.json",
+        file_name_part(source),
+        file_name_part(target)
+    )))
+}
+
+/// Where syncs between the stashes `local` and `remote` keep their state.
+pub fn sync_state_path(local: &str, remote: &str) -> Result<PathBuf> {
+    Ok(get_config_path()?.with_file_name(format!(
+        "syncs/{}-with-{}.json",
+        file_name_part(local),
+        file_name_part(remote)
+    )))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,6 +116,7 @@ mod tests {
             StashConfig {
                 service_type: crate::stash::ServiceType::Snippet,
                 database_url: "sqlite::memory:".to_string(),
+                trash_retention_days: None,
             },
         );
 
@@ -100,4 +137,25 @@ mod tests {
         save(&original_config, &config_path).unwrap();
         assert!(config_path.exists());
     }
+
+    #[test]
+    fn test_copy_checkpoint_path() {
+        let copies = get_config_path().unwrap().with_file_name("copies");
+        let path = |source, target| copy_checkpoint_path(source, target).unwrap();
+
+        assert_eq!(path("work", "home_2"), copies.join("work-to-home_2.json"));
+        assert_ne!(path("a-to-b", "c"), path("a", "b-to-c"));
+        assert_ne!(path("Work", "home"), path("work", "home"));
+        assert_eq!(path("../../etc", "x/y").parent(), Some(copies.as_path()));
+    }
+
+    #[test]
+    fn test_sync_state_path() {
+        let syncs = get_config_path().unwrap().with_file_name("syncs");
+        let path = |local, remote| sync_state_path(local, remote).unwrap();
+
+        assert_eq!(path("laptop", "team"), syncs.join("laptop-with-team.json"));
+        assert_ne!(path("a-with-b", "c"), path("a", "b-with-c"));
+        assert_eq!(path("..", "/tmp/x").parent(), Some(syncs.as_path()));
+    }
 }
@@ -0,0 +1,281 @@
+//! Detection of the project a command runs in.
+//!
+//! A [`ProjectContext`] describes the project around a directory: its root,
+//! name, git remote and languages, the latter found from marker files such
+//! as `Cargo.toml`. A `.rustash.toml` project file can name the project and
+//! add languages and tags:
+//!
+//! ```toml
+//! name = "billing"
+//! languages = ["sql"]
+//! tags = ["k8s", "deploy/*"]
+//! ```
+//!
+//! [Applied](ProjectContext::apply) to a [`Query`], the context ranks items
+//! tagged with the project's languages, its tags or `project:<name>` first,
+//! and leaves out items scoped to other projects.
+
+use crate::error::{Error, Result};
+use crate::models::Query;
+use crate::usage::repository_root;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The name of the project file.
+pub const PROJECT_FILE: &str = ".rustash.toml";
+
+/// Prefix of the tags scoping an item to a project, as in `project:billing`.
+pub const PROJECT_TAG_PREFIX: &str = "project:";
+
+/// Files whose presence marks a project written in a language.
+pub const LANGUAGE_MARKERS: [(&str, &str); 9] = [
+    ("Cargo.toml", "rust"),
+    ("package.json", "javascript"),
+    ("tsconfig.json", "typescript"),
+    ("pyproject.toml", "python"),
+    ("setup.py", "python"),
+    ("requirements.txt", "python"),
+    ("go.mod", "go"),
+    ("Gemfile", "ruby"),
+    ("pom.xml", "java"),
+];
+
+/// The contents of a project file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectFile {
+    name: Option<String>,
+    languages: Vec<String>,
+    tags: Vec<String>,
+}
+
+/// The project around a directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectContext {
+    /// The directory holding the project file, or else the repository root,
+    /// or else the nearest directory with a language marker
+    pub root: PathBuf,
+    /// From the project file, or else the remote's repository name, or else
+    /// the name of the root directory
+    pub name: String,
+    /// The URL of the repository's `origin` remote, or else its first remote
+    pub remote: Option<String>,
+    /// Languages of the markers between the directory and the root, nearest
+    /// first, then those listed in the project file
+    pub languages: Vec<String>,
+    /// Extra tags to rank first, from the project file
+    pub tags: Vec<String>,
+}
+
+impl ProjectContext {
+    /// Detect the project around `dir`, or `None` outside any project.
+    pub fn detect(dir: &Path) -> Result<Option<Self>> {
+        let project_dir = dir
+            .ancestors()
+            .find(|ancestor| ancestor.join(PROJECT_FILE).is_file());
+        let repository = repository_root(dir);
+        // The nearer of the two is the deeper one
+        let root = match (project_dir, &repository) {
+            (Some(project_dir), Some(repository)) if repository.starts_with(project_dir) => {
+                repository.clone()
+            }
+            (Some(project_dir), _) => project_dir.to_path_buf(),
+            (None, Some(repository)) => repository.clone(),
+            (None, None) => match dir.ancestors().find(|ancestor| has_marker(ancestor)) {
+                Some(marked) => marked.to_path_buf(),
+                None => return Ok(None),
+            },
+        };
+
+        let file = match project_dir {
+            Some(project_dir) => read_project_file(&project_dir.join(PROJECT_FILE))?,
+            None => ProjectFile::default(),
+        };
+        let remote = repository
+            .as_deref()
+            .and_then(|repository| read_remote(&repository.join(".git").join("config")));
+
+        let mut languages = Vec::new();
+        let searched = dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&root));
+        for ancestor in searched {
+            for (marker, language) in LANGUAGE_MARKERS {
+                if ancestor.join(marker).is_file() {
+                    languages.push(language.to_string());
+                }
+            }
+        }
+        languages.extend(file.languages);
+        let mut seen = std::collections::HashSet::new();
+        languages.retain(|language| seen.insert(language.clone()));
+
+        let name = file
+            .name
+            .or_else(|| remote.as_deref().and_then(remote_name))
+            .or_else(|| {
+                root.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default();
+
+        Ok(Some(Self {
+            root,
+            name,
+            remote,
+            languages,
+            tags: file.tags,
+        }))
+    }
+
+    /// Detect the project around the current directory.
+    pub fn current() -> Result<Option<Self>> {
+        let cwd = std::env::current_dir()?;
+        Self::detect(&cwd)
+    }
+
+    /// The tag scoping items to this project.
+    pub fn project_tag(&self) -> String {
+        format!("{}{}", PROJECT_TAG_PREFIX, self.name)
+    }
+
+    /// Tags whose items rank first: each language as `rust` and
+    /// `lang/rust`, the project's tag and the project file's tags.
+    pub fn boost_tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        for language in &self.languages {
+            tags.push(language.clone());
+            tags.push(format!("lang/{}", language));
+        }
+        tags.push(self.project_tag());
+        tags.extend(self.tags.iter().cloned());
+        tags
+    }
+
+    /// Rank `query`'s results for this project and scope them to it.
+    pub fn apply(&self, query: &mut Query) {
+        query.boost_tags.extend(self.boost_tags());
+        query.project = Some(self.name.clone());
+    }
+}
+
+fn has_marker(dir: &Path) -> bool {
+    LANGUAGE_MARKERS
+        .iter()
+        .any(|(marker, _)| dir.join(marker).is_file())
+}
+
+fn read_project_file(path: &Path) -> Result<ProjectFile> {
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text)
+        .map_err(|e| Error::validation(format!("Invalid {}: {}", path.display(), e)))
+}
+
+/// The URL of the `origin` remote in a git config file, or else of the
+/// first remote.
+fn read_remote(config: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(config).ok()?;
+    let mut section = String::new();
+    let mut first = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header.trim().to_string();
+            continue;
+        }
+        let Some(("url", url)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) else {
+            continue;
+        };
+        if section == "remote \"origin\"" {
+            return Some(url.to_string());
+        }
+        if section.starts_with("remote ") && first.is_none() {
+            first = Some(url.to_string());
+        }
+    }
+    first
+}
+
+/// The repository name in a remote URL, such as `rustash` in
+/// `git@github.com:org/rustash.git`.
+fn remote_name(url: &str) -> Option<String> {
+    let path = url.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let name = path.rsplit(['/', ':']).next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_remote_name() {
+        assert_eq!(
+            remote_name("git@github.com:org/rustash.git").as_deref(),
+            Some("rustash")
+        );
+        assert_eq!(
+            remote_name("https://example.com/org/billing/").as_deref(),
+            Some("billing")
+        );
+        assert_eq!(remote_name(""), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("billing");
+        let crate_dir = root.join("crates/api");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        assert_eq!(ProjectContext::detect(&crate_dir).unwrap(), None);
+
+        std::fs::write(crate_dir.join("Cargo.toml"), "").unwrap();
+        let context = ProjectContext::detect(&crate_dir).unwrap().unwrap();
+        assert_eq!(context.root, crate_dir);
+        assert_eq!(context.name, "api");
+        assert_eq!(context.languages, vec!["rust"]);
+
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(
+            root.join(".git/config"),
+            "[core]\n\tbare = false\n[remote \"upstream\"]\n\turl = https://example.com/up.git\n\
+             [remote \"origin\"]\n\turl = git@example.com:team/payments.git\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("package.json"), "{}").unwrap();
+        let context = ProjectContext::detect(&crate_dir).unwrap().unwrap();
+        assert_eq!(context.root, root);
+        assert_eq!(context.name, "payments");
+        assert_eq!(
+            context.remote.as_deref(),
+            Some("git@example.com:team/payments.git")
+        );
+        assert_eq!(context.languages, vec!["rust", "javascript"]);
+
+        std::fs::write(
+            root.join(PROJECT_FILE),
+            "name = \"billing\"\nlanguages = [\"sql\", \"rust\"]\ntags = [\"k8s\"]\n",
+        )
+        .unwrap();
+        let context = ProjectContext::detect(&crate_dir).unwrap().unwrap();
+        assert_eq!(context.name, "billing");
+        assert_eq!(context.languages, vec!["rust", "javascript", "sql"]);
+        assert_eq!(
+            context.boost_tags(),
+            vec![
+                "rust",
+                "lang/rust",
+                "javascript",
+                "lang/javascript",
+                "sql",
+                "lang/sql",
+                "project:billing",
+                "k8s"
+            ]
+        );
+
+        std::fs::write(root.join(PROJECT_FILE), "colour = \"blue\"\n").unwrap();
+        assert!(ProjectContext::detect(&crate_dir).is_err());
+    }
+}
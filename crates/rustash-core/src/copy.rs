@@ -0,0 +1,192 @@
+//! Copying everything in one stash to another, whatever their backends.
+//!
+//! Items are read a batch at a time, oldest first, and written with their
+//! UUIDs, timestamps, tags and embeddings as they are; relations follow once
+//! every item is across. After each batch the caller is handed a
+//! [`CopyCheckpoint`], and passing the last one back to [`copy`] picks an
+//! interrupted copy up where it stopped. Items already in the target are
+//! treated as [`LoadMode`] says, so running a copy twice is harmless.
+
+use crate::dump::{load_item, load_relations, LoadMode, LoadSummary};
+use crate::error::{Error, Result};
+use crate::models::Query;
+use crate::storage::StorageBackend;
+use serde::{Deserialize, Serialize};
+
+/// How many items a copy reads and writes at a time unless told otherwise.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// How to copy a stash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Items to read and write at a time
+    pub batch_size: usize,
+    /// What to do with items the target already has
+    pub mode: LoadMode,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            mode: LoadMode::default(),
+        }
+    }
+}
+
+/// How far a copy got.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyCheckpoint {
+    /// Cursor just past the last item written; `None` before the first batch
+    pub after: Option<String>,
+    /// What the copy has done so far
+    pub summary: LoadSummary,
+}
+
+/// Copy every item not in the trash, and the relations between them, from
+/// `source` to `target`, starting from `checkpoint`.
+///
+/// `on_batch` is called with the new checkpoint after each batch of items
+/// is written; an error from it stops the copy. Returns what the whole copy
+/// did, including work done before `checkpoint`.
+pub async fn copy<F>(
+    source: &dyn StorageBackend,
+    target: &dyn StorageBackend,
+    options: CopyOptions,
+    mut checkpoint: CopyCheckpoint,
+    mut on_batch: F,
+) -> Result<LoadSummary>
+where
+    F: FnMut(&CopyCheckpoint) -> Result<()>,
+{
+    if options.batch_size == 0 {
+        return Err(Error::validation("The batch size must be at least 1"));
+    }
+
+    let mut query = Query {
+        sort_by: Some("created_at:asc".to_string()),
+        limit: Some(options.batch_size),
+        ..Default::default()
+    };
+    let spec = query
+        .sort_spec()?
+        .ok_or_else(|| Error::other("A copy needs a keyset ordering"))?;
+    loop {
+        query.after = checkpoint.after.clone();
+        let page = source.query_page(&query).await?;
+        let Some(last) = page.items.last() else {
+            break;
+        };
+        for item in &page.items {
+            load_item(target, item.as_ref(), options.mode, &mut checkpoint.summary).await?;
+        }
+        checkpoint.after = Some(spec.cursor_after(last.as_ref())?);
+        on_batch(&checkpoint)?;
+        if page.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    load_relations(target, source.relations().await?, &mut checkpoint.summary).await?;
+    Ok(checkpoint.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SnippetWithTags;
+    use crate::vector::encode_embedding;
+    use crate::InMemoryBackend;
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    async fn source(count: i64) -> (InMemoryBackend, Vec<SnippetWithTags>) {
+        let backend = InMemoryBackend::new();
+        let start = Utc::now() - Duration::days(1);
+        let mut snippets = Vec::new();
+        for i in 0..count {
+            let mut snippet = SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                format!("snippet {}", i),
+                format!("echo {}", i),
+                vec!["shell".to_string()],
+            );
+            snippet.created_at = start + Duration::minutes(i);
+            snippet.updated_at = snippet.created_at;
+            snippet.embedding = Some(encode_embedding(&[i as f32, 1.0]).unwrap());
+            backend.save(&snippet).await.unwrap();
+            snippets.push(snippet);
+        }
+        backend
+            .add_relation(&snippets[0].id, &snippets[1].id, "SEE_ALSO")
+            .await
+            .unwrap();
+        (backend, snippets)
+    }
+
+    #[tokio::test]
+    async fn test_copy_in_batches() {
+        let (source, snippets) = source(5).await;
+        let target = InMemoryBackend::new();
+        let options = CopyOptions {
+            batch_size: 2,
+            ..Default::default()
+        };
+
+        let mut batches = Vec::new();
+        let summary = copy(
+            &source,
+            &target,
+            options,
+            CopyCheckpoint::default(),
+            |checkpoint| {
+                batches.push(checkpoint.summary.added);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(batches, vec![2, 4, 5]);
+        assert_eq!((summary.added, summary.relations), (5, 1));
+
+        for snippet in &snippets {
+            let copied = target.get(&snippet.id).await.unwrap().unwrap();
+            assert_eq!(copied.created_at(), snippet.created_at);
+            assert_eq!(copied.embedding(), snippet.embedding.as_deref());
+        }
+        assert_eq!(target.relations().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_copy_resumes_from_checkpoint() {
+        let (source, _) = source(5).await;
+        let target = InMemoryBackend::new();
+        let options = CopyOptions {
+            batch_size: 2,
+            ..Default::default()
+        };
+
+        // Fail while saving the checkpoint of the second batch
+        let mut saved = CopyCheckpoint::default();
+        let result = copy(&source, &target, options, saved.clone(), |checkpoint| {
+            if checkpoint.summary.added > 2 {
+                return Err(Error::other("interrupted"));
+            }
+            saved = checkpoint.clone();
+            Ok(())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(saved.summary.added, 2);
+
+        // The first batch is not read again and the second is found copied
+        let summary = copy(&source, &target, options, saved, |_| Ok(()))
+            .await
+            .unwrap();
+        assert_eq!(
+            (summary.added, summary.updated, summary.unchanged),
+            (3, 0, 2)
+        );
+        assert_eq!(target.query(&Query::default()).await.unwrap().len(), 5);
+    }
+}
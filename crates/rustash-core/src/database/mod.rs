@@ -6,9 +6,14 @@
 use crate::error::{Error, Result};
 use diesel_migrations::embed_migrations;
 
-// A common MIGRATIONS constant that can be used by backend-specific modules.
+// The SQLite migrations, which lean on FTS5, triggers and the vss0 extension.
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
+/// The PostgreSQL migrations, a complete set of their own that mirrors the
+/// SQLite schema without the SQLite-only parts.
+#[cfg(feature = "postgres")]
+pub const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
+
 // Re-export the migration types for use in backend modules
 pub use diesel_migrations::EmbeddedMigrations;
 
@@ -34,9 +39,14 @@ pub mod sqlite_pool {
 
         // Run migrations on a new connection from the pool
         let mut conn = pool.get().await.map_err(|e| Error::Pool(e.to_string()))?;
-        (&mut *conn)
-            .run_pending_migrations(MIGRATIONS)
-            .map_err(|e| Error::Other(format!("Migration failed: {}", e)))?;
+        conn.spawn_blocking(|c| {
+            c.run_pending_migrations(MIGRATIONS)
+                .map(|_| ())
+                .map_err(diesel::result::Error::QueryBuilderError)
+        })
+        .await
+        .map_err(|e| Error::Other(format!("Migration failed: {}", e)))?;
+        drop(conn);
 
         Ok(pool)
     }
@@ -45,6 +55,8 @@ pub mod sqlite_pool {
 #[cfg(feature = "postgres")]
 pub mod postgres_pool {
     use super::*;
+    use diesel::Connection;
+    use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
     use diesel_async::pg::AsyncPgConnection;
     use diesel_async::pooled_connection::bb8::Pool;
     use diesel_async::pooled_connection::AsyncDieselConnectionManager;
@@ -59,11 +71,17 @@ pub mod postgres_pool {
             .await
             .map_err(|e| Error::Pool(e.to_string()))?;
 
-        // Run migrations on a new connection from the pool
-        let mut conn = pool.get().await.map_err(|e| Error::Pool(e.to_string()))?;
-        (&mut *conn)
-            .run_pending_migrations(MIGRATIONS)
-            .map_err(|e| Error::Other(format!("Migration failed: {}", e)))?;
+        // Migrations are synchronous, so run them on a blocking thread through
+        // a wrapper connection instead of a pooled async one.
+        let url = database_url.to_string();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut conn = AsyncConnectionWrapper::<AsyncPgConnection>::establish(&url)?;
+            conn.run_pending_migrations(POSTGRES_MIGRATIONS)
+                .map_err(|e| Error::Other(format!("Migration failed: {}", e)))?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| Error::Runtime(e.to_string()))??;
 
         Ok(pool)
     }
@@ -0,0 +1,440 @@
+//! The native dump format: a lossless copy of a stash for backups and for
+//! moving items between stashes, whatever their backend.
+//!
+//! A dump is a sequence of [`Record`]s, written one per line as NDJSON or
+//! as a single JSON array. Each record is an object whose `kind` says what
+//! it holds:
+//!
+//! ```json
+//! {"kind":"header","format":"rustash","version":1,"exported_at":"2025-07-20T09:30:00Z"}
+//! {"kind":"item","id":"…","item_type":"snippet","title":"List files","content":"ls -la","tags":["shell"],"metadata":{},"created_at":"…","updated_at":"…"}
+//! {"kind":"relation","from":"…","to":"…","relation_type":"SEE_ALSO"}
+//! ```
+//!
+//! - `header` comes first. `version` is [`DUMP_FORMAT_VERSION`]; readers
+//!   reject dumps from newer versions.
+//! - `item` is one item with its UUID, type, title, content, tags,
+//!   type-specific `metadata`, optional `embedding` (an array of numbers)
+//!   and RFC 3339 `created_at` and `updated_at` timestamps, all kept as they
+//!   are.
+//! - `relation` is a directed, typed link between two items.
+//!
+//! Items come oldest first and relations follow the items. Items in the
+//! trash, version history, runs and uses are not part of a dump.
+
+use crate::error::{Error, Result};
+use crate::memory::{decode_item, encode_item, MemoryItem, StoredItem};
+use crate::models::{Query, Relation};
+use crate::storage::StorageBackend;
+use crate::vector::{decode_embedding, encode_embedding};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// The value of the header's `format` field.
+pub const DUMP_FORMAT: &str = "rustash";
+
+/// The version of the dump format this build writes and the newest it reads.
+pub const DUMP_FORMAT_VERSION: u32 = 1;
+
+/// One record of a dump.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    /// Names the format and its version
+    Header {
+        format: String,
+        version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Item(ItemRecord),
+    Relation(Relation),
+}
+
+/// An item as written to a dump.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemRecord {
+    pub id: Uuid,
+    pub item_type: String,
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Type-specific state, as stored by the type's codec
+    #[serde(default)]
+    pub metadata: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<StoredItem> for ItemRecord {
+    type Error = Error;
+
+    fn try_from(item: StoredItem) -> Result<Self> {
+        let embedding = item
+            .embedding
+            .as_deref()
+            .map(decode_embedding)
+            .transpose()
+            .map_err(|e| {
+                Error::validation(format!(
+                    "Item {} has an unreadable embedding: {}",
+                    item.id, e
+                ))
+            })?;
+        Ok(Self {
+            id: item.id,
+            item_type: item.item_type,
+            title: item.title,
+            content: item.content,
+            tags: item.tags,
+            metadata: item.metadata,
+            embedding,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+        })
+    }
+}
+
+impl TryFrom<ItemRecord> for StoredItem {
+    type Error = Error;
+
+    fn try_from(record: ItemRecord) -> Result<Self> {
+        Ok(Self {
+            id: record.id,
+            item_type: record.item_type,
+            title: record.title,
+            content: record.content,
+            tags: record.tags,
+            metadata: record.metadata,
+            embedding: record
+                .embedding
+                .as_deref()
+                .map(encode_embedding)
+                .transpose()?,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+        })
+    }
+}
+
+/// How a dump is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// One record per line
+    #[default]
+    Ndjson,
+    /// A JSON array of records
+    Json,
+}
+
+/// What loading a dump does with items the stash already has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LoadMode {
+    /// Take the dump's copy when it was updated more recently
+    #[default]
+    Merge,
+    /// Always take the dump's copy
+    Replace,
+    /// Keep the stash's copy
+    SkipExisting,
+}
+
+/// What loading a dump did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadSummary {
+    /// Items that were not in the stash
+    pub added: usize,
+    /// Items overwritten with the dump's copy
+    pub updated: usize,
+    /// Items left as the stash had them
+    pub unchanged: usize,
+    /// Relations written, including ones the stash already had
+    pub relations: usize,
+    /// Relations left out because an end is missing from the stash
+    pub skipped_relations: usize,
+}
+
+/// Read every item not in the trash, and the relations between them, as a
+/// dump.
+pub async fn dump(backend: &dyn StorageBackend) -> Result<Vec<Record>> {
+    let mut items = backend
+        .query(&Query::default())
+        .await?
+        .iter()
+        .map(|item| encode_item(item.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    items.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+    let mut records = vec![Record::Header {
+        format: DUMP_FORMAT.to_string(),
+        version: DUMP_FORMAT_VERSION,
+        exported_at: Utc::now(),
+    }];
+    for item in items {
+        records.push(Record::Item(item.try_into()?));
+    }
+    records.extend(backend.relations().await?.into_iter().map(Record::Relation));
+    Ok(records)
+}
+
+/// Write records in `format`.
+pub fn write(records: &[Record], format: DumpFormat) -> Result<String> {
+    match format {
+        DumpFormat::Json => {
+            let mut text = serde_json::to_string_pretty(records)?;
+            text.push('\n');
+            Ok(text)
+        }
+        DumpFormat::Ndjson => {
+            let mut text = String::new();
+            for record in records {
+                text.push_str(&serde_json::to_string(record)?);
+                text.push('\n');
+            }
+            Ok(text)
+        }
+    }
+}
+
+/// Read a dump in either format, checking its header.
+pub fn read(text: &str) -> Result<Vec<Record>> {
+    let records: Vec<Record> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text).map_err(|e| Error::validation(format!("Invalid dump: {}", e)))?
+    } else {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    Error::validation(format!("Invalid dump record on line {}: {}", index + 1, e))
+                })
+            })
+            .collect::<Result<_>>()?
+    };
+
+    match records.first() {
+        Some(Record::Header {
+            format, version, ..
+        }) if format == DUMP_FORMAT => {
+            if *version > DUMP_FORMAT_VERSION {
+                return Err(Error::validation(format!(
+                    "The dump is in format version {}, but this version of rustash reads up to {}",
+                    version, DUMP_FORMAT_VERSION
+                )));
+            }
+            Ok(records)
+        }
+        _ => Err(Error::validation(
+            "Not a rustash dump: it must start with a header record",
+        )),
+    }
+}
+
+/// Write the items and relations of a dump to a stash.
+///
+/// Every item is checked before anything is written, so a dump holding an
+/// item type this build does not know changes nothing. Relations whose ends
+/// are not in the stash afterwards are left out.
+pub async fn load(
+    backend: &dyn StorageBackend,
+    records: Vec<Record>,
+    mode: LoadMode,
+) -> Result<LoadSummary> {
+    let mut items = Vec::new();
+    let mut relations = Vec::new();
+    for record in records {
+        match record {
+            Record::Header { .. } => {}
+            Record::Item(item) => items.push(decode_item(item.try_into()?)?),
+            Record::Relation(relation) => relations.push(relation),
+        }
+    }
+
+    let mut summary = LoadSummary::default();
+    for item in items {
+        load_item(backend, item.as_ref(), mode, &mut summary).await?;
+    }
+    load_relations(backend, relations, &mut summary).await?;
+    Ok(summary)
+}
+
+/// Write one item to a stash as `mode` says, counting it in `summary`.
+pub(crate) async fn load_item(
+    backend: &dyn StorageBackend,
+    item: &(dyn MemoryItem + Send + Sync),
+    mode: LoadMode,
+    summary: &mut LoadSummary,
+) -> Result<()> {
+    let write = match backend.get(&item.id()).await? {
+        None => {
+            summary.added += 1;
+            true
+        }
+        Some(existing) => {
+            let write = match mode {
+                LoadMode::Merge => item.updated_at() > existing.updated_at(),
+                LoadMode::Replace => true,
+                LoadMode::SkipExisting => false,
+            };
+            if write {
+                summary.updated += 1;
+            } else {
+                summary.unchanged += 1;
+            }
+            write
+        }
+    };
+    if write {
+        backend.save(item).await?;
+    }
+    Ok(())
+}
+
+/// Write relations to a stash, leaving out those with a missing end.
+pub(crate) async fn load_relations(
+    backend: &dyn StorageBackend,
+    relations: Vec<Relation>,
+    summary: &mut LoadSummary,
+) -> Result<()> {
+    for relation in relations {
+        let from = backend.get(&relation.from).await?;
+        let to = backend.get(&relation.to).await?;
+        if from.is_none() || to.is_none() {
+            summary.skipped_relations += 1;
+            continue;
+        }
+        backend
+            .add_relation(&relation.from, &relation.to, &relation.relation_type)
+            .await?;
+        summary.relations += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SnippetWithTags;
+    use crate::InMemoryBackend;
+    use chrono::TimeZone;
+
+    fn snippet(title: &str, created_at: DateTime<Utc>) -> SnippetWithTags {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            format!("echo {}", title),
+            vec!["shell".to_string()],
+        );
+        snippet.created_at = created_at;
+        snippet.updated_at = created_at;
+        snippet
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let day = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        let source = InMemoryBackend::new();
+        let mut first = snippet("first", day(2));
+        first.embedding = Some(encode_embedding(&[0.25, -1.5]).unwrap());
+        let second = snippet("second", day(1));
+        source.save(&first).await.unwrap();
+        source.save(&second).await.unwrap();
+        source
+            .add_relation(&first.id, &second.id, "SEE_ALSO")
+            .await
+            .unwrap();
+
+        let records = dump(&source).await.unwrap();
+        assert!(matches!(records[0], Record::Header { version: 1, .. }));
+        assert!(matches!(&records[1], Record::Item(item) if item.title == "second"));
+        assert!(
+            matches!(&records[2], Record::Item(item) if item.embedding == Some(vec![0.25, -1.5]))
+        );
+
+        for format in [DumpFormat::Ndjson, DumpFormat::Json] {
+            let text = write(&records, format).unwrap();
+            assert_eq!(read(&text).unwrap(), records);
+
+            let target = InMemoryBackend::new();
+            let summary = load(&target, read(&text).unwrap(), LoadMode::Merge)
+                .await
+                .unwrap();
+            assert_eq!(summary.added, 2);
+            assert_eq!(summary.relations, 1);
+            let mut copied = dump(&target).await.unwrap();
+            copied[0] = records[0].clone();
+            assert_eq!(copied, records);
+        }
+    }
+
+    /// Load a dump holding `original` retitled and stamped `updated_at`,
+    /// related to a missing item.
+    async fn load_copy(
+        backend: &InMemoryBackend,
+        original: &SnippetWithTags,
+        title: &str,
+        updated_at: DateTime<Utc>,
+        mode: LoadMode,
+    ) -> (LoadSummary, String) {
+        let mut copy = original.clone();
+        copy.title = title.to_string();
+        copy.updated_at = updated_at;
+        let records = vec![
+            Record::Header {
+                format: DUMP_FORMAT.to_string(),
+                version: DUMP_FORMAT_VERSION,
+                exported_at: Utc::now(),
+            },
+            Record::Item(encode_item(&copy).unwrap().try_into().unwrap()),
+            Record::Relation(Relation {
+                from: copy.id,
+                to: Uuid::new_v4(),
+                relation_type: "SEE_ALSO".to_string(),
+            }),
+        ];
+        let summary = load(backend, records, mode).await.unwrap();
+
+        let item = backend.get(&original.id).await.unwrap().unwrap();
+        let stored = item.as_any().downcast_ref::<SnippetWithTags>().unwrap();
+        (summary, stored.title.clone())
+    }
+
+    #[tokio::test]
+    async fn test_load_modes() {
+        let day = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        let backend = InMemoryBackend::new();
+        let original = snippet("original", day(2));
+        backend.save(&original).await.unwrap();
+
+        let (summary, title) =
+            load_copy(&backend, &original, "older", day(1), LoadMode::Merge).await;
+        assert_eq!((summary.unchanged, summary.skipped_relations), (1, 1));
+        assert_eq!(title, "original");
+        let (_, title) =
+            load_copy(&backend, &original, "kept", day(3), LoadMode::SkipExisting).await;
+        assert_eq!(title, "original");
+        let (summary, title) =
+            load_copy(&backend, &original, "newer", day(3), LoadMode::Merge).await;
+        assert_eq!(summary.updated, 1);
+        assert_eq!(title, "newer");
+        let (_, title) =
+            load_copy(&backend, &original, "replaced", day(1), LoadMode::Replace).await;
+        assert_eq!(title, "replaced");
+    }
+
+    #[test]
+    fn test_read_checks_header() {
+        assert!(read("").is_err());
+        assert!(read("{\"kind\":\"relation\",\"from\":\"x\"}").is_err());
+        let newer = format!(
+            "{{\"kind\":\"header\",\"format\":\"rustash\",\"version\":{},\"exported_at\":\"2024-01-01T00:00:00Z\"}}",
+            DUMP_FORMAT_VERSION + 1
+        );
+        let err = read(&newer).unwrap_err().to_string();
+        assert!(err.contains("format version 2"), "{}", err);
+    }
+}
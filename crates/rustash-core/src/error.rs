@@ -67,6 +67,10 @@ pub enum Error {
     #[error("Duplicate entry: {0}")]
     Duplicate(String),
 
+    /// References that match more than one item
+    #[error("Ambiguous reference: {0}")]
+    Ambiguous(String),
+
     /// Permission/authorization errors
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
@@ -122,6 +126,23 @@ impl Error {
         Self::Duplicate(format!("{} already exists", resource))
     }
 
+    /// Create an ambiguity error listing the candidates `reference` matched
+    pub fn ambiguous(
+        reference: impl fmt::Display,
+        candidates: impl IntoIterator<Item = impl fmt::Display>,
+    ) -> Self {
+        let candidates: Vec<String> = candidates
+            .into_iter()
+            .map(|candidate| format!("\n  {}", candidate))
+            .collect();
+        Self::Ambiguous(format!(
+            "'{}' matches {} items:{}",
+            reference,
+            candidates.len(),
+            candidates.concat()
+        ))
+    }
+
     /// Create a permission denied error
     pub fn permission_denied(action: impl fmt::Display) -> Self {
         Self::PermissionDenied(format!("Permission denied for: {}", action))
@@ -142,6 +163,11 @@ impl Error {
         matches!(self, Self::Duplicate(_))
     }
 
+    /// Check if this is an ambiguous reference error
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(self, Self::Ambiguous(_))
+    }
+
     /// Check if this is a permission denied error
     pub fn is_permission_denied(&self) -> bool {
         matches!(self, Self::PermissionDenied(_))
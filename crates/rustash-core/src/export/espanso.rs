@@ -0,0 +1,126 @@
+//! espanso match files.
+//!
+//! ```yaml
+//! matches:
+//!   - trigger: ":ping"
+//!     label: "Ping a host"
+//!     form: "ping -c [[count]] [[host]]"
+//!     form_fields:
+//!       count:
+//!         default: "4"
+//! ```
+//!
+//! Each snippet becomes a match triggered by `:` and each of its aliases,
+//! or a name made from its title. Snippets without placeholders replace the
+//! trigger with their content; the others open a form with a field for each
+//! placeholder, `{{host}}` becoming `[[host]]`. Defaults and choices become
+//! the fields' defaults and choice lists.
+
+use super::{choices_default_first, convert_each, yaml_string, Export, ExportedSnippet, Exporter};
+use std::fmt::Write;
+
+/// Exports espanso match files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EspansoExporter;
+
+impl Exporter for EspansoExporter {
+    fn export(&self, snippets: &[ExportedSnippet]) -> Export {
+        let (converted, skipped) = convert_each(snippets, |snippet| {
+            snippet.template.translate(
+                &mut |placeholder| format!("[[{}]]", placeholder.name),
+                &str::to_string,
+            )
+        });
+
+        let mut text = String::from("matches:\n");
+        for (snippet, form) in &converted {
+            let triggers: Vec<String> = snippet
+                .triggers()
+                .iter()
+                .map(|trigger| yaml_string(&format!(":{}", trigger)))
+                .collect();
+            let _ = match triggers.as_slice() {
+                [trigger] => writeln!(text, "  - trigger: {}", trigger),
+                triggers => writeln!(text, "  - triggers: [{}]", triggers.join(", ")),
+            };
+            let _ = writeln!(text, "    label: {}", yaml_string(&snippet.title));
+
+            let placeholders = snippet.template.placeholders();
+            if placeholders.is_empty() {
+                let _ = writeln!(text, "    replace: {}", yaml_string(form));
+                continue;
+            }
+            let _ = writeln!(text, "    form: {}", yaml_string(form));
+            let fields: Vec<_> = placeholders
+                .iter()
+                .filter(|p| p.default.is_some() || !p.choices.is_empty())
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            let _ = writeln!(text, "    form_fields:");
+            for placeholder in fields {
+                let _ = writeln!(text, "      {}:", yaml_string(&placeholder.name));
+                if !placeholder.choices.is_empty() {
+                    let values: Vec<String> = choices_default_first(placeholder)
+                        .iter()
+                        .map(|choice| yaml_string(choice))
+                        .collect();
+                    let _ = writeln!(text, "        type: choice");
+                    let _ = writeln!(text, "        values: [{}]", values.join(", "));
+                }
+                if let Some(default) = &placeholder.default {
+                    let _ = writeln!(text, "        default: {}", yaml_string(default));
+                }
+            }
+        }
+
+        Export {
+            text,
+            count: converted.len(),
+            skipped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::snippet;
+    use super::*;
+
+    #[test]
+    fn test_espanso() {
+        let snippets = [
+            snippet(
+                "Ping",
+                "ping -c {{count:4}} {{host}}",
+                &["alias:p", "alias:pi"],
+            ),
+            snippet("Make", "make {{target:test|build,test}}", &[]),
+            snippet("Date", "date \"+%F\"\n", &[]),
+        ];
+        let export = EspansoExporter.export(&snippets);
+        assert_eq!(export.count, 3);
+        assert_eq!(
+            export.text,
+            "matches:\n\
+             \x20 - triggers: [\":p\", \":pi\"]\n\
+             \x20   label: \"Ping\"\n\
+             \x20   form: \"ping -c [[count]] [[host]]\"\n\
+             \x20   form_fields:\n\
+             \x20     \"count\":\n\
+             \x20       default: \"4\"\n\
+             \x20 - trigger: \":make\"\n\
+             \x20   label: \"Make\"\n\
+             \x20   form: \"make [[target]]\"\n\
+             \x20   form_fields:\n\
+             \x20     \"target\":\n\
+             \x20       type: choice\n\
+             \x20       values: [\"test\", \"build\"]\n\
+             \x20       default: \"test\"\n\
+             \x20 - trigger: \":date\"\n\
+             \x20   label: \"Date\"\n\
+             \x20   replace: \"date \\\"+%F\\\"\\n\"\n"
+        );
+    }
+}
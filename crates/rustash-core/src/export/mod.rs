@@ -0,0 +1,197 @@
+//! Exporting snippets to other tools.
+//!
+//! Each supported format implements [`Exporter`], which writes a set of
+//! [`ExportedSnippet`]s as one file, rewriting placeholders as the tool's
+//! variables. The output depends only on the snippets, which
+//! [`SnippetService::export`](crate::SnippetService::export) passes in title
+//! order, so exported files can be kept under version control.
+
+mod espanso;
+mod navi;
+mod pet;
+mod vscode;
+
+pub use espanso::EspansoExporter;
+pub use navi::NaviExporter;
+pub use pet::PetExporter;
+pub use vscode::VsCodeExporter;
+
+use crate::error::{Error, Result};
+use crate::models::SnippetWithTags;
+use crate::placeholder::{Placeholder, Template};
+use crate::snippet::ALIAS_TAG_PREFIX;
+
+/// The names of the formats [`exporter`] writes.
+pub const EXPORT_FORMATS: [&str; 4] = ["vscode", "espanso", "navi", "pet"];
+
+/// A snippet format of another tool.
+pub trait Exporter {
+    /// Write `snippets` as one file, leaving out those the tool cannot
+    /// express.
+    fn export(&self, snippets: &[ExportedSnippet]) -> Export;
+}
+
+/// A snippet ready to be written in another tool's format.
+#[derive(Debug, Clone)]
+pub struct ExportedSnippet {
+    pub title: String,
+    /// The tags, without aliases
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// The content, with includes inlined
+    pub template: Template,
+}
+
+impl ExportedSnippet {
+    /// Prepare `snippet`, whose content with includes inlined is `template`.
+    pub fn new(snippet: SnippetWithTags, template: Template) -> Self {
+        let (aliases, tags): (Vec<String>, Vec<String>) = snippet
+            .tags
+            .into_iter()
+            .partition(|tag| tag.starts_with(ALIAS_TAG_PREFIX));
+        Self {
+            title: snippet.title,
+            tags,
+            aliases: aliases
+                .into_iter()
+                .map(|tag| tag[ALIAS_TAG_PREFIX.len()..].to_string())
+                .collect(),
+            template,
+        }
+    }
+
+    /// The names the snippet is triggered by: its aliases, or a name made
+    /// from its title.
+    fn triggers(&self) -> Vec<String> {
+        if !self.aliases.is_empty() {
+            return self.aliases.clone();
+        }
+        let words: Vec<String> = self
+            .title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if words.is_empty() {
+            vec!["snippet".to_string()]
+        } else {
+            vec![words.join("-")]
+        }
+    }
+}
+
+/// The result of exporting a set of snippets.
+#[derive(Debug, Default)]
+pub struct Export {
+    /// The file's contents
+    pub text: String,
+    /// How many snippets it holds
+    pub count: usize,
+    /// Snippets left out, such as those using blocks
+    pub skipped: Vec<SkippedExport>,
+}
+
+/// A snippet that could not be exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedExport {
+    pub title: String,
+    /// Why it is left out
+    pub reason: String,
+}
+
+/// The exporter for a format named in [`EXPORT_FORMATS`].
+pub fn exporter(format: &str) -> Result<Box<dyn Exporter>> {
+    match format {
+        "vscode" => Ok(Box::new(VsCodeExporter)),
+        "espanso" => Ok(Box::new(EspansoExporter)),
+        "navi" => Ok(Box::new(NaviExporter)),
+        "pet" => Ok(Box::new(PetExporter)),
+        _ => Err(Error::validation(format!(
+            "Unknown export format '{}'. Use: {}",
+            format,
+            EXPORT_FORMATS.join(", ")
+        ))),
+    }
+}
+
+/// Convert each snippet with `convert`, setting aside those it fails on.
+fn convert_each<'a, T>(
+    snippets: &'a [ExportedSnippet],
+    mut convert: impl FnMut(&'a ExportedSnippet) -> Result<T>,
+) -> (Vec<(&'a ExportedSnippet, T)>, Vec<SkippedExport>) {
+    let mut converted = Vec::new();
+    let mut skipped = Vec::new();
+    for snippet in snippets {
+        match convert(snippet) {
+            Ok(value) => converted.push((snippet, value)),
+            Err(e) => skipped.push(SkippedExport {
+                title: snippet.title.clone(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+    (converted, skipped)
+}
+
+/// A placeholder's choices, with its default first.
+fn choices_default_first(placeholder: &Placeholder) -> Vec<String> {
+    let mut choices = placeholder.choices.clone();
+    if let Some(position) = placeholder
+        .default
+        .as_ref()
+        .and_then(|default| choices.iter().position(|choice| choice == default))
+    {
+        let default = choices.remove(position);
+        choices.insert(0, default);
+    }
+    choices
+}
+
+/// Quote `text` as a YAML string, using JSON's syntax, which YAML accepts.
+fn yaml_string(text: &str) -> String {
+    serde_json::to_string(text).expect("strings serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// An exported snippet with `content` and the given tags.
+    pub(super) fn snippet(title: &str, content: &str, tags: &[&str]) -> ExportedSnippet {
+        ExportedSnippet::new(
+            SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                title.to_string(),
+                content.to_string(),
+                tags.iter().map(|tag| tag.to_string()).collect(),
+            ),
+            Template::parse(content).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_exported_snippet() {
+        let exported = snippet("Git: Log (short)", "git log", &["git", "alias:lg"]);
+        assert_eq!(exported.tags, vec!["git"]);
+        assert_eq!(exported.aliases, vec!["lg"]);
+        assert_eq!(exported.triggers(), vec!["lg"]);
+        let exported = snippet("Git: Log (short)", "git log", &[]);
+        assert_eq!(exported.triggers(), vec!["git-log-short"]);
+    }
+
+    #[test]
+    fn test_exporter() {
+        for format in EXPORT_FORMATS {
+            let snippets = [
+                snippet("Blocks", "{{#if x}}x{{/if}}", &[]),
+                snippet("Echo", "echo {{word}}", &[]),
+            ];
+            let export = exporter(format).unwrap().export(&snippets);
+            assert_eq!(export.count, 1, "{}", format);
+            assert_eq!(export.skipped.len(), 1, "{}", format);
+            assert_eq!(export.skipped[0].title, "Blocks");
+        }
+        assert!(exporter("word").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! navi cheatsheets.
+//!
+//! ```text
+//! % network
+//!
+//! # Ping a host
+//! ping -c <count> <host>
+//!
+//! $ count: echo '4'
+//! ```
+//!
+//! Each snippet gets its own `%` section with its tags, so variables of the
+//! same name in different snippets stay apart. Placeholders become navi
+//! variables, `{{host}}` becoming `<host>`, and defaults and choices become
+//! the variables' suggestions. Blank lines, which would end the command,
+//! are left out.
+
+use super::{choices_default_first, convert_each, Export, ExportedSnippet, Exporter};
+use std::fmt::Write;
+
+/// Exports navi cheatsheets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NaviExporter;
+
+impl Exporter for NaviExporter {
+    fn export(&self, snippets: &[ExportedSnippet]) -> Export {
+        let (converted, skipped) = convert_each(snippets, |snippet| {
+            snippet.template.translate(
+                &mut |placeholder| format!("<{}>", placeholder.name),
+                &str::to_string,
+            )
+        });
+
+        let mut text = String::new();
+        for (snippet, command) in &converted {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            let tags = if snippet.tags.is_empty() {
+                "rustash".to_string()
+            } else {
+                snippet.tags.join(", ")
+            };
+            let _ = writeln!(text, "% {}\n", tags);
+            let _ = writeln!(text, "# {}", snippet.title.replace('\n', " "));
+            for line in command.lines().filter(|line| !line.trim().is_empty()) {
+                let _ = writeln!(text, "{}", line);
+            }
+
+            let mut suggestions = String::new();
+            for placeholder in snippet.template.placeholders() {
+                let values = match (&placeholder.default, placeholder.choices.is_empty()) {
+                    (_, false) => choices_default_first(placeholder),
+                    (Some(default), true) => vec![default.clone()],
+                    (None, true) => continue,
+                };
+                let quoted: Vec<String> = values.iter().map(|value| shell_quote(value)).collect();
+                let _ = match quoted.as_slice() {
+                    [value] => writeln!(suggestions, "$ {}: echo {}", placeholder.name, value),
+                    values => writeln!(
+                        suggestions,
+                        "$ {}: printf '%s\\n' {}",
+                        placeholder.name,
+                        values.join(" ")
+                    ),
+                };
+            }
+            if !suggestions.is_empty() {
+                text.push('\n');
+                text.push_str(&suggestions);
+            }
+        }
+
+        Export {
+            text,
+            count: converted.len(),
+            skipped,
+        }
+    }
+}
+
+/// Quote `text` for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::snippet;
+    use super::*;
+
+    #[test]
+    fn test_navi() {
+        let snippets = [
+            snippet(
+                "Ping",
+                "ping -c {{count:4}} {{host}}",
+                &["network", "alias:p"],
+            ),
+            snippet("Make", "make {{target:test|build,test}}\n\nmake clean", &[]),
+        ];
+        let export = NaviExporter.export(&snippets);
+        assert_eq!(export.count, 2);
+        assert_eq!(
+            export.text,
+            "% network\n\n# Ping\nping -c <count> <host>\n\n$ count: echo '4'\n\n\
+             % rustash\n\n# Make\nmake <target>\nmake clean\n\n\
+             $ target: printf '%s\\n' 'test' 'build'\n"
+        );
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,97 @@
+//! pet's `snippet.toml`.
+//!
+//! Each snippet becomes an entry described by its title. Placeholders
+//! become parameters: `{{host}}` becomes `<host>`, `{{count:4}}` becomes
+//! `<count=4>` and `{{mode:fast|fast,slow}}` becomes `<mode=|_fast_||_slow_|>`,
+//! pet's list of choices.
+
+use super::{choices_default_first, convert_each, Export, ExportedSnippet, Exporter};
+use serde::Serialize;
+
+/// Exports pet's `snippet.toml`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PetExporter;
+
+#[derive(Serialize)]
+struct PetFile<'a> {
+    snippets: Vec<PetSnippet<'a>>,
+}
+
+#[derive(Serialize)]
+struct PetSnippet<'a> {
+    description: &'a str,
+    command: String,
+    tag: &'a [String],
+    output: &'a str,
+}
+
+impl Exporter for PetExporter {
+    fn export(&self, snippets: &[ExportedSnippet]) -> Export {
+        let (converted, skipped) = convert_each(snippets, |snippet| {
+            snippet.template.translate(
+                &mut |placeholder| {
+                    let choices = choices_default_first(placeholder);
+                    if !choices.is_empty() {
+                        format!("<{}=|_{}_|>", placeholder.name, choices.join("_||_"))
+                    } else if let Some(default) = &placeholder.default {
+                        format!("<{}={}>", placeholder.name, default)
+                    } else {
+                        format!("<{}>", placeholder.name)
+                    }
+                },
+                &str::to_string,
+            )
+        });
+
+        let file = PetFile {
+            snippets: converted
+                .iter()
+                .map(|(snippet, command)| PetSnippet {
+                    description: &snippet.title,
+                    command: command.clone(),
+                    tag: &snippet.tags,
+                    output: "",
+                })
+                .collect(),
+        };
+        Export {
+            text: toml::to_string(&file).expect("pet snippets serialize"),
+            count: converted.len(),
+            skipped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::snippet;
+    use super::*;
+    use crate::import::{Importer, PetImporter};
+    use std::path::Path;
+
+    #[test]
+    fn test_pet() {
+        let snippets = [
+            snippet(
+                "Ping",
+                "ping -c {{count:4}} {{host}}",
+                &["network", "alias:p"],
+            ),
+            snippet("Make", "make {{target:test|build,test}}", &[]),
+        ];
+        let export = PetExporter.export(&snippets);
+        assert_eq!(export.count, 2);
+        assert!(export
+            .text
+            .contains("command = \"ping -c <count=4> <host>\""));
+
+        // pet's own format reads back as the same snippets
+        let imported = PetImporter
+            .parse(&export.text, Path::new("snippet.toml"))
+            .unwrap();
+        assert_eq!(imported[0].title, "Ping");
+        assert_eq!(imported[0].content, "ping -c {{count:4}} {{host}}");
+        assert_eq!(imported[0].tags, vec!["network"]);
+        assert_eq!(imported[1].content, "make {{target:test|test,build}}");
+    }
+}
@@ -0,0 +1,147 @@
+//! VS Code's `*.code-snippets` files.
+//!
+//! Each snippet becomes an entry named by its title, with its aliases, or a
+//! name made from its title, as prefixes and its `lang/*` tags as the scope.
+//! Placeholders become tabstops numbered in order of appearance: `{{host}}`
+//! becomes `${1:host}`, `{{count:4}}` becomes `${2:4}` and
+//! `{{mode:fast|fast,slow}}` becomes `${3|fast,slow|}`. A placeholder used
+//! again becomes a mirror of its tabstop, such as `$1`.
+
+use super::{choices_default_first, convert_each, Export, ExportedSnippet, Exporter};
+use crate::error::Result;
+use crate::placeholder::Template;
+use serde_json::{json, Map, Value};
+
+/// Exports VS Code snippet files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VsCodeExporter;
+
+impl Exporter for VsCodeExporter {
+    fn export(&self, snippets: &[ExportedSnippet]) -> Export {
+        let (converted, skipped) = convert_each(snippets, |snippet| body(&snippet.template));
+        let mut entries = Map::new();
+        for (snippet, body) in &converted {
+            // Entries are keyed by name, so titles shared by several
+            // snippets are numbered
+            let mut name = snippet.title.clone();
+            let mut number = 2;
+            while entries.contains_key(&name) {
+                name = format!("{} ({})", snippet.title, number);
+                number += 1;
+            }
+
+            let mut entry = json!({
+                "prefix": snippet.triggers(),
+                "body": body.split('\n').collect::<Vec<_>>(),
+                "description": snippet.title,
+            });
+            let languages: Vec<&str> = snippet
+                .tags
+                .iter()
+                .filter_map(|tag| tag.strip_prefix("lang/"))
+                .collect();
+            if !languages.is_empty() {
+                entry["scope"] = Value::from(languages.join(","));
+            }
+            entries.insert(name, entry);
+        }
+
+        let mut text = serde_json::to_string_pretty(&entries).expect("JSON values serialize");
+        text.push('\n');
+        Export {
+            text,
+            count: converted.len(),
+            skipped,
+        }
+    }
+}
+
+/// Write a template in VS Code's snippet syntax.
+fn body(template: &Template) -> Result<String> {
+    let mut tabstops: Vec<String> = Vec::new();
+    template.translate(
+        &mut |placeholder| {
+            if let Some(index) = tabstops.iter().position(|name| name == &placeholder.name) {
+                return format!("${}", index + 1);
+            }
+            tabstops.push(placeholder.name.clone());
+            let number = tabstops.len();
+            if placeholder.choices.is_empty() {
+                let text = placeholder.default.as_deref().unwrap_or(&placeholder.name);
+                format!("${{{}:{}}}", number, escape(text))
+            } else {
+                let choices: Vec<String> = choices_default_first(placeholder)
+                    .iter()
+                    .map(|choice| escape_choice(choice))
+                    .collect();
+                format!("${{{}|{}|}}", number, choices.join(","))
+            }
+        },
+        &escape,
+    )
+}
+
+/// Escape the characters VS Code would read as part of a tabstop.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+/// Escape the characters VS Code would read as part of a list of choices.
+fn escape_choice(choice: &str) -> String {
+    let mut escaped = String::new();
+    for c in choice.chars() {
+        if matches!(c, '\\' | ',' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::snippet;
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        let body = |content: &str| body(&Template::parse(content).unwrap()).unwrap();
+        assert_eq!(
+            body("ssh {{user:root}}@{{host}} -p {{port:int=22}} # {{host}}"),
+            "ssh ${1:root}@${2:host} -p ${3:22} # $2"
+        );
+        assert_eq!(
+            body("make {{target:test|build,test}}"),
+            "make ${1|test,build|}"
+        );
+        assert_eq!(body("echo $HOME {}"), "echo \\$HOME {\\}");
+    }
+
+    #[test]
+    fn test_vscode() {
+        let snippets = [
+            snippet(
+                "Log",
+                "console.log({{value}});",
+                &["lang/javascript", "alias:cl"],
+            ),
+            snippet("Log", "print()\n", &[]),
+        ];
+        let export = VsCodeExporter.export(&snippets);
+        assert_eq!(export.count, 2);
+        let entries: Value = serde_json::from_str(&export.text).unwrap();
+        assert_eq!(
+            entries["Log"],
+            json!({
+                "prefix": ["cl"],
+                "body": ["console.log(${1:value});"],
+                "description": "Log",
+                "scope": "javascript",
+            })
+        );
+        assert_eq!(entries["Log (2)"]["body"], json!(["print()", ""]));
+        assert_eq!(entries["Log (2)"]["prefix"], json!(["log"]));
+    }
+}
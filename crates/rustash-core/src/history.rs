@@ -0,0 +1,246 @@
+//! Version history of stored items.
+//!
+//! Every backend records a [`Version`] when a save creates an item or changes
+//! its title, content or tags, so earlier states can be listed, compared with
+//! [`VersionDiff`] and restored.
+
+use crate::memory::{title_and_tags, MemoryItem};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Environment variable naming the author recorded with new versions.
+///
+/// When unset, the login name from `USER` or `USERNAME` is used instead.
+pub const AUTHOR_ENV: &str = "RUSTASH_AUTHOR";
+
+/// One saved state of an item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    /// The item this is a version of
+    pub item_id: Uuid,
+    /// Sequence number, starting at 1 for the save that created the item
+    pub number: u32,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    /// Who saved this version, if known
+    pub author: Option<String>,
+    /// When this version was saved
+    pub created_at: DateTime<Utc>,
+}
+
+impl Version {
+    /// Capture the tracked fields of `item` as version `number`.
+    pub fn snapshot(item: &(dyn MemoryItem + Send + Sync), number: u32) -> Self {
+        let (title, tags) = title_and_tags(item);
+        Self {
+            item_id: item.id(),
+            number,
+            title,
+            content: item.content().to_string(),
+            tags,
+            author: current_author(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether `item` differs from this version in a tracked field.
+    pub fn differs_from(&self, item: &(dyn MemoryItem + Send + Sync)) -> bool {
+        let (title, tags) = title_and_tags(item);
+        self.title != title || self.content != item.content() || self.tags != tags
+    }
+}
+
+/// The version that saving `item` should record, given the latest one stored.
+///
+/// Saves that leave the tracked fields untouched record nothing.
+pub(crate) fn next_version(
+    latest: Option<&Version>,
+    item: &(dyn MemoryItem + Send + Sync),
+) -> Option<Version> {
+    match latest {
+        None => Some(Version::snapshot(item, 1)),
+        Some(latest) if latest.differs_from(item) => {
+            Some(Version::snapshot(item, latest.number + 1))
+        }
+        Some(_) => None,
+    }
+}
+
+/// The author to record with new versions.
+pub fn current_author() -> Option<String> {
+    [AUTHOR_ENV, "USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+}
+
+/// One line of a content diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Unchanged(String),
+    Added(String),
+    Removed(String),
+}
+
+/// The changes between two versions of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDiff {
+    /// The older version number
+    pub from: u32,
+    /// The newer version number
+    pub to: u32,
+    /// The old and new title, if it changed
+    pub title: Option<(String, String)>,
+    /// Tags present only in the newer version
+    pub added_tags: Vec<String>,
+    /// Tags present only in the older version
+    pub removed_tags: Vec<String>,
+    /// The content, line by line
+    pub content: Vec<DiffLine>,
+}
+
+impl VersionDiff {
+    /// Compare `old` with `new`.
+    pub fn between(old: &Version, new: &Version) -> Self {
+        let title = (old.title != new.title).then(|| (old.title.clone(), new.title.clone()));
+        let only_in = |a: &[String], b: &[String]| {
+            a.iter()
+                .filter(|tag| !b.contains(tag))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            from: old.number,
+            to: new.number,
+            title,
+            added_tags: only_in(&new.tags, &old.tags),
+            removed_tags: only_in(&old.tags, &new.tags),
+            content: diff_lines(&old.content, &new.content),
+        }
+    }
+
+    /// Whether the two versions are identical.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.added_tags.is_empty()
+            && self.removed_tags.is_empty()
+            && self
+                .content
+                .iter()
+                .all(|line| matches!(line, DiffLine::Unchanged(_)))
+    }
+}
+
+/// A line diff of `old` against `new`, built from their longest common
+/// subsequence. Removals are listed before additions at each change.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Unchanged(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SnippetWithTags;
+
+    fn version(number: u32, title: &str, content: &str, tags: &[&str]) -> Version {
+        Version {
+            item_id: Uuid::nil(),
+            number,
+            title: title.to_string(),
+            content: content.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            author: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        use DiffLine::*;
+
+        let lines = diff_lines("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(
+            lines,
+            vec![
+                Unchanged("a".into()),
+                Removed("b".into()),
+                Added("x".into()),
+                Unchanged("c".into()),
+                Added("d".into()),
+            ]
+        );
+        assert_eq!(diff_lines("", "a"), vec![Added("a".into())]);
+        assert_eq!(diff_lines("a", ""), vec![Removed("a".into())]);
+    }
+
+    #[test]
+    fn test_version_diff() {
+        let old = version(1, "Deploy", "make build\nmake deploy", &["ops", "make"]);
+        let new = version(2, "Deploy app", "make build\nmake deploy", &["ops", "ci"]);
+
+        let diff = VersionDiff::between(&old, &new);
+        assert_eq!(
+            diff.title,
+            Some(("Deploy".to_string(), "Deploy app".to_string()))
+        );
+        assert_eq!(diff.added_tags, vec!["ci"]);
+        assert_eq!(diff.removed_tags, vec!["make"]);
+        assert!(!diff.is_empty());
+
+        assert!(VersionDiff::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_next_version() {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Title".to_string(),
+            "echo hi".to_string(),
+            vec!["shell".to_string()],
+        );
+
+        let first = next_version(None, &snippet).unwrap();
+        assert_eq!(first.number, 1);
+        assert_eq!(first.item_id, snippet.id);
+        assert!(next_version(Some(&first), &snippet).is_none());
+
+        snippet.content = "echo hello".to_string();
+        let second = next_version(Some(&first), &snippet).unwrap();
+        assert_eq!(second.number, 2);
+        assert_eq!(second.content, "echo hello");
+    }
+}
@@ -0,0 +1,271 @@
+//! Importing snippets kept by other tools.
+//!
+//! Each supported format implements [`Importer`], which turns one file into
+//! [`ImportedSnippet`]s and rewrites the tool's variables as rustash
+//! placeholders. [`SnippetService::plan_import`](crate::SnippetService::plan_import)
+//! then sets aside duplicates of snippets already in the stash.
+
+mod navi;
+mod pet;
+mod tldr;
+mod vscode;
+
+pub use navi::NaviImporter;
+pub use pet::PetImporter;
+pub use tldr::TldrImporter;
+pub use vscode::VsCodeImporter;
+
+use crate::error::{Error, Result};
+use crate::models::SnippetWithTags;
+use crate::placeholder::Placeholder;
+use std::path::{Path, PathBuf};
+
+/// The names of the formats [`importer`] accepts.
+pub const IMPORT_FORMATS: [&str; 4] = ["pet", "navi", "vscode", "tldr"];
+
+/// A snippet format of another tool.
+pub trait Importer {
+    /// Whether a file found while importing a directory holds snippets in
+    /// this format.
+    fn accepts(&self, path: &Path) -> bool;
+
+    /// Read the snippets in `text`, the contents of the file at `path`.
+    fn parse(&self, text: &str, path: &Path) -> Result<Vec<ImportedSnippet>>;
+}
+
+/// A snippet read from another tool, not yet in a stash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSnippet {
+    pub title: String,
+    /// The content, with the tool's variables rewritten as placeholders
+    pub content: String,
+    pub tags: Vec<String>,
+    /// Names the tool triggered the snippet by, kept as aliases
+    pub aliases: Vec<String>,
+    /// The file the snippet came from
+    pub source: PathBuf,
+}
+
+/// What importing a set of snippets would do.
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    /// Snippets to add, with their aliases among the tags
+    pub new: Vec<SnippetWithTags>,
+    /// Snippets left out, such as duplicates
+    pub skipped: Vec<SkippedSnippet>,
+    /// Aliases left out because a snippet already has them
+    pub dropped_aliases: Vec<String>,
+}
+
+/// An imported snippet that will not be added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedSnippet {
+    pub title: String,
+    pub source: PathBuf,
+    /// Why it is left out
+    pub reason: String,
+}
+
+/// The importer for a format named in [`IMPORT_FORMATS`].
+pub fn importer(format: &str) -> Result<Box<dyn Importer>> {
+    match format {
+        "pet" => Ok(Box::new(PetImporter)),
+        "navi" => Ok(Box::new(NaviImporter)),
+        "vscode" => Ok(Box::new(VsCodeImporter)),
+        "tldr" => Ok(Box::new(TldrImporter)),
+        _ => Err(Error::validation(format!(
+            "Unknown import format '{}'. Use: {}",
+            format,
+            IMPORT_FORMATS.join(", ")
+        ))),
+    }
+}
+
+/// Read the snippets in `path`: a file, or a directory searched recursively
+/// for the files the importer accepts, in name order.
+pub fn read_snippets(importer: &dyn Importer, path: &Path) -> Result<Vec<ImportedSnippet>> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        collect_files(importer, path, &mut files)?;
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut snippets = Vec::new();
+    for file in files {
+        let text = std::fs::read_to_string(&file)
+            .map_err(|e| Error::other(format!("Failed to read {}: {}", file.display(), e)))?;
+        let parsed = importer
+            .parse(&text, &file)
+            .map_err(|e| Error::validation(format!("{}: {}", file.display(), e)))?;
+        snippets.extend(parsed);
+    }
+    Ok(snippets)
+}
+
+fn collect_files(importer: &dyn Importer, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(importer, &path, files)?;
+        } else if importer.accepts(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` has the extension `extension`.
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|found| found.eq_ignore_ascii_case(extension))
+}
+
+/// Escape text that would otherwise start a placeholder.
+fn escape(text: &str) -> String {
+    text.replace("{{", "\\{{")
+}
+
+/// A placeholder name made from free text, such as `path_to_file` from
+/// `path/to/file`.
+fn placeholder_name(text: &str) -> String {
+    let mut name = String::new();
+    for c in text.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_');
+    match name.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => name.to_string(),
+        Some(_) => format!("arg_{}", name),
+        None => "value".to_string(),
+    }
+}
+
+/// Write a placeholder with an optional default and choices, escaped so
+/// they are read back as given.
+fn placeholder(name: &str, default: Option<&str>, choices: &[String]) -> String {
+    Placeholder {
+        name: name.to_string(),
+        default: default
+            .filter(|default| !default.is_empty())
+            .map(str::to_string),
+        choices: choices.to_vec(),
+        ..Default::default()
+    }
+    .to_string()
+}
+
+/// Rewrite `<name>` and `<name=default>` parameters, as used by pet and
+/// navi, as placeholders. Angle brackets around anything else, such as
+/// shell redirections, are kept.
+fn angle_parameters(
+    command: &str,
+    default_value: impl Fn(&str) -> (String, Vec<String>),
+) -> String {
+    let mut out = String::new();
+    let mut rest = command;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&escape(&rest[..start]));
+        let after = &rest[start + 1..];
+        let parameter = after.find('>').map(|end| &after[..end]).filter(|inner| {
+            let name = inner.split_once('=').map_or(*inner, |(name, _)| name);
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+        match parameter {
+            Some(inner) => {
+                let (name, default) = match inner.split_once('=') {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (inner, None),
+                };
+                let (default, choices) = match default {
+                    Some(default) => default_value(default),
+                    None => (String::new(), Vec::new()),
+                };
+                out.push_str(&placeholder(
+                    &placeholder_name(name),
+                    Some(&default),
+                    &choices,
+                ));
+                rest = &after[inner.len() + 1..];
+            }
+            None => {
+                out.push('<');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(&escape(rest));
+    out
+}
+
+/// The first line of `text`, without a line continuation, for snippets
+/// without a title of their own.
+fn first_line(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    line.trim().trim_end_matches('\\').trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placeholder::Template;
+
+    #[test]
+    fn test_placeholder_name() {
+        assert_eq!(placeholder_name("path/to/file"), "path_to_file");
+        assert_eq!(placeholder_name(" branch-name "), "branch-name");
+        assert_eq!(placeholder_name("1"), "arg_1");
+        assert_eq!(placeholder_name("..."), "value");
+    }
+
+    #[test]
+    fn test_angle_parameters() {
+        let plain = |default: &str| (default.to_string(), Vec::new());
+        assert_eq!(
+            angle_parameters("ssh <user>@<host=example.com> < in > out", plain),
+            "ssh {{user}}@{{host:example.com}} < in > out"
+        );
+        assert_eq!(
+            angle_parameters("docker ps --format '{{.Names}}' <x>", plain),
+            "docker ps --format '\\{{.Names}}' {{x}}"
+        );
+    }
+
+    #[test]
+    fn test_defaults_and_choices_are_escaped() {
+        let plain = |default: &str| (default.to_string(), Vec::new());
+        let content = angle_parameters("curl <url=http://a:8080> -o <out=path>", plain);
+        assert_eq!(
+            content,
+            "curl {{url:http\\://a\\:8080}} -o {{out:str=path}}"
+        );
+        let template = Template::parse(&content).unwrap();
+        let defaults: Vec<_> = template
+            .placeholders()
+            .iter()
+            .map(|placeholder| placeholder.default.as_deref())
+            .collect();
+        assert_eq!(defaults, vec![Some("http://a:8080"), Some("path")]);
+
+        let choices = vec!["a|b".to_string(), "c,d".to_string()];
+        let template = Template::parse(&placeholder("x", Some("}}"), &choices)).unwrap();
+        assert_eq!(template.placeholders()[0].default.as_deref(), Some("}}"));
+        assert_eq!(template.placeholders()[0].choices, choices);
+    }
+}
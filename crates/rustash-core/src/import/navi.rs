@@ -0,0 +1,90 @@
+//! navi's `.cheat` files.
+//!
+//! ```text
+//! % git, code
+//!
+//! # Switch to a branch
+//! git checkout <branch>
+//!
+//! $ branch: git branch | awk '{print $NF}'
+//! ```
+//!
+//! `%` lines tag the snippets that follow, `#` lines title the next command
+//! and `<branch>` becomes `{{branch}}`. Variable sources (`$` lines),
+//! comments (`;`) and `@` extensions have no rustash equivalent and are
+//! skipped.
+
+use super::{angle_parameters, first_line, has_extension, ImportedSnippet, Importer};
+use crate::error::Result;
+use std::path::Path;
+
+/// Imports navi's `.cheat` files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NaviImporter;
+
+impl Importer for NaviImporter {
+    fn accepts(&self, path: &Path) -> bool {
+        has_extension(path, "cheat")
+    }
+
+    fn parse(&self, text: &str, path: &Path) -> Result<Vec<ImportedSnippet>> {
+        let mut snippets = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        let mut description: Option<String> = None;
+        let mut command: Vec<&str> = Vec::new();
+
+        // A trailing blank line ends the last command
+        for line in text.lines().chain([""]) {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with(['%', '#', '$', ';', '@']) {
+                command.push(line.trim_end());
+                continue;
+            }
+            if !command.is_empty() {
+                let text = command.join("\n");
+                command.clear();
+                snippets.push(ImportedSnippet {
+                    title: description.take().unwrap_or_else(|| first_line(&text)),
+                    content: angle_parameters(&text, |default| (default.to_string(), Vec::new())),
+                    tags: tags.clone(),
+                    aliases: Vec::new(),
+                    source: path.to_path_buf(),
+                });
+            }
+
+            if let Some(rest) = trimmed.strip_prefix('%') {
+                description = None;
+                tags = rest
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            } else if let Some(rest) = trimmed.strip_prefix('#') {
+                description = Some(rest.trim().to_string());
+            }
+        }
+        Ok(snippets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navi() {
+        let text = "% git, code\n\n# Switch to a branch\ngit checkout <branch>\n\n\
+                    $ branch: git branch | awk '{print $NF}'\n\n; a comment\n\
+                    git log \\\n  --oneline\n\n% docker\n# Remove a container\ndocker rm <id>\n";
+        let snippets = NaviImporter.parse(text, Path::new("git.cheat")).unwrap();
+        assert_eq!(snippets.len(), 3);
+        assert_eq!(snippets[0].title, "Switch to a branch");
+        assert_eq!(snippets[0].content, "git checkout {{branch}}");
+        assert_eq!(snippets[0].tags, vec!["git", "code"]);
+        assert_eq!(snippets[1].title, "git log");
+        assert_eq!(snippets[1].content, "git log \\\n  --oneline");
+        assert_eq!(snippets[2].content, "docker rm {{id}}");
+        assert_eq!(snippets[2].tags, vec!["docker"]);
+    }
+}
@@ -0,0 +1,106 @@
+//! pet's `snippet.toml`.
+//!
+//! ```toml
+//! [[snippets]]
+//!   description = "Ping a host"
+//!   command = "ping -c <count=4> <host>"
+//!   tag = ["network"]
+//! ```
+//!
+//! Parameters become placeholders: `<host>` becomes `{{host}}`,
+//! `<count=4>` becomes `{{count:4}}` and `<mode=|_fast_||_slow_|>`, pet's
+//! list of choices, becomes `{{mode:fast|fast,slow}}`.
+
+use super::{angle_parameters, first_line, has_extension, ImportedSnippet, Importer};
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Imports pet's `snippet.toml`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PetImporter;
+
+#[derive(Deserialize)]
+struct PetFile {
+    #[serde(default)]
+    snippets: Vec<PetSnippet>,
+}
+
+#[derive(Deserialize)]
+struct PetSnippet {
+    #[serde(default)]
+    description: String,
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+}
+
+impl Importer for PetImporter {
+    fn accepts(&self, path: &Path) -> bool {
+        has_extension(path, "toml")
+    }
+
+    fn parse(&self, text: &str, path: &Path) -> Result<Vec<ImportedSnippet>> {
+        let file: PetFile = toml::from_str(text)
+            .map_err(|e| Error::validation(format!("Invalid pet file: {}", e)))?;
+        Ok(file
+            .snippets
+            .into_iter()
+            .filter(|snippet| !snippet.command.trim().is_empty())
+            .map(|snippet| {
+                let title = match snippet.description.trim() {
+                    "" => first_line(&snippet.command),
+                    description => description.to_string(),
+                };
+                ImportedSnippet {
+                    title,
+                    content: angle_parameters(snippet.command.trim(), default_value),
+                    tags: snippet.tag,
+                    aliases: Vec::new(),
+                    source: path.to_path_buf(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Split a pet default into the default and choices, the first choice being
+/// the default.
+fn default_value(default: &str) -> (String, Vec<String>) {
+    let Some(choices) = default
+        .strip_prefix("|_")
+        .and_then(|choices| choices.strip_suffix("_|"))
+    else {
+        return (default.to_string(), Vec::new());
+    };
+    let choices: Vec<String> = choices.split("_||_").map(str::to_string).collect();
+    (choices[0].clone(), choices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pet() {
+        let text = r#"
+[[snippets]]
+  description = "Ping a host"
+  command = "ping -c <count=4> <host>"
+  tag = ["network"]
+  output = ""
+
+[[snippets]]
+  command = "make <target=|_build_||_test_|>"
+"#;
+        let snippets = PetImporter.parse(text, Path::new("snippet.toml")).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].title, "Ping a host");
+        assert_eq!(snippets[0].content, "ping -c {{count:4}} {{host}}");
+        assert_eq!(snippets[0].tags, vec!["network"]);
+        assert_eq!(snippets[1].title, "make <target=|_build_||_test_|>");
+        assert_eq!(snippets[1].content, "make {{target:build|build,test}}");
+
+        assert!(PetImporter.parse("snippets = 1", Path::new("x")).is_err());
+    }
+}
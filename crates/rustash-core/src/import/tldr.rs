@@ -0,0 +1,117 @@
+//! tldr pages.
+//!
+//! ```markdown
+//! # tar
+//!
+//! > Archiving utility.
+//!
+//! - Create an archive from files:
+//!
+//! `tar {{[-c|--create]}} {{[-f|--file]}} {{target.tar}} {{path/to/file}}`
+//! ```
+//!
+//! Each example becomes a snippet titled `tar: Create an archive from files`
+//! and tagged with the page's command. Placeholders are named after their
+//! text, so `{{path/to/file}}` becomes `{{path_to_file}}`, while option
+//! placeholders such as `{{[-c|--create]}}` become their long form.
+
+use super::{has_extension, placeholder, placeholder_name, ImportedSnippet, Importer};
+use crate::error::Result;
+use std::path::Path;
+
+/// Imports tldr pages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TldrImporter;
+
+impl Importer for TldrImporter {
+    fn accepts(&self, path: &Path) -> bool {
+        has_extension(path, "md")
+    }
+
+    fn parse(&self, text: &str, path: &Path) -> Result<Vec<ImportedSnippet>> {
+        let mut command = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut description: Option<String> = None;
+        let mut snippets = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("# ") {
+                command = name.trim().to_string();
+            } else if let Some(text) = line.strip_prefix("- ") {
+                description = Some(text.trim().trim_end_matches(':').to_string());
+            } else if let Some(example) = line
+                .strip_prefix('`')
+                .and_then(|line| line.strip_suffix('`'))
+            {
+                let title = match description.take() {
+                    Some(description) => format!("{}: {}", command, description),
+                    None => format!("{}: {}", command, example),
+                };
+                snippets.push(ImportedSnippet {
+                    title,
+                    content: rewrite(example),
+                    tags: vec![command.clone()],
+                    aliases: Vec::new(),
+                    source: path.to_path_buf(),
+                });
+            }
+        }
+        Ok(snippets)
+    }
+}
+
+/// Rewrite tldr's placeholders as rustash ones, turning its escaped braces
+/// `\{\{` and `\}\}` back into braces.
+fn rewrite(example: &str) -> String {
+    let mut out = String::new();
+    let mut rest = example;
+    while let Some(start) = rest.find("{{") {
+        let (before, after) = rest.split_at(start);
+        out.push_str(&unescape(before));
+        let Some(end) = after.find("}}") else {
+            out.push_str(&unescape(after));
+            return out;
+        };
+        let inner = &after[2..end];
+        match inner
+            .strip_prefix('[')
+            .and_then(|options| options.strip_suffix(']'))
+        {
+            Some(options) => out.push_str(options.rsplit('|').next().unwrap_or(options)),
+            None => out.push_str(&placeholder(&placeholder_name(inner), None, &[])),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(&unescape(rest));
+    out
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\{\\{", "\\{{").replace("\\}\\}", "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tldr() {
+        let text = "# tar\n\n> Archiving utility.\n> More information: <https://example.com>.\n\n\
+                    - Create an archive from files:\n\n\
+                    `tar {{[-c|--create]}} -f {{target.tar}} {{path/to/file1 path/to/file2 ...}}`\n\n\
+                    - Print a template:\n\n`echo '\\{\\{ name \\}\\}' > {{file}}`\n";
+        let snippets = TldrImporter
+            .parse(text, Path::new("pages/common/tar.md"))
+            .unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].title, "tar: Create an archive from files");
+        assert_eq!(
+            snippets[0].content,
+            "tar --create -f {{target_tar}} {{path_to_file1_path_to_file2}}"
+        );
+        assert_eq!(snippets[0].tags, vec!["tar"]);
+        assert_eq!(snippets[1].content, "echo '\\{{ name }}' > {{file}}");
+    }
+}
@@ -0,0 +1,323 @@
+//! VS Code snippet files: `*.code-snippets` and per-language `<lang>.json`.
+//!
+//! ```json
+//! {
+//!   "Print to console": {
+//!     "scope": "javascript,typescript",
+//!     "prefix": "log",
+//!     "body": ["console.log('${1:message}');", "$0"]
+//!   }
+//! }
+//! ```
+//!
+//! Each entry becomes a snippet titled by its name, tagged `lang/<language>`
+//! for each language of its scope, or of a per-language file, and aliased by
+//! its prefixes. Tabstops become placeholders named after their number:
+//! `$1` becomes `{{arg1}}`, `${1:message}` becomes `{{arg1:message}}` and
+//! `${1|a,b|}` becomes `{{arg1:a|a,b}}`. Variables such as `$TM_FILENAME`
+//! become placeholders of the same name, and the final cursor `$0` is
+//! dropped. Comments and trailing commas, which VS Code allows, are ignored.
+
+use super::{escape, has_extension, placeholder, ImportedSnippet, Importer};
+use crate::error::{Error, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Imports VS Code snippet files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VsCodeImporter;
+
+impl Importer for VsCodeImporter {
+    fn accepts(&self, path: &Path) -> bool {
+        has_extension(path, "code-snippets") || has_extension(path, "json")
+    }
+
+    fn parse(&self, text: &str, path: &Path) -> Result<Vec<ImportedSnippet>> {
+        let entries: serde_json::Map<String, Value> = serde_json::from_str(&strip_jsonc(text))
+            .map_err(|e| Error::validation(format!("Invalid VS Code snippets: {}", e)))?;
+        // A per-language file is named after its language
+        let file_language = path
+            .file_stem()
+            .filter(|_| has_extension(path, "json"))
+            .map(|stem| stem.to_string_lossy().into_owned());
+
+        let mut snippets = Vec::new();
+        for (name, entry) in entries {
+            let body = strings(&entry["body"]).join("\n");
+            if body.trim().is_empty() {
+                continue;
+            }
+            let scope = entry["scope"].as_str().unwrap_or_default();
+            let languages = scope
+                .split(',')
+                .map(str::trim)
+                .filter(|language| !language.is_empty())
+                .map(str::to_string)
+                .chain(file_language.clone());
+            snippets.push(ImportedSnippet {
+                title: name,
+                content: BodyParser::new(&body).parse_until(None).0,
+                tags: languages
+                    .map(|language| format!("lang/{}", language))
+                    .collect(),
+                aliases: strings(&entry["prefix"]),
+                source: path.to_path_buf(),
+            });
+        }
+        Ok(snippets)
+    }
+}
+
+/// A string, or the strings of an array.
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Remove comments and trailing commas from JSON.
+fn strip_jsonc(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut uncommented = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            uncommented.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                uncommented.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else {
+            in_string = c == '"';
+            uncommented.push(c);
+        }
+        i += 1;
+    }
+
+    let mut out = String::with_capacity(uncommented.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in uncommented.iter().enumerate() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == ',' {
+            let next = uncommented[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        } else {
+            in_string = c == '"';
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Rewrites a snippet body's TextMate syntax as rustash placeholders.
+struct BodyParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl BodyParser {
+    fn new(body: &str) -> Self {
+        Self {
+            chars: body.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Parse up to the closing `stop`, or the end, returning the rewritten
+    /// text and the text as it reads with every placeholder at its default.
+    fn parse_until(&mut self, stop: Option<char>) -> (String, String) {
+        let mut text = String::new();
+        let mut literal = String::new();
+        let mut plain = String::new();
+        while let Some(c) = self.peek() {
+            if Some(c) == stop {
+                self.pos += 1;
+                break;
+            }
+            self.pos += 1;
+            match c {
+                '\\' if matches!(self.peek(), Some('$' | '}' | '\\')) => {
+                    let escaped = self.chars[self.pos];
+                    self.pos += 1;
+                    literal.push(escaped);
+                    plain.push(escaped);
+                }
+                '$' => match self.dollar() {
+                    Some((placeholder, default)) => {
+                        text.push_str(&escape(&std::mem::take(&mut literal)));
+                        text.push_str(&placeholder);
+                        plain.push_str(&default);
+                    }
+                    None => {
+                        literal.push('$');
+                        plain.push('$');
+                    }
+                },
+                c => {
+                    literal.push(c);
+                    plain.push(c);
+                }
+            }
+        }
+        text.push_str(&escape(&literal));
+        (text, plain)
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&keep) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Parse what follows a `$`: the placeholder it becomes and its default,
+    /// or `None` when the `$` is literal.
+    fn dollar(&mut self) -> Option<(String, String)> {
+        let braced = self.peek() == Some('{');
+        let start = self.pos;
+        if braced {
+            self.pos += 1;
+        }
+        let name = match self.peek() {
+            Some(c) if c.is_ascii_digit() => self.take_while(|c| c.is_ascii_digit()),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+            }
+            _ => {
+                self.pos = start;
+                return None;
+            }
+        };
+        let name = match name.parse::<u32>() {
+            Ok(0) => String::new(),
+            Ok(number) => format!("arg{}", number),
+            Err(_) => name,
+        };
+
+        let (default, choices) = if !braced {
+            (String::new(), Vec::new())
+        } else {
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    (String::new(), Vec::new())
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    (self.parse_until(Some('}')).1, Vec::new())
+                }
+                Some('|') => {
+                    self.pos += 1;
+                    let list = self.take_while(|c| c != '|');
+                    self.pos += 1;
+                    if self.peek() == Some('}') {
+                        self.pos += 1;
+                    }
+                    let choices: Vec<String> = list.split(',').map(str::to_string).collect();
+                    (choices[0].clone(), choices)
+                }
+                // Transforms such as `${TM_FILENAME/(.*)/$1/}` keep the variable
+                Some('/') => {
+                    self.take_while(|c| c != '}');
+                    self.pos += 1;
+                    (String::new(), Vec::new())
+                }
+                _ => {
+                    self.pos = start;
+                    return None;
+                }
+            }
+        };
+
+        if name.is_empty() {
+            // The final cursor position
+            return Some((String::new(), default));
+        }
+        let default = default.replace(['|', '}'], "");
+        Some((placeholder(&name, Some(&default), &choices), default))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(text: &str) -> String {
+        BodyParser::new(text).parse_until(None).0
+    }
+
+    #[test]
+    fn test_body() {
+        assert_eq!(body("echo $1 ${2} $1"), "echo {{arg1}} {{arg2}} {{arg1}}");
+        assert_eq!(
+            body("ssh ${1:user}@${2:host $3}$0"),
+            "ssh {{arg1:user}}@{{arg2:host }}"
+        );
+        assert_eq!(body("${1|fast,slow|}"), "{{arg1:fast|fast,slow}}");
+        assert_eq!(
+            body("// $TM_FILENAME ${TM_LINE_NUMBER/(.*)/$1/}"),
+            "// {{TM_FILENAME}} {{TM_LINE_NUMBER}}"
+        );
+        assert_eq!(
+            body("cost: \\$5 and $ alone, {{x}}"),
+            "cost: $5 and $ alone, \\{{x}}"
+        );
+    }
+
+    #[test]
+    fn test_vscode() {
+        let text = r#"{
+            // A comment
+            "Print to console": {
+                "scope": "javascript,typescript",
+                "prefix": ["log", "cl"],
+                "body": ["console.log('${1:message}');", "$0"], /* trailing */
+            },
+            "Empty": { "body": [] },
+        }"#;
+        let snippets = VsCodeImporter
+            .parse(text, Path::new("mine.code-snippets"))
+            .unwrap();
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].title, "Print to console");
+        assert_eq!(snippets[0].content, "console.log('{{arg1:message}}');\n");
+        assert_eq!(snippets[0].tags, vec!["lang/javascript", "lang/typescript"]);
+        assert_eq!(snippets[0].aliases, vec!["log", "cl"]);
+
+        let text = r#"{ "Main": { "prefix": "main", "body": "fn main() {}" } }"#;
+        let snippets = VsCodeImporter.parse(text, Path::new("rust.json")).unwrap();
+        assert_eq!(snippets[0].tags, vec!["lang/rust"]);
+    }
+}
@@ -2,26 +2,40 @@
 #![recursion_limit = "8192"]
 
 pub mod config;
+pub mod context;
+pub mod copy;
 pub mod database;
+pub mod dump;
 pub mod error;
+pub mod export;
 pub mod graph;
+pub mod history;
+pub mod import;
 pub mod memory;
 pub mod models;
+pub mod placeholder;
 pub mod rag;
 pub mod schema;
 pub mod snippet;
 pub mod stash;
 pub mod storage;
+pub mod sync;
+pub mod usage;
+pub mod vector;
 
 #[cfg(feature = "vector-search")]
 pub mod search;
 
 // Re-export commonly used types
+pub use context::ProjectContext;
 pub use error::{Error, Result};
-pub use memory::MemoryItem;
+pub use history::{Version, VersionDiff};
+pub use memory::{register_item_type, MemoryItem, StoredItem};
 pub use models::{NewDbSnippet, Snippet, SnippetWithTags};
+pub use placeholder::{Placeholder, Template, ValueKind};
 pub use stash::{ServiceType, Stash, StashConfig};
 pub use storage::{InMemoryBackend, StorageBackend};
+pub use usage::{Usage, UsageStats};
 
 #[cfg(feature = "postgres")]
 pub use storage::postgres::PostgresBackend;
@@ -29,10 +43,13 @@ pub use storage::postgres::PostgresBackend;
 #[cfg(feature = "sqlite")]
 pub use storage::sqlite::SqliteBackend;
 
-pub use snippet::{expand_placeholders, validate_snippet_content, SnippetService};
+pub use snippet::{
+    alias_tag, expand_placeholders, parse_front_matter, to_front_matter, validate_snippet_content,
+    SnippetService, ALIAS_TAG_PREFIX, DANGEROUS_TAG,
+};
 
 #[cfg(feature = "vector-search")]
-pub use search::search_similar_snippets;
+pub use search::{search_similar_snippets, HnswIndex, IndexedBackend};
 
 /// Create a new storage backend dynamically based on the database URL.
 pub async fn create_backend(database_url: &str) -> Result<Box<dyn StorageBackend>> {
@@ -56,11 +73,22 @@ pub async fn create_backend(database_url: &str) -> Result<Box<dyn StorageBackend
         #[cfg(feature = "sqlite")]
         {
             let pool = crate::database::sqlite_pool::create_pool(database_url).await?;
-            Ok(Box::new(SqliteBackend::new(pool)))
+            let backend: Box<dyn StorageBackend> = Box::new(SqliteBackend::new(pool));
+
+            // File-backed stashes keep an HNSW index next to the database.
+            #[cfg(feature = "vector-search")]
+            if let Some(index_path) = search::index_path_for(database_url) {
+                return Ok(Box::new(IndexedBackend::open(backend, index_path).await?));
+            }
+
+            Ok(backend)
         }
+    } else if database_url.starts_with("memory://") {
+        // Ephemeral stash: contents live only as long as the process.
+        Ok(Box::new(InMemoryBackend::new()))
     } else {
         Err(crate::error::Error::other(
-            "Unsupported database URL scheme. Use 'sqlite://' or 'postgres://'.",
+            "Unsupported database URL scheme. Use 'sqlite://', 'postgres://' or 'memory://'.",
         ))
     }
 }
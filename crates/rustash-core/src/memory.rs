@@ -1,10 +1,18 @@
 //! Core memory item trait for Rustash storage system.
+//!
+//! Besides the [`MemoryItem`] trait itself, this module hosts the item type
+//! registry. Storage backends persist every item as a generic [`StoredItem`]
+//! row and use the registry to turn that row back into the concrete type that
+//! was saved, keyed by [`MemoryItem::item_type`].
 
+use crate::error::{Error, Result};
 use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::any::Any;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::{OnceLock, RwLock};
 use uuid::Uuid;
 
 /// The core trait for any piece of information stored in Rustash.
@@ -27,6 +35,9 @@ pub trait MemoryItem: erased_serde::Serialize + Send + Sync + fmt::Debug + Any +
     /// Returns when this memory item was last updated
     fn updated_at(&self) -> DateTime<Utc>;
 
+    /// Sets when this memory item was last updated
+    fn set_updated_at(&mut self, updated_at: DateTime<Utc>);
+
     /// Create a boxed clone of this memory item
     fn clone_dyn(&self) -> Box<dyn MemoryItem>;
 
@@ -35,6 +46,11 @@ pub trait MemoryItem: erased_serde::Serialize + Send + Sync + fmt::Debug + Any +
 
     /// Returns a reference to the Any trait to allow for downcasting
     fn as_any(&self) -> &dyn Any;
+
+    /// Returns the serialized vector embedding of this memory item, if any
+    fn embedding(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
 // This allows us to serialize a `Box<dyn MemoryItem>`
@@ -66,6 +82,10 @@ impl MemoryItem for Box<dyn MemoryItem> {
         (**self).updated_at()
     }
 
+    fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+        (**self).set_updated_at(updated_at)
+    }
+
     fn clone_dyn(&self) -> Box<dyn MemoryItem> {
         (**self).clone_dyn()
     }
@@ -77,6 +97,10 @@ impl MemoryItem for Box<dyn MemoryItem> {
     fn as_any(&self) -> &dyn std::any::Any {
         (**self).as_any()
     }
+
+    fn embedding(&self) -> Option<&[u8]> {
+        (**self).embedding()
+    }
 }
 
 // Implement Clone for Box<dyn MemoryItem>
@@ -93,11 +117,137 @@ impl Clone for Box<dyn MemoryItem + Send + Sync> {
     }
 }
 
+/// A memory item as persisted by a storage backend, independent of its concrete type.
+///
+/// `title` and `tags` are lifted out of [`MemoryItem::metadata`] so that backends can
+/// index and filter on them; everything else a type needs to rebuild itself lives in
+/// `metadata`, as produced by its registered [`ItemCodec`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredItem {
+    pub id: Uuid,
+    pub item_type: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub metadata: Value,
+    pub embedding: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Functions used to convert an item type to and from a [`StoredItem`].
+#[derive(Debug, Clone, Copy)]
+pub struct ItemCodec {
+    /// Serialize the type-specific state of an item into the `metadata` JSON column
+    pub encode: fn(&(dyn MemoryItem + Send + Sync)) -> Result<Value>,
+    /// Rebuild the concrete item from a stored row
+    pub decode: fn(StoredItem) -> Result<Box<dyn MemoryItem + Send + Sync>>,
+}
+
+type Registry = RwLock<HashMap<&'static str, ItemCodec>>;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(|| {
+        let mut codecs = HashMap::new();
+        codecs.insert(
+            crate::models::SNIPPET_ITEM_TYPE,
+            ItemCodec {
+                encode: |_| Ok(Value::Object(Default::default())),
+                decode: |row| Ok(Box::new(crate::models::SnippetWithTags::from(row))),
+            },
+        );
+        RwLock::new(codecs)
+    })
+}
+
+fn codec_for(item_type: &str) -> Result<ItemCodec> {
+    registry()
+        .read()
+        .map_err(|_| Error::other("RwLock poisoned"))?
+        .get(item_type)
+        .copied()
+        .ok_or_else(|| Error::other(format!("Unknown item type '{}'", item_type)))
+}
+
+/// Register a custom codec for the given item type, replacing any existing one.
+pub fn register_item_codec(item_type: &'static str, codec: ItemCodec) -> Result<()> {
+    registry()
+        .write()
+        .map_err(|_| Error::other("RwLock poisoned"))?
+        .insert(item_type, codec);
+    Ok(())
+}
+
+/// Register an item type that is stored as its full serde representation.
+///
+/// The item is serialized into the `metadata` column on save and deserialized
+/// from it when it is read back, so `T` must round-trip through JSON.
+pub fn register_item_type<T>(item_type: &'static str) -> Result<()>
+where
+    T: MemoryItem + Serialize + DeserializeOwned,
+{
+    register_item_codec(
+        item_type,
+        ItemCodec {
+            encode: |item| Ok(serde_json::to_value(item)?),
+            decode: |row| Ok(Box::new(serde_json::from_value::<T>(row.metadata)?)),
+        },
+    )
+}
+
+/// Check whether a codec is registered for the given item type.
+pub fn is_registered(item_type: &str) -> bool {
+    codec_for(item_type).is_ok()
+}
+
+/// The `title` and `tags` an item exposes through its metadata, if any.
+pub(crate) fn title_and_tags(item: &(dyn MemoryItem + Send + Sync)) -> (String, Vec<String>) {
+    let metadata = item.metadata();
+
+    let title = metadata
+        .get("title")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let tags = metadata
+        .get("tags")
+        .and_then(|tags| serde_json::from_value(tags.clone()).ok())
+        .unwrap_or_default();
+
+    (title, tags)
+}
+
+/// Convert an item into the generic row representation used by storage backends.
+pub fn encode_item(item: &(dyn MemoryItem + Send + Sync)) -> Result<StoredItem> {
+    let codec = codec_for(item.item_type())?;
+    let (title, tags) = title_and_tags(item);
+
+    Ok(StoredItem {
+        id: item.id(),
+        item_type: item.item_type().to_string(),
+        title,
+        content: item.content().to_string(),
+        tags,
+        metadata: (codec.encode)(item)?,
+        embedding: item.embedding().map(<[u8]>::to_vec),
+        created_at: item.created_at(),
+        updated_at: item.updated_at(),
+    })
+}
+
+/// Rebuild the concrete item type from a stored row.
+pub fn decode_item(row: StoredItem) -> Result<Box<dyn MemoryItem + Send + Sync>> {
+    let codec = codec_for(&row.item_type)?;
+    (codec.decode)(row)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[derive(Debug, Clone, serde::Serialize)]
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
     struct TestMemory {
         id: Uuid,
         content: String,
@@ -124,6 +274,9 @@ mod tests {
         fn updated_at(&self) -> DateTime<Utc> {
             self.updated_at
         }
+        fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+            self.updated_at = updated_at;
+        }
 
         fn clone_dyn(&self) -> Box<dyn MemoryItem> {
             Box::new(self.clone())
@@ -168,4 +321,58 @@ mod tests {
         assert_eq!(test_item.created_at(), now);
         assert_eq!(test_item.updated_at(), now);
     }
+
+    #[test]
+    fn test_registry_round_trip() {
+        register_item_type::<TestMemory>("test").unwrap();
+        assert!(is_registered("test"));
+
+        let item = TestMemory::new("Registered content");
+        let row = encode_item(&item).unwrap();
+        assert_eq!(row.item_type, "test");
+        assert_eq!(row.content, "Registered content");
+        assert!(row.tags.is_empty());
+
+        let decoded = decode_item(row).unwrap();
+        let decoded = decoded.as_any().downcast_ref::<TestMemory>().unwrap();
+        assert_eq!(decoded.id, item.id);
+        assert_eq!(decoded.content, item.content);
+    }
+
+    #[test]
+    fn test_snippets_are_registered_by_default() {
+        let snippet = crate::models::SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Title".to_string(),
+            "echo hi".to_string(),
+            vec!["shell".to_string()],
+        );
+        let row = encode_item(&snippet).unwrap();
+        assert_eq!(row.title, "Title");
+        assert_eq!(row.tags, vec!["shell"]);
+
+        let decoded = decode_item(row).unwrap();
+        let decoded = decoded
+            .as_any()
+            .downcast_ref::<crate::models::SnippetWithTags>()
+            .unwrap();
+        assert_eq!(decoded.id, snippet.id);
+        assert_eq!(decoded.content, "echo hi");
+    }
+
+    #[test]
+    fn test_unknown_item_type_is_rejected() {
+        let row = StoredItem {
+            id: Uuid::new_v4(),
+            item_type: "does-not-exist".to_string(),
+            title: String::new(),
+            content: String::new(),
+            tags: Vec::new(),
+            metadata: Value::Null,
+            embedding: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(decode_item(row).is_err());
+    }
 }
@@ -1,29 +1,59 @@
 //! Data models for Rustash
 
-use crate::memory::MemoryItem;
-use crate::schema::snippets;
+use crate::error::{Error, Result};
+use crate::history::Version;
+use crate::memory::{decode_item, MemoryItem, StoredItem};
+use crate::schema::{relations, snippet_runs, snippet_usage, snippet_versions, snippets};
+use crate::storage::sort::{Cursor, SortSpec};
+use crate::usage::{Usage, UsageStats, FRECENCY_SORT};
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use diesel::prelude::*;
-use diesel::sql_types::{Text, Timestamp};
+use diesel::sql_types::{BigInt, Text, Timestamp};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt;
 use uuid::Uuid;
 
+/// The `item_type` tag used for snippets
+pub const SNIPPET_ITEM_TYPE: &str = "snippet";
+
 /// Query parameters for searching snippets
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Query {
-    /// Text to search for in title or content
+    /// Text to search for in title or content.
+    ///
+    /// Backends with a full-text index treat this as a match expression
+    /// (FTS5 syntax on SQLite) and order results by relevance.
     pub text_filter: Option<String>,
-    /// Tags to filter by
+    /// Tags every result must carry; shorthand for `tag_filter.all`
     pub tags: Option<Vec<String>>,
+    /// Required, alternative and excluded tags
+    #[serde(default)]
+    pub tag_filter: TagFilter,
     /// Maximum number of results to return
     pub limit: Option<usize>,
-    /// Optional field to control sorting ("title", "created_at", "updated_at")
+    /// Sort keys, e.g. `"updated_at:desc,title"` (see [`SortSpec`]), or
+    /// `"frecency"` for the most used first (see [`crate::usage`]).
+    ///
+    /// Without it results are oldest first, or most relevant first when
+    /// `text_filter` is set.
     pub sort_by: Option<String>,
     /// Content to search for (alternative to text_filter for backward compatibility)
     pub content: Option<String>,
+    /// Only return items of this type (see `MemoryItem::item_type`)
+    pub item_type: Option<String>,
+    /// Number of results to skip
+    pub offset: Option<usize>,
+    /// Only return results after this cursor (see [`Page::next_cursor`])
+    pub after: Option<String>,
+    /// Tags whose items rank ahead of the rest, otherwise keeping their
+    /// order; a pattern ending in `*` matches by prefix
+    #[serde(default)]
+    pub boost_tags: Vec<String>,
+    /// The project the query runs in: items scoped to other projects with a
+    /// `project:<name>` tag are left out (see [`crate::context`])
+    pub project: Option<String>,
 }
 
 impl Query {
@@ -48,6 +78,280 @@ impl Query {
         self.limit = Some(limit);
         self
     }
+
+    /// Restrict the results to a single item type
+    pub fn with_item_type(mut self, item_type: &str) -> Self {
+        self.item_type = Some(item_type.to_string());
+        self
+    }
+
+    /// Set the sort keys
+    pub fn with_sort(mut self, sort_by: &str) -> Self {
+        self.sort_by = Some(sort_by.to_string());
+        self
+    }
+
+    /// Skip the first `offset` results
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Continue after a cursor returned with a previous page
+    pub fn with_cursor(mut self, cursor: &str) -> Self {
+        self.after = Some(cursor.to_string());
+        self
+    }
+
+    /// Require at least one of these tags
+    pub fn with_any_tags(mut self, tags: Vec<String>) -> Self {
+        self.tag_filter.any = tags;
+        self
+    }
+
+    /// Exclude items carrying any of these tags
+    pub fn without_tags(mut self, tags: Vec<String>) -> Self {
+        self.tag_filter.none = tags;
+        self
+    }
+
+    /// The complete tag filter, with `tags` folded into `all`.
+    pub fn effective_tag_filter(&self) -> TagFilter {
+        let mut filter = self.tag_filter.clone();
+        filter.all.extend(self.tags.iter().flatten().cloned());
+        filter
+    }
+
+    /// Whether results are ordered by frecency rather than by sort keys.
+    pub fn is_frecency_sorted(&self) -> bool {
+        self.sort_by.as_deref().map(str::trim) == Some(FRECENCY_SORT)
+    }
+
+    /// Whether results are reordered after the backend runs the query,
+    /// by frecency, boosted tags or project scope.
+    pub fn needs_ranking(&self) -> bool {
+        self.is_frecency_sorted() || !self.boost_tags.is_empty() || self.project.is_some()
+    }
+
+    /// The ordering to apply, or `None` when results are ranked by relevance,
+    /// frecency or the project context.
+    pub fn sort_spec(&self) -> Result<Option<SortSpec>> {
+        if self.needs_ranking() {
+            return Ok(None);
+        }
+        match (&self.sort_by, &self.text_filter) {
+            (Some(sort_by), _) => Ok(Some(SortSpec::parse(sort_by)?)),
+            (None, Some(text)) if !text.trim().is_empty() => Ok(None),
+            (None, _) => Ok(Some(SortSpec::default())),
+        }
+    }
+
+    /// The ordering to apply when paging with `after`.
+    ///
+    /// Relevance has no stable key to resume from, so a cursor on a text
+    /// search needs an explicit `sort_by`.
+    pub(crate) fn keyset_spec(&self) -> Result<Option<(SortSpec, Cursor)>> {
+        let Some(after) = &self.after else {
+            return Ok(None);
+        };
+        let spec = self.sort_spec()?.ok_or_else(|| {
+            Error::validation("Paging a text search with a cursor requires a sort order")
+        })?;
+        let cursor = spec.decode_cursor(after)?;
+        Ok(Some((spec, cursor)))
+    }
+}
+
+/// A boolean filter over item tags.
+///
+/// Every non-empty list must hold: an item matches when it carries all of
+/// `all`, at least one of `any` and none of `none`. A pattern ending in `*`
+/// matches by prefix, so `lang/*` matches `lang/rust` and `lang/go`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// Tags that must all be present
+    #[serde(default)]
+    pub all: Vec<String>,
+    /// Tags of which at least one must be present
+    #[serde(default)]
+    pub any: Vec<String>,
+    /// Tags that must not be present
+    #[serde(default)]
+    pub none: Vec<String>,
+}
+
+/// A single tag pattern of a [`TagFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TagPattern<'a> {
+    Exact(&'a str),
+    Prefix(&'a str),
+}
+
+impl<'a> TagPattern<'a> {
+    fn parse(pattern: &'a str) -> Self {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => Self::Prefix(prefix),
+            None => Self::Exact(pattern),
+        }
+    }
+
+    fn matches(self, tag: &str) -> bool {
+        match self {
+            Self::Exact(exact) => tag == exact,
+            Self::Prefix(prefix) => tag.starts_with(prefix),
+        }
+    }
+}
+
+impl TagFilter {
+    /// Whether the filter accepts everything.
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty() && self.any.is_empty() && self.none.is_empty()
+    }
+
+    /// Whether an item carrying `tags` passes the filter.
+    pub fn matches(&self, tags: &[String]) -> bool {
+        let has = |pattern: &String| {
+            let pattern = TagPattern::parse(pattern);
+            tags.iter().any(|tag| pattern.matches(tag))
+        };
+        self.all.iter().all(has)
+            && (self.any.is_empty() || self.any.iter().any(has))
+            && !self.none.iter().any(has)
+    }
+
+    /// Build a SQL condition from `has_tag`, which renders a condition that
+    /// holds when an item carries a tag matching the pattern.
+    pub(crate) fn to_sql(&self, has_tag: impl Fn(TagPattern) -> String) -> Option<String> {
+        let render = |patterns: &[String], joiner: &str| {
+            patterns
+                .iter()
+                .map(|pattern| has_tag(TagPattern::parse(pattern)))
+                .collect::<Vec<_>>()
+                .join(joiner)
+        };
+
+        let mut conditions = Vec::new();
+        if !self.all.is_empty() {
+            conditions.push(render(&self.all, " AND "));
+        }
+        if !self.any.is_empty() {
+            conditions.push(format!("({})", render(&self.any, " OR ")));
+        }
+        if !self.none.is_empty() {
+            conditions.push(format!("NOT ({})", render(&self.none, " OR ")));
+        }
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(format!("({})", conditions.join(" AND ")))
+        }
+    }
+}
+
+/// One page of query results
+#[derive(Debug)]
+pub struct Page {
+    /// The items on this page, in query order
+    pub items: Vec<Box<dyn MemoryItem + Send + Sync>>,
+    /// Pass as [`Query::after`] to fetch the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// An item in the trash
+#[derive(Debug)]
+pub struct TrashedItem {
+    /// The item as it was when deleted
+    pub item: Box<dyn MemoryItem + Send + Sync>,
+    /// When the item was moved to the trash
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A directed, typed link from one item to another
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Relation {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub relation_type: String,
+}
+
+/// One recorded execution of an item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Run {
+    /// The item that was run
+    pub item_id: Uuid,
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// The working directory it ran in, if known
+    pub cwd: Option<String>,
+    /// The exit code, or `None` when the process was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+impl Run {
+    /// Start a run of `item_id` now, in the current working directory.
+    ///
+    /// The exit code is left unset until the process finishes.
+    pub fn start(item_id: Uuid) -> Self {
+        Self {
+            item_id,
+            started_at: Utc::now(),
+            cwd: std::env::current_dir()
+                .ok()
+                .map(|dir| dir.display().to_string()),
+            exit_code: None,
+        }
+    }
+}
+
+/// Marks the start of a matched term in [`SearchHit`] highlights
+pub const HIGHLIGHT_START: &str = "\u{2}";
+/// Marks the end of a matched term in [`SearchHit`] highlights
+pub const HIGHLIGHT_END: &str = "\u{3}";
+
+/// A ranked result of a full-text search
+#[derive(Debug)]
+pub struct SearchHit {
+    /// The matching item
+    pub item: Box<dyn MemoryItem + Send + Sync>,
+    /// Relevance score; higher is more relevant
+    pub score: f32,
+    /// The title with matched terms wrapped in the highlight markers
+    pub highlighted_title: Option<String>,
+    /// A short excerpt around the best match, with matched terms wrapped in
+    /// the highlight markers
+    pub excerpt: Option<String>,
+}
+
+impl SearchHit {
+    /// Wrap an item that was matched without ranking information
+    pub fn unranked(item: Box<dyn MemoryItem + Send + Sync>) -> Self {
+        Self {
+            item,
+            score: 0.0,
+            highlighted_title: None,
+            excerpt: None,
+        }
+    }
+
+    /// The excerpt with the highlight markers replaced by `start` and `end`
+    pub fn excerpt_with(&self, start: &str, end: &str) -> Option<String> {
+        self.excerpt
+            .as_deref()
+            .map(|excerpt| replace_highlights(excerpt, start, end))
+    }
+
+    /// The highlighted title with the markers replaced by `start` and `end`
+    pub fn title_with(&self, start: &str, end: &str) -> Option<String> {
+        self.highlighted_title
+            .as_deref()
+            .map(|title| replace_highlights(title, start, end))
+    }
+}
+
+fn replace_highlights(text: &str, start: &str, end: &str) -> String {
+    text.replace(HIGHLIGHT_START, start)
+        .replace(HIGHLIGHT_END, end)
 }
 
 /// A snippet stored in the database
@@ -65,6 +369,9 @@ pub struct DbSnippet {
     pub embedding: Option<Vec<u8>>, // Vector embedding as binary
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub item_type: String,
+    pub metadata: String,                  // JSON object stored as string
+    pub deleted_at: Option<NaiveDateTime>, // Set while the item is in the trash
 }
 
 /// A new snippet to be inserted into the database
@@ -76,6 +383,84 @@ pub struct NewDbSnippet {
     pub content: String,
     pub tags: String, // JSON array stored as string
     pub embedding: Option<Vec<u8>>,
+    pub item_type: String,
+    pub metadata: String, // JSON object stored as string
+}
+
+/// A saved version of an item as stored in the database
+#[derive(Queryable, Selectable, Debug, Clone, PartialEq)]
+#[diesel(table_name = crate::schema::snippet_versions)]
+#[cfg_attr(feature = "sqlite", diesel(check_for_backend(diesel::sqlite::Sqlite)))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct DbVersion {
+    pub snippet_uuid: String,
+    pub version: i32,
+    pub title: String,
+    pub content: String,
+    pub tags: String, // JSON array stored as string
+    pub author: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A new version to be inserted into the database
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = snippet_versions)]
+pub struct NewDbVersion {
+    pub snippet_uuid: String,
+    pub version: i32,
+    pub title: String,
+    pub content: String,
+    pub tags: String, // JSON array stored as string
+    pub author: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// A recorded run as stored in the database
+#[derive(Queryable, Selectable, Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = snippet_runs)]
+#[cfg_attr(feature = "sqlite", diesel(check_for_backend(diesel::sqlite::Sqlite)))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct DbRun {
+    pub snippet_uuid: String,
+    pub started_at: NaiveDateTime,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+}
+
+/// A relation as stored in the database
+#[derive(Queryable, QueryableByName, Selectable, Debug, Clone, PartialEq)]
+#[diesel(table_name = relations)]
+#[cfg_attr(feature = "sqlite", diesel(check_for_backend(diesel::sqlite::Sqlite)))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct DbRelation {
+    pub from_uuid: String,
+    pub to_uuid: String,
+    pub relation_type: String,
+}
+
+/// A recorded use as stored in the database
+#[derive(Queryable, Selectable, Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = snippet_usage)]
+#[cfg_attr(feature = "sqlite", diesel(check_for_backend(diesel::sqlite::Sqlite)))]
+#[cfg_attr(feature = "postgres", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct DbUsage {
+    pub snippet_uuid: String,
+    pub used_at: NaiveDateTime,
+    pub cwd: Option<String>,
+    pub repo: Option<String>,
+}
+
+/// Usage statistics of one item, as summarised by the database
+#[derive(QueryableByName, Debug, Clone, PartialEq)]
+pub struct DbUsageStats {
+    #[diesel(sql_type = Text)]
+    pub snippet_uuid: String,
+    #[diesel(sql_type = BigInt)]
+    pub uses: i64,
+    #[diesel(sql_type = Timestamp)]
+    pub last_used_at: NaiveDateTime,
+    #[diesel(sql_type = BigInt)]
+    pub frecency: i64,
 }
 
 /// A lightweight representation of a snippet for list views
@@ -121,7 +506,7 @@ impl MemoryItem for SnippetWithTags {
     }
 
     fn item_type(&self) -> &'static str {
-        "snippet"
+        SNIPPET_ITEM_TYPE
     }
 
     fn content(&self) -> &str {
@@ -152,6 +537,10 @@ impl MemoryItem for SnippetWithTags {
         self.updated_at
     }
 
+    fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+        self.updated_at = updated_at;
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -163,6 +552,10 @@ impl MemoryItem for SnippetWithTags {
     fn clone_dyn_send_sync(&self) -> Box<dyn MemoryItem + Send + Sync> {
         Box::new(self.clone())
     }
+
+    fn embedding(&self) -> Option<&[u8]> {
+        self.embedding.as_deref()
+    }
 }
 
 impl SnippetWithTags {
@@ -261,7 +654,7 @@ impl MemoryItem for Snippet {
     }
 
     fn item_type(&self) -> &'static str {
-        "snippet"
+        SNIPPET_ITEM_TYPE
     }
 
     fn content(&self) -> &str {
@@ -303,6 +696,10 @@ impl MemoryItem for Snippet {
         Utc.from_utc_datetime(&self.updated_at)
     }
 
+    fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+        self.updated_at = updated_at.naive_utc();
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -314,6 +711,10 @@ impl MemoryItem for Snippet {
     fn clone_dyn_send_sync(&self) -> Box<dyn MemoryItem + Send + Sync> {
         Box::new(self.clone())
     }
+
+    fn embedding(&self) -> Option<&[u8]> {
+        self.embedding.as_deref()
+    }
 }
 
 // Conversion implementations
@@ -340,6 +741,67 @@ impl From<Snippet> for NewDbSnippet {
             content: snippet.content,
             tags: snippet.tags, // Already in JSON string format
             embedding: snippet.embedding,
+            item_type: SNIPPET_ITEM_TYPE.to_string(),
+            metadata: "{}".to_string(),
+        }
+    }
+}
+
+impl From<DbSnippet> for StoredItem {
+    fn from(db_snippet: DbSnippet) -> Self {
+        Self {
+            id: Uuid::parse_str(&db_snippet.uuid).unwrap_or_else(|_| Uuid::new_v4()),
+            item_type: db_snippet.item_type,
+            title: db_snippet.title,
+            content: db_snippet.content,
+            tags: serde_json::from_str(&db_snippet.tags).unwrap_or_default(),
+            metadata: serde_json::from_str(&db_snippet.metadata).unwrap_or_default(),
+            embedding: db_snippet.embedding,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(db_snippet.created_at, Utc),
+            updated_at: DateTime::<Utc>::from_naive_utc_and_offset(db_snippet.updated_at, Utc),
+        }
+    }
+}
+
+impl TryFrom<DbSnippet> for TrashedItem {
+    type Error = Error;
+
+    fn try_from(db_snippet: DbSnippet) -> Result<Self> {
+        let deleted_at = db_snippet
+            .deleted_at
+            .ok_or_else(|| Error::other(format!("Item {} is not in the trash", db_snippet.uuid)))?;
+        Ok(Self {
+            item: decode_item(db_snippet.into())?,
+            deleted_at: DateTime::<Utc>::from_naive_utc_and_offset(deleted_at, Utc),
+        })
+    }
+}
+
+impl From<&StoredItem> for NewDbSnippet {
+    fn from(item: &StoredItem) -> Self {
+        Self {
+            uuid: item.id.to_string(),
+            title: item.title.clone(),
+            content: item.content.clone(),
+            tags: serde_json::to_string(&item.tags).unwrap_or_else(|_| "[]".to_string()),
+            embedding: item.embedding.clone(),
+            item_type: item.item_type.clone(),
+            metadata: item.metadata.to_string(),
+        }
+    }
+}
+
+impl From<StoredItem> for SnippetWithTags {
+    fn from(item: StoredItem) -> Self {
+        Self {
+            uuid: item.id.to_string(),
+            id: item.id,
+            title: item.title,
+            content: item.content,
+            tags: item.tags,
+            embedding: item.embedding,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
         }
     }
 }
@@ -380,6 +842,88 @@ impl From<Snippet> for SnippetWithTags {
     }
 }
 
+impl From<DbVersion> for Version {
+    fn from(db_version: DbVersion) -> Self {
+        Self {
+            item_id: Uuid::parse_str(&db_version.snippet_uuid).unwrap_or_else(|_| Uuid::nil()),
+            number: db_version.version.max(0) as u32,
+            title: db_version.title,
+            content: db_version.content,
+            tags: serde_json::from_str(&db_version.tags).unwrap_or_default(),
+            author: db_version.author,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(db_version.created_at, Utc),
+        }
+    }
+}
+
+impl From<&Version> for NewDbVersion {
+    fn from(version: &Version) -> Self {
+        Self {
+            snippet_uuid: version.item_id.to_string(),
+            version: version.number as i32,
+            title: version.title.clone(),
+            content: version.content.clone(),
+            tags: serde_json::to_string(&version.tags).unwrap_or_else(|_| "[]".to_string()),
+            author: version.author.clone(),
+            created_at: version.created_at.naive_utc(),
+        }
+    }
+}
+
+impl From<DbRun> for Run {
+    fn from(db_run: DbRun) -> Self {
+        Self {
+            item_id: Uuid::parse_str(&db_run.snippet_uuid).unwrap_or_else(|_| Uuid::nil()),
+            started_at: DateTime::<Utc>::from_naive_utc_and_offset(db_run.started_at, Utc),
+            cwd: db_run.cwd,
+            exit_code: db_run.exit_code,
+        }
+    }
+}
+
+impl From<DbRelation> for Relation {
+    fn from(db_relation: DbRelation) -> Self {
+        Self {
+            from: Uuid::parse_str(&db_relation.from_uuid).unwrap_or_else(|_| Uuid::nil()),
+            to: Uuid::parse_str(&db_relation.to_uuid).unwrap_or_else(|_| Uuid::nil()),
+            relation_type: db_relation.relation_type,
+        }
+    }
+}
+
+impl From<&Run> for DbRun {
+    fn from(run: &Run) -> Self {
+        Self {
+            snippet_uuid: run.item_id.to_string(),
+            started_at: run.started_at.naive_utc(),
+            cwd: run.cwd.clone(),
+            exit_code: run.exit_code,
+        }
+    }
+}
+
+impl From<&Usage> for DbUsage {
+    fn from(usage: &Usage) -> Self {
+        Self {
+            snippet_uuid: usage.item_id.to_string(),
+            used_at: usage.used_at.naive_utc(),
+            cwd: usage.cwd.clone(),
+            repo: usage.repo.clone(),
+        }
+    }
+}
+
+impl From<DbUsageStats> for UsageStats {
+    fn from(stats: DbUsageStats) -> Self {
+        Self {
+            item_id: Uuid::parse_str(&stats.snippet_uuid).unwrap_or_else(|_| Uuid::nil()),
+            count: stats.uses.max(0) as u64,
+            last_used_at: DateTime::<Utc>::from_naive_utc_and_offset(stats.last_used_at, Utc),
+            frecency: stats.frecency.max(0) as u64,
+        }
+    }
+}
+
 impl From<DbSnippet> for SnippetListItem {
     fn from(snippet: DbSnippet) -> Self {
         Self {
@@ -402,6 +946,8 @@ impl NewDbSnippet {
             content,
             tags: tags_json,
             embedding: None,
+            item_type: SNIPPET_ITEM_TYPE.to_string(),
+            metadata: "{}".to_string(),
         }
     }
 
@@ -420,6 +966,8 @@ impl NewDbSnippet {
             content,
             tags: tags_json,
             embedding: Some(embedding),
+            item_type: SNIPPET_ITEM_TYPE.to_string(),
+            metadata: "{}".to_string(),
         }
     }
 }
@@ -477,6 +1025,32 @@ impl UpdateSnippet {
         self.embedding = Some(embedding);
         self
     }
+
+    /// Whether the update leaves every field unchanged
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.content.is_none()
+            && self.tags.is_none()
+            && self.embedding.is_none()
+    }
+
+    /// Copy the set fields onto `snippet` and stamp it with `updated_at`
+    pub fn apply(&self, snippet: &mut SnippetWithTags) -> Result<()> {
+        if let Some(title) = &self.title {
+            snippet.title = title.clone();
+        }
+        if let Some(content) = &self.content {
+            snippet.content = content.clone();
+        }
+        if let Some(tags) = &self.tags {
+            snippet.tags = serde_json::from_str(tags)?;
+        }
+        if let Some(embedding) = &self.embedding {
+            snippet.embedding = embedding.clone();
+        }
+        snippet.updated_at = DateTime::<Utc>::from_naive_utc_and_offset(self.updated_at, Utc);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -513,6 +1087,9 @@ mod tests {
             embedding: None,
             created_at: now,
             updated_at: now,
+            item_type: SNIPPET_ITEM_TYPE.to_string(),
+            metadata: "{}".to_string(),
+            deleted_at: None,
         };
 
         let snippet_with_tags: SnippetWithTags = db_snippet.into();
@@ -529,4 +1106,31 @@ mod tests {
             NewDbSnippet::new("A".to_string(), "B".to_string(), vec!["C".to_string()]);
         assert_eq!(new_snippet.tags, "[\"C\"]");
     }
+
+    #[test]
+    fn test_tag_filter() {
+        let tags = vec!["lang/rust".to_string(), "cli".to_string()];
+        let filter = |all: &[&str], any: &[&str], none: &[&str]| TagFilter {
+            all: all.iter().map(|tag| tag.to_string()).collect(),
+            any: any.iter().map(|tag| tag.to_string()).collect(),
+            none: none.iter().map(|tag| tag.to_string()).collect(),
+        };
+
+        assert!(filter(&[], &[], &[]).matches(&tags));
+        assert!(filter(&["cli", "lang/*"], &[], &[]).matches(&tags));
+        assert!(!filter(&["cli", "web"], &[], &[]).matches(&tags));
+        assert!(filter(&[], &["web", "cli"], &[]).matches(&tags));
+        assert!(!filter(&[], &["web", "lang/go"], &[]).matches(&tags));
+        assert!(!filter(&[], &[], &["lang/*"]).matches(&tags));
+
+        let sql = filter(&["a"], &["b", "c*"], &["d"]).to_sql(|pattern| match pattern {
+            TagPattern::Exact(tag) => format!("has({tag})"),
+            TagPattern::Prefix(prefix) => format!("starts({prefix})"),
+        });
+        assert_eq!(
+            sql.as_deref(),
+            Some("(has(a) AND (has(b) OR starts(c)) AND NOT (has(d)))")
+        );
+        assert_eq!(filter(&[], &[], &[]).to_sql(|_| unreachable!()), None);
+    }
 }
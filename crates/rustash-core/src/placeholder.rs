@@ -0,0 +1,971 @@
+//! Placeholders, includes and blocks in snippet content.
+//!
+//! A placeholder is written `{{name}}` and may declare more about the value
+//! it expects:
+//!
+//! - `{{name:default}}` falls back to `default` when no value is given
+//! - `{{name:int}}`, `{{name:path}}`, `{{name:bool}}` and `{{name:str}}`
+//!   check the value's type; `{{name:int=8080}}` adds a default
+//! - `{{mode|fast,slow}}` only accepts one of the listed choices, and
+//!   `{{mode:slow|fast,slow}}` picks one by default
+//! - `{{env:HOME}}` defaults to the `HOME` environment variable
+//!
+//! Snippets can also be composed:
+//!
+//! - `{{> reference}}` includes another snippet, found by title, alias or
+//!   UUID (see [`SnippetService::compose`](crate::SnippetService::compose))
+//! - `{{#if name}}…{{else}}…{{/if}}` keeps the first part when `name` is set
+//!   to anything but an empty or false value, and the `{{else}}` part
+//!   otherwise
+//! - `{{#each name}}…{{/each}}` repeats its body for each comma-separated
+//!   entry of `name`, which the body refers to as `{{this}}`
+//!
+//! Whitespace just inside the braces is ignored, and `\{{` writes a literal
+//! `{{`. Inside a placeholder, a backslash before `}`, `|`, `,`, `:`, `=` or
+//! another backslash makes that character literal, as in `{{sep:\,}}`. Text in
+//! braces that does not fit this grammar is left untouched.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of placeholders that default to an environment variable
+const ENV_PREFIX: &str = "env";
+
+/// Characters a backslash makes literal inside a placeholder
+const ESCAPABLE: &[char] = &['\\', '}', '|', ',', ':', '='];
+
+/// The type of value a placeholder accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueKind {
+    /// Any text
+    #[default]
+    Str,
+    /// A whole number
+    Int,
+    /// A filesystem path; a leading `~` is expanded to the home directory
+    Path,
+    /// `true` or `false`, also accepting yes/no, on/off and 1/0
+    Bool,
+}
+
+impl ValueKind {
+    /// Check `value` and return it in canonical form.
+    pub fn validate(self, value: &str) -> Result<String> {
+        match self {
+            ValueKind::Str => Ok(value.to_string()),
+            ValueKind::Int => value
+                .trim()
+                .parse::<i64>()
+                .map(|number| number.to_string())
+                .map_err(|_| Error::validation(format!("'{}' is not a whole number", value))),
+            ValueKind::Path => {
+                let path = value.trim();
+                if path.is_empty() {
+                    return Err(Error::validation("A path cannot be empty"));
+                }
+                match (path.strip_prefix('~'), std::env::var("HOME")) {
+                    (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => {
+                        Ok(format!("{}{}", home, rest))
+                    }
+                    _ => Ok(path.to_string()),
+                }
+            }
+            ValueKind::Bool => match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "on" | "1" => Ok("true".to_string()),
+                "false" | "no" | "n" | "off" | "0" => Ok("false".to_string()),
+                _ => Err(Error::validation(format!(
+                    "'{}' is not a boolean; use true or false",
+                    value
+                ))),
+            },
+        }
+    }
+}
+
+impl FromStr for ValueKind {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "str" => Ok(ValueKind::Str),
+            "int" => Ok(ValueKind::Int),
+            "path" => Ok(ValueKind::Path),
+            "bool" => Ok(ValueKind::Bool),
+            _ => Err(Error::validation(format!("Unknown value type '{}'", name))),
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueKind::Str => "str",
+            ValueKind::Int => "int",
+            ValueKind::Path => "path",
+            ValueKind::Bool => "bool",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A value a snippet asks for, merged over every place it appears.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub kind: ValueKind,
+    /// Value used when none is given
+    pub default: Option<String>,
+    /// The only values accepted, if not empty
+    pub choices: Vec<String>,
+    /// Whether the environment variable `name` overrides `default`
+    pub from_env: bool,
+}
+
+impl Placeholder {
+    /// The value to use when none is given, if there is one.
+    pub fn default_value(&self) -> Option<String> {
+        self.from_env
+            .then(|| std::env::var(&self.name).ok())
+            .flatten()
+            .or_else(|| self.default.clone())
+    }
+
+    /// Check `value` against the type and choices, returning it in canonical
+    /// form.
+    pub fn validate(&self, value: &str) -> Result<String> {
+        let value = self.kind.validate(value).map_err(|err| match err {
+            Error::Validation(reason) => {
+                Error::validation(format!("Invalid value for '{}': {}", self.name, reason))
+            }
+            other => other,
+        })?;
+        if !self.choices.is_empty() && !self.choices.contains(&value) {
+            return Err(Error::validation(format!(
+                "Invalid value for '{}': '{}' is not one of {}",
+                self.name,
+                value,
+                self.choices.join(", ")
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Take on what a later occurrence of the same placeholder declares and
+    /// this one leaves open.
+    fn merge(&mut self, other: Placeholder) {
+        if self.kind == ValueKind::Str {
+            self.kind = other.kind;
+        }
+        if self.default.is_none() {
+            self.default = other.default;
+        }
+        if self.choices.is_empty() {
+            self.choices = other.choices;
+        }
+        self.from_env |= other.from_env;
+    }
+}
+
+impl fmt::Display for Placeholder {
+    /// Write the placeholder in template syntax, escaping its default and
+    /// choices so that it parses back to the same placeholder.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.from_env {
+            write!(f, "{{{{{}:{}", ENV_PREFIX, self.name)?;
+        } else {
+            write!(f, "{{{{{}", self.name)?;
+            match &self.default {
+                // A default that reads as a type needs the type spelled out
+                Some(default)
+                    if self.kind != ValueKind::Str || default.parse::<ValueKind>().is_ok() =>
+                {
+                    write!(f, ":{}={}", self.kind, escape(default))?
+                }
+                Some(default) => write!(f, ":{}", escape(default))?,
+                None if self.kind != ValueKind::Str => write!(f, ":{}", self.kind)?,
+                None => {}
+            }
+        }
+        if !self.choices.is_empty() {
+            let choices: Vec<String> = self.choices.iter().map(|choice| escape(choice)).collect();
+            write!(f, "|{}", choices.join(","))?;
+        }
+        f.write_str("}}")
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    /// A placeholder, with its text as written
+    Placeholder {
+        name: String,
+        raw: String,
+    },
+    /// `{{this}}`, the current item of the innermost `{{#each}}`
+    Item,
+    /// `{{> reference}}`, with its text as written
+    Include {
+        reference: String,
+        raw: String,
+    },
+    /// `{{#if name}}…{{else}}…{{/if}}`
+    If {
+        name: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    /// `{{#each name}}…{{/each}}`
+    Each {
+        name: String,
+        body: Vec<Node>,
+    },
+}
+
+/// A block whose closing tag has not been reached yet.
+struct OpenBlock {
+    /// The opening tag: `if` or `each`, and the variable
+    tag: Option<(&'static str, String)>,
+    nodes: Vec<Node>,
+    /// The `{{else}}` branch of an `if`, once reached
+    otherwise: Option<Vec<Node>>,
+}
+
+impl OpenBlock {
+    fn new(tag: Option<(&'static str, String)>) -> Self {
+        Self {
+            tag,
+            nodes: Vec::new(),
+            otherwise: None,
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        if matches!(&node, Node::Text(text) if text.is_empty()) {
+            return;
+        }
+        let nodes = self.otherwise.as_mut().unwrap_or(&mut self.nodes);
+        match (nodes.last_mut(), node) {
+            (Some(Node::Text(text)), Node::Text(more)) => text.push_str(&more),
+            (_, node) => nodes.push(node),
+        }
+    }
+}
+
+/// Snippet content parsed into text, placeholders, includes and blocks.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+    placeholders: Vec<Placeholder>,
+}
+
+impl Template {
+    /// Parse `content`.
+    ///
+    /// Anything in braces that is not a placeholder, include or block tag is
+    /// kept as text; only unbalanced blocks are an error.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut placeholders = Vec::new();
+        let mut stack = vec![OpenBlock::new(None)];
+        let mut rest = content;
+
+        while let Some(start) = rest.find("{{") {
+            let top = stack.len() - 1;
+            if rest[..start].ends_with('\\') {
+                stack[top].push(Node::Text(format!("{}{{{{", &rest[..start - 1])));
+                rest = &rest[start + 2..];
+                continue;
+            }
+            stack[top].push(Node::Text(rest[..start].to_string()));
+
+            let Some(end) = find_close(&rest[start + 2..]) else {
+                rest = &rest[start..];
+                break;
+            };
+            let raw = &rest[start..start + end + 4];
+            let body = raw[2..raw.len() - 2].trim();
+            rest = &rest[start + end + 4..];
+
+            if let Some((tag, name)) = open_tag(body) {
+                if !is_name(name) {
+                    return Err(Error::validation(format!(
+                        "Expected a variable name in '{}'",
+                        raw
+                    )));
+                }
+                // Variables that only steer blocks are optional.
+                add_placeholder(
+                    &mut placeholders,
+                    Placeholder {
+                        name: name.to_string(),
+                        default: Some(String::new()),
+                        ..Default::default()
+                    },
+                );
+                stack.push(OpenBlock::new(Some((tag, name.to_string()))));
+            } else if body == "else" {
+                match stack.last_mut() {
+                    Some(block) if block.otherwise.is_none() && is_tag(block, "if") => {
+                        block.otherwise = Some(Vec::new());
+                    }
+                    _ => return Err(Error::validation("'{{else}}' outside '{{#if}}'")),
+                }
+            } else if let Some(tag) = body.strip_prefix('/') {
+                let block = stack.pop().filter(|block| is_tag(block, tag.trim()));
+                let (Some(block), Some(parent)) = (block, stack.last_mut()) else {
+                    return Err(Error::validation(format!("Unexpected '{}'", raw)));
+                };
+                let (tag, name) = block.tag.expect("only the root block has no tag");
+                parent.push(match tag {
+                    "if" => Node::If {
+                        name,
+                        then: block.nodes,
+                        otherwise: block.otherwise.unwrap_or_default(),
+                    },
+                    _ => Node::Each {
+                        name,
+                        body: block.nodes,
+                    },
+                });
+            } else if let Some(reference) = body.strip_prefix('>') {
+                let top = stack.len() - 1;
+                stack[top].push(Node::Include {
+                    reference: reference.trim().to_string(),
+                    raw: raw.to_string(),
+                });
+            } else if body == "this" && stack.iter().any(|block| is_tag(block, "each")) {
+                let top = stack.len() - 1;
+                stack[top].push(Node::Item);
+            } else {
+                let node = match parse_placeholder(body) {
+                    Some(placeholder) => {
+                        let name = placeholder.name.clone();
+                        add_placeholder(&mut placeholders, placeholder);
+                        Node::Placeholder {
+                            name,
+                            raw: raw.to_string(),
+                        }
+                    }
+                    None => Node::Text(raw.to_string()),
+                };
+                let top = stack.len() - 1;
+                stack[top].push(node);
+            }
+        }
+
+        let mut root = stack.remove(0);
+        if let Some(OpenBlock {
+            tag: Some((tag, name)),
+            ..
+        }) = stack.pop()
+        {
+            return Err(Error::validation(format!(
+                "'{{{{#{} {}}}}}' is never closed",
+                tag, name
+            )));
+        }
+        root.push(Node::Text(rest.to_string()));
+        Ok(Template {
+            nodes: root.nodes,
+            placeholders,
+        })
+    }
+
+    /// The placeholders, in order of first appearance.
+    ///
+    /// Variables used by `{{#if}}` and `{{#each}}` are listed too, with an
+    /// empty default.
+    pub fn placeholders(&self) -> &[Placeholder] {
+        &self.placeholders
+    }
+
+    /// The references of the snippets included with `{{> reference}}`.
+    pub fn includes(&self) -> Vec<&str> {
+        fn collect<'a>(nodes: &'a [Node], includes: &mut Vec<&'a str>) {
+            for node in nodes {
+                match node {
+                    Node::Include { reference, .. } => includes.push(reference),
+                    Node::If {
+                        then, otherwise, ..
+                    } => {
+                        collect(then, includes);
+                        collect(otherwise, includes);
+                    }
+                    Node::Each { body, .. } => collect(body, includes),
+                    _ => {}
+                }
+            }
+        }
+        let mut includes = Vec::new();
+        collect(&self.nodes, &mut includes);
+        includes
+    }
+
+    /// Replace every include with the template `lookup` returns for its
+    /// reference, taking on that template's placeholders.
+    pub fn inline(&self, lookup: &mut impl FnMut(&str) -> Result<Template>) -> Result<Template> {
+        fn walk(
+            nodes: &[Node],
+            lookup: &mut impl FnMut(&str) -> Result<Template>,
+            placeholders: &mut Vec<Placeholder>,
+        ) -> Result<Vec<Node>> {
+            let mut inlined = Vec::with_capacity(nodes.len());
+            for node in nodes {
+                match node {
+                    Node::Include { reference, .. } => {
+                        let included = lookup(reference)?;
+                        for placeholder in included.placeholders {
+                            add_placeholder(placeholders, placeholder);
+                        }
+                        inlined.extend(included.nodes);
+                    }
+                    Node::If {
+                        name,
+                        then,
+                        otherwise,
+                    } => inlined.push(Node::If {
+                        name: name.clone(),
+                        then: walk(then, lookup, placeholders)?,
+                        otherwise: walk(otherwise, lookup, placeholders)?,
+                    }),
+                    Node::Each { name, body } => inlined.push(Node::Each {
+                        name: name.clone(),
+                        body: walk(body, lookup, placeholders)?,
+                    }),
+                    other => inlined.push(other.clone()),
+                }
+            }
+            Ok(inlined)
+        }
+
+        let mut placeholders = self.placeholders.clone();
+        let nodes = walk(&self.nodes, lookup, &mut placeholders)?;
+        Ok(Template {
+            nodes,
+            placeholders,
+        })
+    }
+
+    /// Fill in every placeholder from `values` or its default.
+    ///
+    /// Values are validated against the placeholder's type and choices. It
+    /// is an error for a placeholder in a rendered part of the template to
+    /// have neither a value nor a default, and every such placeholder is
+    /// named. Includes must have been [inlined](Self::inline) first.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        let mut resolved = HashMap::new();
+        for placeholder in &self.placeholders {
+            let value = values
+                .get(&placeholder.name)
+                .cloned()
+                .or_else(|| placeholder.default_value());
+            if let Some(value) = value {
+                resolved.insert(placeholder.name.clone(), placeholder.validate(&value)?);
+            }
+        }
+
+        let mut renderer = Renderer {
+            values: &resolved,
+            missing: Vec::new(),
+            includes: Vec::new(),
+        };
+        let mut output = String::new();
+        renderer.write(&self.nodes, None, &mut output);
+
+        if let Some(reference) = renderer.includes.first() {
+            return Err(Error::validation(format!(
+                "Cannot render '{{{{> {}}}}}' without the stash it refers to",
+                reference
+            )));
+        }
+        if !renderer.missing.is_empty() {
+            return Err(Error::validation(format!(
+                "No value given for placeholder{} {}",
+                if renderer.missing.len() == 1 { "" } else { "s" },
+                renderer
+                    .missing
+                    .iter()
+                    .map(|name| format!("'{}'", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        Ok(output)
+    }
+
+    /// Fill in the placeholders that have a value or default, leaving the
+    /// rest and any includes as written. Values are not validated.
+    pub fn expand(&self, values: &HashMap<String, String>) -> String {
+        self.expand_with_missing(values).0
+    }
+
+    /// Like [`expand`](Self::expand), also naming the placeholders in
+    /// rendered parts of the template that were left as written, in order.
+    pub fn expand_with_missing(&self, values: &HashMap<String, String>) -> (String, Vec<String>) {
+        let mut resolved: HashMap<String, String> = self
+            .placeholders
+            .iter()
+            .filter_map(|p| Some((p.name.clone(), p.default_value()?)))
+            .collect();
+        resolved.extend(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let mut renderer = Renderer {
+            values: &resolved,
+            missing: Vec::new(),
+            includes: Vec::new(),
+        };
+        let mut output = String::new();
+        renderer.write(&self.nodes, None, &mut output);
+        let missing = renderer
+            .missing
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        (output, missing)
+    }
+
+    /// Write the template in another tool's syntax: `placeholder` writes
+    /// each placeholder and `text` each run of literal text.
+    ///
+    /// Blocks have no counterpart in other tools and are an error, as are
+    /// includes that have not been [inlined](Self::inline).
+    pub fn translate(
+        &self,
+        placeholder: &mut impl FnMut(&Placeholder) -> String,
+        text: &impl Fn(&str) -> String,
+    ) -> Result<String> {
+        let mut output = String::new();
+        for node in &self.nodes {
+            match node {
+                Node::Text(literal) => output.push_str(&text(literal)),
+                Node::Placeholder { name, .. } => {
+                    let declared = self.placeholders.iter().find(|p| &p.name == name);
+                    output.push_str(&placeholder(declared.expect("placeholders are collected")));
+                }
+                Node::Include { raw, .. } => {
+                    return Err(Error::validation(format!(
+                        "Includes such as '{}' must be inlined first",
+                        raw
+                    )))
+                }
+                Node::Item | Node::If { .. } | Node::Each { .. } => {
+                    return Err(Error::validation(
+                        "{{#if}} and {{#each}} blocks cannot be written in other tools' syntax",
+                    ))
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Writes template nodes, noting what it could not fill in.
+struct Renderer<'a> {
+    values: &'a HashMap<String, String>,
+    missing: Vec<&'a str>,
+    includes: Vec<&'a str>,
+}
+
+impl<'a> Renderer<'a> {
+    fn write(&mut self, nodes: &'a [Node], item: Option<&str>, output: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Placeholder { name, raw } => match self.values.get(name) {
+                    Some(value) => output.push_str(value),
+                    None => {
+                        if !self.missing.contains(&name.as_str()) {
+                            self.missing.push(name);
+                        }
+                        output.push_str(raw);
+                    }
+                },
+                Node::Item => output.push_str(item.unwrap_or_default()),
+                Node::Include { reference, raw } => {
+                    self.includes.push(reference);
+                    output.push_str(raw);
+                }
+                Node::If {
+                    name,
+                    then,
+                    otherwise,
+                } => {
+                    let value = self.values.get(name).map(String::as_str);
+                    let branch = if value.is_some_and(is_truthy) {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    self.write(branch, item, output);
+                }
+                Node::Each { name, body } => {
+                    let list = self.values.get(name).map(String::as_str).unwrap_or("");
+                    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                        self.write(body, Some(entry), output);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `{{#if x}}` and `{{#each x}}` tags, as the block kind and variable.
+fn open_tag(body: &str) -> Option<(&'static str, &str)> {
+    let tag = body.strip_prefix('#')?;
+    let (kind, name) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let kind = match kind {
+        "if" => "if",
+        "each" => "each",
+        _ => return None,
+    };
+    Some((kind, name.trim()))
+}
+
+fn is_tag(block: &OpenBlock, tag: &str) -> bool {
+    matches!(&block.tag, Some((kind, _)) if *kind == tag)
+}
+
+/// Values that make `{{#if}}` take its first branch: anything but empty
+/// text, `false`, `no`, `off` or `0`.
+fn is_truthy(value: &str) -> bool {
+    !matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "" | "false" | "no" | "off" | "0"
+    )
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Add `placeholder` to `placeholders`, merging it into an earlier
+/// occurrence of the same name.
+fn add_placeholder(placeholders: &mut Vec<Placeholder>, placeholder: Placeholder) {
+    match placeholders
+        .iter_mut()
+        .find(|existing| existing.name == placeholder.name)
+    {
+        Some(existing) => existing.merge(placeholder),
+        None => placeholders.push(placeholder),
+    }
+}
+
+/// Escape `text` so that it reads back unchanged as a placeholder default
+/// or choice.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if ESCAPABLE.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Undo [`escape`].
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && ESCAPABLE.contains(&next) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Byte offset of the first `separator` in `text` that is not escaped.
+fn find_unescaped(text: &str, separator: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && text[i + 1..].starts_with(ESCAPABLE) {
+            escaped = true;
+        } else if c == separator {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Byte offset of the `}}` that closes a placeholder body.
+fn find_close(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(i) = find_unescaped(&text[offset..], '}') {
+        let at = offset + i;
+        if text[at + 1..].starts_with('}') {
+            return Some(at);
+        }
+        offset = at + 1;
+    }
+    None
+}
+
+/// Split `text` around its first unescaped `separator`.
+fn split_once_unescaped(text: &str, separator: char) -> Option<(&str, &str)> {
+    find_unescaped(text, separator).map(|i| (&text[..i], &text[i + separator.len_utf8()..]))
+}
+
+/// Split `text` around every unescaped `separator`.
+fn split_unescaped(mut text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    while let Some((part, rest)) = split_once_unescaped(text, separator) {
+        parts.push(part);
+        text = rest;
+    }
+    parts.push(text);
+    parts
+}
+
+/// Parse the text between `{{` and `}}`, or `None` if it is not a placeholder.
+fn parse_placeholder(body: &str) -> Option<Placeholder> {
+    let body = body.trim();
+    let (head, choices) = match split_once_unescaped(body, '|') {
+        Some((head, choices)) => (
+            head,
+            split_unescaped(choices, ',')
+                .into_iter()
+                .map(str::trim)
+                .filter(|choice| !choice.is_empty())
+                .map(unescape)
+                .collect(),
+        ),
+        None => (body, Vec::new()),
+    };
+    let (name, spec) = match split_once_unescaped(head, ':') {
+        Some((name, spec)) => (name.trim(), Some(spec.trim())),
+        None => (head.trim(), None),
+    };
+
+    if name == ENV_PREFIX {
+        let variable = spec?;
+        let valid = !variable.is_empty()
+            && variable
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        return valid.then(|| Placeholder {
+            name: variable.to_string(),
+            choices,
+            from_env: true,
+            ..Default::default()
+        });
+    }
+
+    if !is_name(name) {
+        return None;
+    }
+
+    let (kind, default) = match spec {
+        None => (ValueKind::Str, None),
+        Some(spec) => match split_once_unescaped(spec, '=')
+            .map(|(kind, default)| (kind.trim().parse::<ValueKind>(), default))
+        {
+            Some((Ok(kind), default)) => (kind, Some(unescape(default.trim()))),
+            _ => match spec.parse::<ValueKind>() {
+                Ok(kind) => (kind, None),
+                Err(_) => (ValueKind::Str, Some(unescape(spec))),
+            },
+        },
+    };
+
+    Some(Placeholder {
+        name: name.to_string(),
+        kind,
+        default,
+        choices,
+        from_env: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_placeholders() {
+        let template = Template::parse(
+            "ssh {{ user:root }}@{{host}} -p {{port:int=22}} {{mode|fast,slow}} {{env:HOME}} {{host}}",
+        )
+        .unwrap();
+        let placeholders = template.placeholders();
+        assert_eq!(placeholders.len(), 5);
+
+        assert_eq!(placeholders[0].name, "user");
+        assert_eq!(placeholders[0].default.as_deref(), Some("root"));
+        assert_eq!(placeholders[1].name, "host");
+        assert_eq!(placeholders[1].default, None);
+        assert_eq!(placeholders[2].kind, ValueKind::Int);
+        assert_eq!(placeholders[2].default.as_deref(), Some("22"));
+        assert_eq!(placeholders[3].choices, vec!["fast", "slow"]);
+        assert_eq!(placeholders[4].name, "HOME");
+        assert!(placeholders[4].from_env);
+    }
+
+    #[test]
+    fn test_render() {
+        let template =
+            Template::parse("curl -X {{method:GET|GET,POST}} {{url}} --retry {{n:int}}").unwrap();
+
+        let rendered = template
+            .render(&values(&[("url", "example.com"), ("n", " 3 ")]))
+            .unwrap();
+        assert_eq!(rendered, "curl -X GET example.com --retry 3");
+
+        let missing = template.render(&values(&[("n", "3")])).unwrap_err();
+        assert!(missing.to_string().contains("'url'"));
+        let not_a_number = template.render(&values(&[("url", "x"), ("n", "three")]));
+        assert!(not_a_number.is_err());
+        let bad_choice = values(&[("url", "x"), ("n", "1"), ("method", "PUT")]);
+        assert!(template.render(&bad_choice).is_err());
+    }
+
+    #[test]
+    fn test_expand_with_missing() {
+        let template =
+            Template::parse("ssh {{host}} -p {{port:22}}{{#if user}} -l {{user}}{{/if}}").unwrap();
+
+        let (expanded, missing) = template.expand_with_missing(&values(&[]));
+        assert_eq!(expanded, "ssh {{host}} -p 22");
+        assert_eq!(missing, vec!["host"]);
+
+        let (expanded, missing) = template.expand_with_missing(&values(&[("user", "root")]));
+        assert_eq!(expanded, "ssh {{host}} -p 22 -l root");
+        assert_eq!(missing, vec!["host"]);
+    }
+
+    #[test]
+    fn test_escaping_and_literal_braces() {
+        let template = Template::parse(r"echo \{{name}} {{ .Field }} {{name}} {{unclosed").unwrap();
+        assert_eq!(template.placeholders().len(), 1);
+        assert_eq!(
+            template.render(&values(&[("name", "x")])).unwrap(),
+            "echo {{name}} {{ .Field }} x {{unclosed"
+        );
+    }
+
+    #[test]
+    fn test_escaped_defaults_and_choices() {
+        let template =
+            Template::parse(r"{{url:http\://a\:8080}} {{sep:\,|\,,\|,\}\}}} {{x:int\=5}}").unwrap();
+        let placeholders = template.placeholders();
+        assert_eq!(placeholders.len(), 3);
+        assert_eq!(placeholders[0].default.as_deref(), Some("http://a:8080"));
+        assert_eq!(placeholders[1].default.as_deref(), Some(","));
+        assert_eq!(placeholders[1].choices, vec![",", "|", "}}"]);
+        assert_eq!(placeholders[2].kind, ValueKind::Str);
+        assert_eq!(placeholders[2].default.as_deref(), Some("int=5"));
+
+        // Placeholders are written back in a form that parses the same
+        for placeholder in [
+            Placeholder {
+                name: "url".to_string(),
+                default: Some("http://a:8080/?q=a|b".to_string()),
+                ..Default::default()
+            },
+            Placeholder {
+                name: "kind".to_string(),
+                default: Some("path".to_string()),
+                choices: vec!["path".to_string(), "a,b".to_string(), r"c:\".to_string()],
+                ..Default::default()
+            },
+            Placeholder {
+                name: "port".to_string(),
+                kind: ValueKind::Int,
+                ..Default::default()
+            },
+            Placeholder {
+                name: "HOME".to_string(),
+                from_env: true,
+                ..Default::default()
+            },
+        ] {
+            let template = Template::parse(&placeholder.to_string()).unwrap();
+            assert_eq!(template.placeholders(), [placeholder]);
+        }
+    }
+
+    #[test]
+    fn test_blocks() {
+        let template = Template::parse(
+            "curl{{#if verbose}} -v{{else}} -s{{/if}}{{#each header}} -H '{{this}}'{{/each}} {{url}}",
+        )
+        .unwrap();
+        let names: Vec<_> = template.placeholders().iter().map(|p| &p.name).collect();
+        assert_eq!(names, ["verbose", "header", "url"]);
+
+        assert_eq!(
+            template.render(&values(&[("url", "x")])).unwrap(),
+            "curl -s x"
+        );
+        let all = values(&[("url", "x"), ("verbose", "yes"), ("header", "A: 1, B: 2")]);
+        assert_eq!(
+            template.render(&all).unwrap(),
+            "curl -v -H 'A: 1' -H 'B: 2' x"
+        );
+
+        // Placeholders in branches that are not rendered are not required.
+        let optional = Template::parse("ls{{#if dir}} {{dir}}{{/if}} {{a}}{{b}}").unwrap();
+        assert_eq!(
+            optional.render(&values(&[("a", "-l"), ("b", "")])).unwrap(),
+            "ls -l"
+        );
+        let missing = optional.render(&HashMap::new()).unwrap_err().to_string();
+        assert!(missing.contains("placeholders 'a', 'b'"));
+
+        assert!(Template::parse("{{#if x}}open").is_err());
+        assert!(Template::parse("{{#if x}}{{/each}}").is_err());
+        assert!(Template::parse("{{else}}").is_err());
+        assert!(Template::parse("{{#each}}{{/each}}").is_err());
+    }
+
+    #[test]
+    fn test_inline_includes() {
+        let template = Template::parse("{{> auth}} {{url}}").unwrap();
+        assert_eq!(template.includes(), ["auth"]);
+        assert!(template.render(&values(&[("url", "x")])).is_err());
+
+        let inlined = template
+            .inline(&mut |reference| {
+                assert_eq!(reference, "auth");
+                Template::parse("curl -H 'Authorization: {{token}}'")
+            })
+            .unwrap();
+        let names: Vec<_> = inlined.placeholders().iter().map(|p| &p.name).collect();
+        assert_eq!(names, ["url", "token"]);
+        assert_eq!(
+            inlined
+                .render(&values(&[("url", "x"), ("token", "t")]))
+                .unwrap(),
+            "curl -H 'Authorization: t' x"
+        );
+    }
+
+    #[test]
+    fn test_value_kinds() {
+        assert_eq!(ValueKind::Bool.validate("Yes").unwrap(), "true");
+        assert_eq!(ValueKind::Bool.validate("0").unwrap(), "false");
+        assert!(ValueKind::Bool.validate("maybe").is_err());
+        assert!(ValueKind::Path.validate(" ").is_err());
+        assert_eq!(ValueKind::Path.validate("/tmp/x").unwrap(), "/tmp/x");
+        assert!(ValueKind::Int.validate("1.5").is_err());
+    }
+}
@@ -9,6 +9,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    snippet_runs (snippet_uuid, started_at) {
+        snippet_uuid -> Text,
+        started_at -> Timestamp,
+        cwd -> Nullable<Text>,
+        exit_code -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    snippet_usage (snippet_uuid, used_at) {
+        snippet_uuid -> Text,
+        used_at -> Timestamp,
+        cwd -> Nullable<Text>,
+        repo -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    snippet_versions (snippet_uuid, version) {
+        snippet_uuid -> Text,
+        version -> Integer,
+        title -> Text,
+        content -> Text,
+        tags -> Text,
+        author -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     snippets (uuid) {
         uuid -> Text,
@@ -18,6 +48,9 @@ diesel::table! {
         embedding -> Nullable<Binary>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        item_type -> Text,
+        metadata -> Text,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -30,4 +63,11 @@ diesel::table! {
 
 diesel::joinable!(relations -> snippets (from_uuid));
 
-diesel::allow_tables_to_appear_in_same_query!(relations, snippets, vss_snippets,);
+diesel::allow_tables_to_appear_in_same_query!(
+    relations,
+    snippet_runs,
+    snippet_usage,
+    snippet_versions,
+    snippets,
+    vss_snippets,
+);
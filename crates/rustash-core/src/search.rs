@@ -1 +1,789 @@
-// stub
+//! Approximate nearest-neighbour search backed by an HNSW index.
+//!
+//! [`HnswIndex`] keeps an `hnsw_rs` graph of item embeddings keyed by UUID.
+//! [`IndexedBackend`] wraps any [`StorageBackend`], keeps the index in step with
+//! `save`, `delete` and `restore`, and answers `vector_search` from the graph instead of
+//! scanning every stored embedding. For SQLite stashes the index is persisted
+//! next to the database file (see [`index_path_for`]), and rebuilt on open if
+//! the stash has changed without it.
+
+use crate::error::{Error, Result};
+use crate::history::Version;
+use crate::memory::MemoryItem;
+use crate::models::{Query, Relation, Run, SearchHit, SnippetWithTags, TrashedItem};
+use crate::storage::StorageBackend;
+use crate::usage::{Usage, UsageStats};
+use crate::vector::decode_embedding;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hnsw_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Maximum number of links per node in each layer.
+const MAX_NB_CONNECTION: usize = 16;
+/// Number of layers in the graph (the `hnsw_rs` maximum).
+const MAX_LAYER: usize = 16;
+/// Candidate list size used while inserting.
+const EF_CONSTRUCTION: usize = 200;
+/// Lower bound for the candidate list size used while searching.
+const MIN_EF_SEARCH: usize = 64;
+
+/// Bookkeeping persisted next to the graph files.
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexMeta {
+    /// Basename the graph was actually dumped under
+    basename: Option<String>,
+    /// Embedding dimension, or 0 if nothing has been indexed yet
+    dimension: usize,
+    /// Item id for each HNSW data id; `None` marks a deleted point
+    points: Vec<Option<Uuid>>,
+    /// The stash the index was in step with when it was saved
+    fingerprint: Fingerprint,
+}
+
+/// A summary of the embedded items in a stash, used to tell whether a saved
+/// index still matches it.
+///
+/// Timestamps are summed rather than taking the latest one so the summary
+/// can be kept up to date when an item is removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    /// Number of items with an embedding
+    embedded: u64,
+    /// XOR of their ids
+    ids: u128,
+    /// Sum of their `updated_at` timestamps, in milliseconds
+    updated: i64,
+}
+
+impl Fingerprint {
+    /// Summarise every embedded item stored in `backend`.
+    async fn of(backend: &dyn StorageBackend) -> Result<Self> {
+        let mut fingerprint = Self::default();
+        for item in backend.query(&Query::default()).await? {
+            fingerprint.add(item.as_ref());
+        }
+        Ok(fingerprint)
+    }
+
+    fn add(&mut self, item: &dyn MemoryItem) {
+        if item.embedding().is_some() {
+            self.embedded = self.embedded.wrapping_add(1);
+            self.ids ^= item.id().as_u128();
+            self.updated = self
+                .updated
+                .wrapping_add(item.updated_at().timestamp_millis());
+        }
+    }
+
+    fn remove(&mut self, item: &dyn MemoryItem) {
+        if item.embedding().is_some() {
+            self.embedded = self.embedded.wrapping_sub(1);
+            self.ids ^= item.id().as_u128();
+            self.updated = self
+                .updated
+                .wrapping_sub(item.updated_at().timestamp_millis());
+        }
+    }
+}
+
+/// An HNSW graph over item embeddings, using cosine distance.
+///
+/// `hnsw_rs` cannot remove points, so deletions and re-inserts leave
+/// tombstones that are filtered out at query time. [`HnswIndex::deleted`]
+/// reports how many there are so callers can decide when to rebuild.
+pub struct HnswIndex {
+    graph: Hnsw<'static, f32, DistCosine>,
+    dimension: usize,
+    points: Vec<Option<Uuid>>,
+    by_id: HashMap<Uuid, usize>,
+    fingerprint: Fingerprint,
+}
+
+impl fmt::Debug for HnswIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HnswIndex")
+            .field("dimension", &self.dimension)
+            .field("len", &self.len())
+            .field("deleted", &self.deleted())
+            .finish()
+    }
+}
+
+impl Default for HnswIndex {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl HnswIndex {
+    /// Create an empty index sized for roughly `capacity` embeddings.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_parts(new_graph(capacity), 0, Vec::new(), Fingerprint::default())
+    }
+
+    fn from_parts(
+        graph: Hnsw<'static, f32, DistCosine>,
+        dimension: usize,
+        points: Vec<Option<Uuid>>,
+        fingerprint: Fingerprint,
+    ) -> Self {
+        let by_id = points
+            .iter()
+            .enumerate()
+            .filter_map(|(data_id, id)| id.map(|id| (id, data_id)))
+            .collect();
+        Self {
+            graph,
+            dimension,
+            points,
+            by_id,
+            fingerprint,
+        }
+    }
+
+    /// Build an index from every embedded item stored in `backend`.
+    pub async fn build(backend: &dyn StorageBackend) -> Result<Self> {
+        let mut entries = Vec::new();
+        let mut fingerprint = Fingerprint::default();
+        for item in backend.query(&Query::default()).await? {
+            if let Some(bytes) = item.embedding() {
+                entries.push((item.id(), decode_embedding(bytes)?));
+            }
+            fingerprint.add(item.as_ref());
+        }
+
+        let mut index = Self::with_capacity(entries.len());
+        index.fingerprint = fingerprint;
+        let Some((_, first)) = entries.first() else {
+            return Ok(index);
+        };
+        index.dimension = first.len();
+
+        let mut batch = Vec::with_capacity(entries.len());
+        for (id, embedding) in &entries {
+            // Items embedded with a different model cannot share the graph.
+            if embedding.len() != index.dimension {
+                continue;
+            }
+            let data_id = index.points.len();
+            index.points.push(Some(*id));
+            index.by_id.insert(*id, data_id);
+            batch.push((embedding.as_slice(), data_id));
+        }
+        index.graph.parallel_insert_slice(&batch);
+        Ok(index)
+    }
+
+    /// Number of live embeddings in the index.
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    /// Whether the index holds no live embeddings.
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// Number of tombstoned points still present in the graph.
+    pub fn deleted(&self) -> usize {
+        self.points.len() - self.by_id.len()
+    }
+
+    /// Embedding dimension, or `None` if nothing has been indexed yet.
+    pub fn dimension(&self) -> Option<usize> {
+        (self.dimension > 0).then_some(self.dimension)
+    }
+
+    /// Insert or replace the embedding for `id`.
+    ///
+    /// Returns `false` if the embedding's dimension does not match the index.
+    pub fn insert(&mut self, id: Uuid, embedding: &[f32]) -> bool {
+        if embedding.is_empty() {
+            return false;
+        }
+        if self.dimension == 0 {
+            self.dimension = embedding.len();
+        } else if embedding.len() != self.dimension {
+            return false;
+        }
+
+        self.remove(&id);
+        let data_id = self.points.len();
+        self.points.push(Some(id));
+        self.by_id.insert(id, data_id);
+        self.graph.insert_slice((embedding, data_id));
+        true
+    }
+
+    /// Remove the embedding for `id`, returning whether it was present.
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        match self.by_id.remove(id) {
+            Some(data_id) => {
+                self.points[data_id] = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Find the `limit` nearest items to `embedding`.
+    ///
+    /// Scores are cosine similarities, so higher is more similar. Returns an
+    /// empty list if the query's dimension does not match the index.
+    pub fn search(&self, embedding: &[f32], limit: usize) -> Vec<(Uuid, f32)> {
+        if limit == 0 || self.is_empty() || embedding.len() != self.dimension {
+            return Vec::new();
+        }
+
+        let live = |data_id: &usize| matches!(self.points.get(*data_id), Some(Some(_)));
+        let ef_search = (limit * 2).max(MIN_EF_SEARCH);
+        self.graph
+            .search_filter(embedding, limit, ef_search, Some(&live))
+            .into_iter()
+            .filter_map(|neighbour| {
+                let id = self.points.get(neighbour.d_id).copied().flatten()?;
+                Some((id, 1.0 - neighbour.distance))
+            })
+            .collect()
+    }
+
+    /// Write the index to disk, using `path` as the base for the file names.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let (directory, basename) = split_index_path(path)?;
+
+        // `hnsw_rs` refuses to dump an empty graph; the metadata alone
+        // is enough to reopen an empty index.
+        let basename = if self.points.is_empty() {
+            None
+        } else {
+            let dumped = self
+                .graph
+                .file_dump(directory, basename)
+                .map_err(|e| Error::other(format!("Failed to save HNSW index: {}", e)))?;
+            Some(dumped)
+        };
+
+        let meta = IndexMeta {
+            basename,
+            dimension: self.dimension,
+            points: self.points.clone(),
+            fingerprint: self.fingerprint,
+        };
+        std::fs::write(meta_path(path), bincode::serialize(&meta)?)?;
+        Ok(())
+    }
+
+    /// Load an index previously written with [`HnswIndex::save`].
+    ///
+    /// Returns `Ok(None)` if no index has been saved at `path`, or if it was
+    /// saved in a format this version does not read.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let meta_file = meta_path(path);
+        if !meta_file.exists() {
+            return Ok(None);
+        }
+        let Ok(meta) = bincode::deserialize::<IndexMeta>(&std::fs::read(meta_file)?) else {
+            return Ok(None);
+        };
+
+        let graph = match &meta.basename {
+            Some(basename) => {
+                let (directory, _) = split_index_path(path)?;
+                // The loaded graph borrows from its loader. An index is opened
+                // once per backend, so leaking the small loader is the simplest
+                // way to give the graph a `'static` lifetime.
+                let loader = Box::leak(Box::new(HnswIo::new(directory, basename)));
+                let graph: Hnsw<'static, f32, DistCosine> = loader
+                    .load_hnsw()
+                    .map_err(|e| Error::other(format!("Failed to load HNSW index: {}", e)))?;
+                graph
+            }
+            None => new_graph(0),
+        };
+
+        Ok(Some(Self::from_parts(
+            graph,
+            meta.dimension,
+            meta.points,
+            meta.fingerprint,
+        )))
+    }
+}
+
+fn new_graph(capacity: usize) -> Hnsw<'static, f32, DistCosine> {
+    Hnsw::new(
+        MAX_NB_CONNECTION,
+        capacity.max(1),
+        MAX_LAYER,
+        EF_CONSTRUCTION,
+        DistCosine {},
+    )
+}
+
+fn meta_path(path: &Path) -> PathBuf {
+    let mut file_name = path.as_os_str().to_owned();
+    file_name.push(".hnsw.meta");
+    PathBuf::from(file_name)
+}
+
+fn split_index_path(path: &Path) -> Result<(&Path, &str)> {
+    let basename = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::other(format!("Invalid index path: {}", path.display())))?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok((directory, basename))
+}
+
+/// The base path for the HNSW index of a SQLite database URL.
+///
+/// Returns `None` for in-memory databases, which have nowhere to keep an index.
+pub fn index_path_for(database_url: &str) -> Option<PathBuf> {
+    let path = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))
+        .unwrap_or(database_url);
+    let path = path.split('?').next().unwrap_or(path);
+    if path.is_empty() || path.contains(":memory:") || path.contains("mode=memory") {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// A storage backend decorator that answers `vector_search` from an HNSW index.
+///
+/// Writes go to the wrapped backend first and are then applied to the index.
+/// When the index has a path it is written back to disk by
+/// [`IndexedBackend::persist`], or when the backend is dropped.
+pub struct IndexedBackend {
+    inner: Box<dyn StorageBackend>,
+    index: RwLock<HnswIndex>,
+    path: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl fmt::Debug for IndexedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexedBackend")
+            .field("inner", &self.inner)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl IndexedBackend {
+    /// Wrap `inner` with an in-memory index built from its current contents.
+    pub async fn new(inner: Box<dyn StorageBackend>) -> Result<Self> {
+        let index = HnswIndex::build(inner.as_ref()).await?;
+        Ok(Self {
+            inner,
+            index: RwLock::new(index),
+            path: None,
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Wrap `inner` with the index saved at `path`.
+    ///
+    /// The index is built and saved if it does not exist yet, if more than
+    /// half of its points are tombstones, or if the stash has changed since
+    /// it was saved, for instance by another process or one that crashed
+    /// before writing the index back.
+    pub async fn open(inner: Box<dyn StorageBackend>, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let saved = HnswIndex::load(&path)?;
+        let needs_rebuild = match &saved {
+            Some(index) => {
+                index.deleted() > index.len()
+                    || index.fingerprint != Fingerprint::of(inner.as_ref()).await?
+            }
+            None => true,
+        };
+        let backend = Self {
+            index: RwLock::new(saved.unwrap_or_default()),
+            inner,
+            path: Some(path),
+            dirty: AtomicBool::new(false),
+        };
+
+        if needs_rebuild {
+            backend.rebuild().await?;
+            backend.persist()?;
+        }
+        Ok(backend)
+    }
+
+    /// Rebuild the index from the wrapped backend, discarding tombstones.
+    ///
+    /// Use this after the stash has been modified without going through the index.
+    pub async fn rebuild(&self) -> Result<()> {
+        let index = HnswIndex::build(self.inner.as_ref()).await?;
+        *self
+            .index
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))? = index;
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Write the index to disk if it has changed since it was loaded.
+    pub fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            if let Err(e) = self.read_index()?.save(path) {
+                self.dirty.store(true, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_index(&self) -> Result<std::sync::RwLockReadGuard<'_, HnswIndex>> {
+        self.index
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_index(&self) -> Result<std::sync::RwLockWriteGuard<'_, HnswIndex>> {
+        self.index
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+}
+
+impl Drop for IndexedBackend {
+    fn drop(&mut self) {
+        // Errors cannot be reported from `drop`; a stale index on disk is
+        // tolerated because `open` sees it no longer matches the stash and
+        // rebuilds it.
+        let _ = self.persist();
+    }
+}
+
+#[async_trait]
+impl StorageBackend for IndexedBackend {
+    async fn save(&self, item: &(dyn MemoryItem + Send + Sync)) -> Result<()> {
+        // Reject an unreadable embedding before anything is written.
+        let embedding = item.embedding().map(decode_embedding).transpose()?;
+        let previous = self.inner.get(&item.id()).await?;
+        self.inner.save(item).await?;
+
+        let mut index = self.write_index()?;
+        if let Some(previous) = &previous {
+            index.fingerprint.remove(previous.as_ref());
+        }
+        index.fingerprint.add(item);
+        match embedding {
+            Some(embedding) => {
+                index.insert(item.id(), &embedding);
+            }
+            None => {
+                index.remove(&item.id());
+            }
+        }
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn get(&self, id: &Uuid) -> Result<Option<Box<dyn MemoryItem + Send + Sync>>> {
+        self.inner.get(id).await
+    }
+
+    async fn delete(&self, id: &Uuid) -> Result<()> {
+        let previous = self.inner.get(id).await?;
+        self.inner.delete(id).await?;
+
+        let mut index = self.write_index()?;
+        if let Some(previous) = &previous {
+            index.fingerprint.remove(previous.as_ref());
+        }
+        if index.remove(id) || previous.is_some() {
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    async fn trash(&self) -> Result<Vec<TrashedItem>> {
+        self.inner.trash().await
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<()> {
+        self.inner.restore(id).await?;
+
+        let Some(item) = self.inner.get(id).await? else {
+            return Ok(());
+        };
+        let embedding = item.embedding().map(decode_embedding).transpose()?;
+        let mut index = self.write_index()?;
+        index.fingerprint.add(item.as_ref());
+        if let Some(embedding) = embedding {
+            index.insert(*id, &embedding);
+        }
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn purge(&self, deleted_before: Option<DateTime<Utc>>) -> Result<usize> {
+        // Trashed items already left the index when they were deleted.
+        self.inner.purge(deleted_before).await
+    }
+
+    async fn vector_search(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(Box<dyn MemoryItem + Send + Sync>, f32)>> {
+        let hits = {
+            let index = self.read_index()?;
+            if index.dimension() != Some(embedding.len()) {
+                // Nothing comparable is indexed; let the backend decide.
+                None
+            } else {
+                Some(index.search(embedding, limit))
+            }
+        };
+        let Some(hits) = hits else {
+            return self.inner.vector_search(embedding, limit).await;
+        };
+
+        let mut results = Vec::with_capacity(hits.len());
+        for (id, score) in hits {
+            // Skip entries whose item was removed behind the index's back.
+            if let Some(item) = self.inner.get(&id).await? {
+                results.push((item, score));
+            }
+        }
+        Ok(results)
+    }
+
+    async fn add_relation(&self, from: &Uuid, to: &Uuid, relation_type: &str) -> Result<()> {
+        self.inner.add_relation(from, to, relation_type).await
+    }
+
+    async fn query(&self, query: &Query) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
+        self.inner.query(query).await
+    }
+
+    async fn search(&self, query: &Query) -> Result<Vec<SearchHit>> {
+        self.inner.search(query).await
+    }
+
+    async fn get_related(
+        &self,
+        id: &Uuid,
+        relation_type: Option<&str>,
+    ) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
+        self.inner.get_related(id, relation_type).await
+    }
+
+    async fn relations(&self) -> Result<Vec<Relation>> {
+        self.inner.relations().await
+    }
+
+    async fn versions(&self, id: &Uuid) -> Result<Vec<Version>> {
+        self.inner.versions(id).await
+    }
+
+    async fn version(&self, id: &Uuid, number: u32) -> Result<Option<Version>> {
+        self.inner.version(id, number).await
+    }
+
+    async fn record_run(&self, run: &Run) -> Result<()> {
+        self.inner.record_run(run).await
+    }
+
+    async fn runs(&self, id: &Uuid) -> Result<Vec<Run>> {
+        self.inner.runs(id).await
+    }
+
+    async fn record_usage(&self, usage: &Usage) -> Result<()> {
+        self.inner.record_usage(usage).await
+    }
+
+    async fn usage_stats(&self, place: Option<&str>) -> Result<Vec<UsageStats>> {
+        self.inner.usage_stats(place).await
+    }
+}
+
+/// Find the snippets most similar to `embedding`, best match first.
+///
+/// Non-snippet items returned by the backend are skipped.
+pub async fn search_similar_snippets(
+    backend: &dyn StorageBackend,
+    embedding: &[f32],
+    limit: usize,
+) -> Result<Vec<(SnippetWithTags, f32)>> {
+    let results = backend.vector_search(embedding, limit).await?;
+    Ok(results
+        .into_iter()
+        .filter_map(|(item, score)| {
+            item.as_any()
+                .downcast_ref::<SnippetWithTags>()
+                .map(|snippet| (snippet.clone(), score))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryBackend;
+    use crate::vector::encode_embedding;
+
+    fn embedded_snippet(title: &str, embedding: &[f32]) -> SnippetWithTags {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            format!("{title} content"),
+            vec![],
+        );
+        snippet.embedding = Some(encode_embedding(embedding).unwrap());
+        snippet
+    }
+
+    #[test]
+    fn test_index_insert_search_remove() {
+        let mut index = HnswIndex::default();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(index.insert(a, &[1.0, 0.0, 0.0]));
+        assert!(index.insert(b, &[0.9, 0.1, 0.0]));
+        assert!(index.insert(c, &[0.0, 0.0, 1.0]));
+        assert!(!index.insert(Uuid::new_v4(), &[1.0, 0.0]));
+
+        let hits = index.search(&[1.0, 0.0, 0.0], 2);
+        assert_eq!(
+            hits.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![a, b]
+        );
+        assert!((hits[0].1 - 1.0).abs() < 1e-5);
+
+        assert!(index.remove(&a));
+        assert!(!index.remove(&a));
+        assert_eq!(index.search(&[1.0, 0.0, 0.0], 1)[0].0, b);
+        assert_eq!((index.len(), index.deleted()), (2, 1));
+
+        // Re-inserting replaces the previous point
+        index.insert(c, &[1.0, 0.0, 0.0]);
+        assert_eq!(index.search(&[1.0, 0.0, 0.0], 1)[0].0, c);
+        assert_eq!((index.len(), index.deleted()), (2, 2));
+    }
+
+    #[test]
+    fn test_index_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snippets.db");
+
+        let mut index = HnswIndex::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        index.insert(a, &[1.0, 0.0]);
+        index.insert(b, &[0.0, 1.0]);
+        index.remove(&a);
+        index.save(&path).unwrap();
+
+        let loaded = HnswIndex::load(&path).unwrap().unwrap();
+        assert_eq!((loaded.len(), loaded.deleted()), (1, 1));
+        assert_eq!(loaded.search(&[0.1, 1.0], 5)[0].0, b);
+
+        assert!(HnswIndex::load(&dir.path().join("missing.db"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_indexed_backend_tracks_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snippets.db");
+
+        let inner = InMemoryBackend::default();
+        let existing = embedded_snippet("existing", &[0.0, 1.0]);
+        inner.save(&existing).await.unwrap();
+
+        let backend = IndexedBackend::open(Box::new(inner), &path).await.unwrap();
+        let added = embedded_snippet("added", &[1.0, 0.0]);
+        backend.save(&added).await.unwrap();
+
+        let results = search_similar_snippets(&backend, &[1.0, 0.1], 1)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.title, "added");
+
+        backend.delete(&added.id).await.unwrap();
+        let results = search_similar_snippets(&backend, &[1.0, 0.1], 1)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.title, "existing");
+
+        backend.persist().unwrap();
+        let saved = HnswIndex::load(&path).unwrap().unwrap();
+        assert_eq!((saved.len(), saved.deleted()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_indexed_backend_rebuilds_stale_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snippets.db");
+        let existing = embedded_snippet("existing", &[0.0, 1.0]);
+        let removed = embedded_snippet("removed", &[0.0, 1.0]);
+
+        let inner = InMemoryBackend::default();
+        inner.save(&existing).await.unwrap();
+        inner.save(&removed).await.unwrap();
+        let backend = IndexedBackend::open(Box::new(inner), &path).await.unwrap();
+        backend.delete(&removed.id).await.unwrap();
+        drop(backend);
+
+        // The saved index still matches a stash holding the same items
+        let inner = InMemoryBackend::default();
+        inner.save(&existing).await.unwrap();
+        let backend = IndexedBackend::open(Box::new(inner), &path).await.unwrap();
+        assert_eq!(backend.read_index().unwrap().deleted(), 1);
+        drop(backend);
+
+        // An item added without the index, say by a process that crashed
+        // before saving it, is picked up
+        let inner = InMemoryBackend::default();
+        inner.save(&existing).await.unwrap();
+        let added = embedded_snippet("added", &[1.0, 0.0]);
+        inner.save(&added).await.unwrap();
+        let backend = IndexedBackend::open(Box::new(inner), &path).await.unwrap();
+        let results = search_similar_snippets(&backend, &[1.0, 0.1], 1)
+            .await
+            .unwrap();
+        assert_eq!(results[0].0.title, "added");
+    }
+
+    #[tokio::test]
+    async fn test_indexed_backend_rejects_bad_embedding() {
+        let backend = IndexedBackend::new(Box::new(InMemoryBackend::default()))
+            .await
+            .unwrap();
+        let mut snippet = embedded_snippet("broken", &[1.0]);
+        snippet.embedding = Some(vec![1, 2, 3]);
+
+        assert!(backend.save(&snippet).await.is_err());
+        assert!(backend.get(&snippet.id).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_index_path_for() {
+        assert_eq!(
+            index_path_for("sqlite://data/snippets.db"),
+            Some(PathBuf::from("data/snippets.db"))
+        );
+        assert_eq!(index_path_for("sqlite://:memory:"), None);
+        assert_eq!(index_path_for(":memory:"), None);
+    }
+}
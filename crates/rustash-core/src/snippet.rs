@@ -1,12 +1,19 @@
 //! Snippet helper functions
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::error::{Error, Result};
 use crate::{
-    models::{Query, Snippet, SnippetWithTags},
+    export::{Export, ExportedSnippet, Exporter, SkippedExport},
+    history::{Version, VersionDiff},
+    import::{ImportPlan, ImportedSnippet, SkippedSnippet},
+    models::{Query, Run, Snippet, SnippetWithTags, TrashedItem, UpdateSnippet, SNIPPET_ITEM_TYPE},
+    placeholder::Template,
     storage::StorageBackend,
+    usage::{Usage, UsageStats},
 };
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -17,16 +24,13 @@ use uuid::Uuid;
 /// * `variables` - A map of variable names to their values
 ///
 /// # Returns
-/// The content with all placeholders replaced by their corresponding values
+/// The content with every placeholder that has a value or default replaced;
+/// see [`Template::render`] for a stricter, validating expansion.
 pub fn expand_placeholders(content_str: &str, variables: &HashMap<String, String>) -> String {
-    let mut result = content_str.to_string();
-
-    for (key, value) in variables {
-        let placeholder = format!("{{{{{}}}}}", key);
-        result = result.replace(&placeholder, value);
+    match Template::parse(content_str) {
+        Ok(template) => template.expand(variables),
+        Err(_) => content_str.to_string(),
     }
-
-    result
 }
 
 pub fn validate_snippet_content(snippet_title: &str, snippet_content: &str) -> Result<()> {
@@ -53,6 +57,106 @@ pub fn validate_snippet_content(snippet_title: &str, snippet_content: &str) -> R
     Ok(())
 }
 
+/// Tag marking a snippet whose command must always be confirmed before
+/// `snippets run` executes it, even with `--yes`
+pub const DANGEROUS_TAG: &str = "dangerous";
+
+/// Prefix of the tags that hold a snippet's aliases, e.g. `alias:deploy`
+pub const ALIAS_TAG_PREFIX: &str = "alias:";
+
+/// Build the tag that assigns `alias` to a snippet.
+///
+/// Aliases are short, user-assigned names (slugs) that
+/// [`SnippetService::resolve`] accepts in place of a UUID.
+pub fn alias_tag(alias: &str) -> Result<String> {
+    let alias = alias.strip_prefix(ALIAS_TAG_PREFIX).unwrap_or(alias);
+    if alias.is_empty() || alias.chars().any(char::is_whitespace) {
+        return Err(Error::validation(format!(
+            "Invalid alias '{}': aliases must be non-empty and contain no whitespace",
+            alias
+        )));
+    }
+    if alias.parse::<Uuid>().is_ok() {
+        return Err(Error::validation(format!(
+            "Invalid alias '{}': aliases cannot be UUIDs",
+            alias
+        )));
+    }
+    Ok(format!("{}{}", ALIAS_TAG_PREFIX, alias))
+}
+
+/// Fences the TOML front matter of a snippet document
+const FRONT_MATTER_FENCE: &str = "+++";
+
+/// The fields of a snippet kept in its front matter
+#[derive(Serialize, Deserialize)]
+struct FrontMatter {
+    title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Render a snippet as an editable document: TOML front matter holding the
+/// title and tags, followed by the content.
+///
+/// ```text
+/// +++
+/// title = "List files"
+/// tags = ["shell"]
+/// +++
+/// ls -la
+/// ```
+pub fn to_front_matter(snippet: &SnippetWithTags) -> Result<String> {
+    let header = toml::to_string(&FrontMatter {
+        title: snippet.title.clone(),
+        tags: snippet.tags.clone(),
+    })
+    .map_err(|e| Error::other(format!("Failed to render front matter: {}", e)))?;
+
+    Ok(format!(
+        "{FRONT_MATTER_FENCE}\n{header}{FRONT_MATTER_FENCE}\n{}\n",
+        snippet.content
+    ))
+}
+
+/// Parse a document in the [`to_front_matter`] format into an update that sets
+/// the title, tags and content.
+pub fn parse_front_matter(document: &str) -> Result<UpdateSnippet> {
+    let invalid = |reason: &str| Error::validation(format!("Invalid snippet document: {}", reason));
+
+    let rest = document
+        .strip_prefix(FRONT_MATTER_FENCE)
+        .and_then(|rest| {
+            rest.strip_prefix('\n')
+                .or_else(|| rest.strip_prefix("\r\n"))
+        })
+        .ok_or_else(|| invalid("it must start with a '+++' line"))?;
+
+    let mut header_len = 0;
+    let mut body = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_FENCE {
+            body = Some(&rest[header_len + line.len()..]);
+            break;
+        }
+        header_len += line.len();
+    }
+    let body = body.ok_or_else(|| invalid("the front matter is not closed by a '+++' line"))?;
+
+    let front_matter: FrontMatter =
+        toml::from_str(&rest[..header_len]).map_err(|e| invalid(e.message()))?;
+    // The renderer ends the content with a newline; editors usually keep it.
+    let content = body
+        .strip_suffix('\n')
+        .map(|content| content.strip_suffix('\r').unwrap_or(content))
+        .unwrap_or(body);
+
+    Ok(UpdateSnippet::new()
+        .with_title(front_matter.title)
+        .with_tags(front_matter.tags)
+        .with_content(content.to_string()))
+}
+
 /// High level service for snippet-related operations.
 pub struct SnippetService {
     backend: Arc<Box<dyn StorageBackend>>,
@@ -81,10 +185,423 @@ impl SnippetService {
             .collect())
     }
 
+    /// Find the snippet a user-supplied reference points to.
+    ///
+    /// `reference` may be a full UUID, an alias, an exact title or a unique
+    /// UUID prefix, tried in that order. A reference matching several
+    /// snippets at the same step is reported as [`Error::Ambiguous`].
+    pub async fn resolve(&self, reference: &str) -> Result<SnippetWithTags> {
+        let reference = reference.trim();
+        if reference.is_empty() {
+            return Err(Error::validation("Snippet reference cannot be empty"));
+        }
+        if let Ok(id) = reference.parse::<Uuid>() {
+            return self
+                .get_snippet_by_id(&id)
+                .await?
+                .ok_or_else(|| Error::not_found(format!("Snippet {}", id)));
+        }
+
+        if let Ok(tag) = alias_tag(reference) {
+            let query = Query {
+                tags: Some(vec![tag]),
+                item_type: Some(SNIPPET_ITEM_TYPE.to_string()),
+                ..Default::default()
+            };
+            let by_alias = self.list_all_snippets(&query).await?;
+            if let Some(snippet) = single_match(reference, by_alias)? {
+                return Ok(snippet);
+            }
+        }
+
+        let query = Query {
+            item_type: Some(SNIPPET_ITEM_TYPE.to_string()),
+            ..Default::default()
+        };
+        let snippets = self.list_all_snippets(&query).await?;
+        let (by_title, others): (Vec<_>, Vec<_>) = snippets
+            .into_iter()
+            .partition(|snippet| snippet.title == reference);
+        if let Some(snippet) = single_match(reference, by_title)? {
+            return Ok(snippet);
+        }
+
+        let prefix = reference.to_ascii_lowercase();
+        if prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            let by_prefix = others
+                .into_iter()
+                .filter(|snippet| snippet.id.to_string().starts_with(&prefix))
+                .collect();
+            if let Some(snippet) = single_match(reference, by_prefix)? {
+                return Ok(snippet);
+            }
+        }
+
+        Err(Error::not_found(format!(
+            "Snippet matching '{}'",
+            reference
+        )))
+    }
+
+    /// Like [`resolve`](Self::resolve), but returns only the id.
+    ///
+    /// Full UUIDs are passed through untouched, so they may also name items
+    /// that are not snippets.
+    pub async fn resolve_id(&self, reference: &str) -> Result<Uuid> {
+        match reference.trim().parse::<Uuid>() {
+            Ok(id) => Ok(id),
+            Err(_) => Ok(self.resolve(reference).await?.id),
+        }
+    }
+
+    /// Parse a snippet's content with every snippet it includes inlined.
+    ///
+    /// `{{> reference}}` includes are resolved like
+    /// [`resolve`](Self::resolve), and so are the includes of included
+    /// snippets. A snippet that ends up including itself is an error.
+    pub async fn compose(&self, snippet: &SnippetWithTags) -> Result<Template> {
+        let root = Template::parse(&snippet.content)?;
+
+        // Load every snippet reachable through includes first, so inlining
+        // them needs no further lookups.
+        let mut loaded: HashMap<String, SnippetWithTags> = HashMap::new();
+        let mut templates: HashMap<Uuid, Template> = HashMap::new();
+        let mut pending: Vec<(String, String)> = root
+            .includes()
+            .into_iter()
+            .map(|reference| (reference.to_string(), snippet.title.clone()))
+            .collect();
+        while let Some((reference, parent)) = pending.pop() {
+            if loaded.contains_key(&reference) {
+                continue;
+            }
+            let included = self.resolve(&reference).await.map_err(|err| match err {
+                Error::NotFound(_) => {
+                    Error::not_found(format!("Snippet '{}' included by '{}'", reference, parent))
+                }
+                other => other,
+            })?;
+            if included.id != snippet.id && !templates.contains_key(&included.id) {
+                let template = Template::parse(&included.content).map_err(|err| match err {
+                    Error::Validation(reason) => Error::validation(format!(
+                        "In included snippet '{}': {}",
+                        included.title, reason
+                    )),
+                    other => other,
+                })?;
+                pending.extend(
+                    template
+                        .includes()
+                        .into_iter()
+                        .map(|reference| (reference.to_string(), included.title.clone())),
+                );
+                templates.insert(included.id, template);
+            }
+            loaded.insert(reference, included);
+        }
+
+        inline_includes(&root, &loaded, &templates, &mut vec![snippet])
+    }
+
     /// Save a snippet to the backend.
     pub async fn save_snippet(&self, snippet: &Snippet) -> Result<()> {
         self.backend.save(snippet).await
     }
+
+    /// List the versions of a snippet, oldest first.
+    pub async fn history(&self, id: &Uuid) -> Result<Vec<Version>> {
+        self.backend.versions(id).await
+    }
+
+    /// Retrieve one version of a snippet.
+    pub async fn version(&self, id: &Uuid, number: u32) -> Result<Version> {
+        self.backend
+            .version(id, number)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Version {} of snippet {}", number, id)))
+    }
+
+    /// Compare two versions of a snippet.
+    pub async fn diff(&self, id: &Uuid, from: u32, to: u32) -> Result<VersionDiff> {
+        let old = self.version(id, from).await?;
+        let new = self.version(id, to).await?;
+        Ok(VersionDiff::between(&old, &new))
+    }
+
+    /// Bring back the title, content and tags of an earlier version.
+    ///
+    /// The restored state is saved as a new version, so the restore itself
+    /// can be undone.
+    pub async fn restore_version(&self, id: &Uuid, number: u32) -> Result<SnippetWithTags> {
+        let mut snippet = self
+            .get_snippet_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Snippet {}", id)))?;
+        let version = self.version(id, number).await?;
+
+        snippet.title = version.title;
+        snippet.content = version.content;
+        snippet.tags = version.tags;
+        snippet.updated_at = chrono::Utc::now();
+        self.backend.save(&snippet).await?;
+        Ok(snippet)
+    }
+
+    /// Apply `update` to a snippet and save it.
+    ///
+    /// Fields the update leaves unset keep their current value, and the
+    /// result is validated like a new snippet.
+    pub async fn update_snippet(
+        &self,
+        id: &Uuid,
+        update: &UpdateSnippet,
+    ) -> Result<SnippetWithTags> {
+        let mut snippet = self
+            .get_snippet_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Snippet {}", id)))?;
+        update.apply(&mut snippet)?;
+        validate_snippet_content(&snippet.title, &snippet.content)?;
+        self.check_aliases(id, &snippet.tags).await?;
+        self.backend.save(&snippet).await?;
+        Ok(snippet)
+    }
+
+    /// Move a snippet to the trash, returning it as it was.
+    pub async fn delete_snippet(&self, id: &Uuid) -> Result<SnippetWithTags> {
+        let snippet = self
+            .get_snippet_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Snippet {}", id)))?;
+        self.backend.delete(id).await?;
+        Ok(snippet)
+    }
+
+    /// List the snippets in the trash, most recently deleted first.
+    pub async fn trash(&self) -> Result<Vec<TrashedItem>> {
+        let mut trashed = self.backend.trash().await?;
+        trashed.retain(|entry| entry.item.item_type() == SNIPPET_ITEM_TYPE);
+        Ok(trashed)
+    }
+
+    /// Take a snippet back out of the trash.
+    pub async fn restore_snippet(&self, id: &Uuid) -> Result<SnippetWithTags> {
+        self.backend.restore(id).await?;
+        self.get_snippet_by_id(id)
+            .await?
+            .ok_or_else(|| Error::not_found(format!("Snippet {}", id)))
+    }
+
+    /// Permanently remove everything in the trash, or only what was deleted
+    /// more than `older_than_days` days ago. Returns how many items were removed.
+    pub async fn empty_trash(&self, older_than_days: Option<u32>) -> Result<usize> {
+        let cutoff = older_than_days.map(|days| Utc::now() - Duration::days(i64::from(days)));
+        self.backend.purge(cutoff).await
+    }
+
+    /// Record an execution of a snippet.
+    pub async fn record_run(&self, run: &Run) -> Result<()> {
+        self.backend.record_run(run).await
+    }
+
+    /// List the recorded runs of a snippet, oldest first.
+    pub async fn runs(&self, id: &Uuid) -> Result<Vec<Run>> {
+        self.backend.runs(id).await
+    }
+
+    /// Record that a snippet was just used, in the current directory.
+    pub async fn record_use(&self, id: &Uuid) -> Result<()> {
+        self.backend.record_usage(&Usage::now(*id)).await
+    }
+
+    /// Up to `limit` snippets with their usage, most recently used first.
+    ///
+    /// When `place` is given only uses made there count, so snippets never
+    /// used there are left out.
+    pub async fn recent(
+        &self,
+        limit: usize,
+        place: Option<&str>,
+    ) -> Result<Vec<(SnippetWithTags, UsageStats)>> {
+        let mut used = self.backend.usage_stats(place).await?;
+        used.sort_by_key(|stats| std::cmp::Reverse(stats.last_used_at));
+
+        let mut recent = Vec::new();
+        for stats in used {
+            if recent.len() == limit {
+                break;
+            }
+            // Uses of trashed items and other item types are skipped
+            if let Some(snippet) = self.get_snippet_by_id(&stats.item_id).await? {
+                recent.push((snippet, stats));
+            }
+        }
+        Ok(recent)
+    }
+
+    /// Work out what importing `imported` would add.
+    ///
+    /// Snippets with the same content as one in the stash, or as an earlier
+    /// one in the import, are skipped, as are invalid ones. Aliases already
+    /// taken are dropped.
+    pub async fn plan_import(&self, imported: Vec<ImportedSnippet>) -> Result<ImportPlan> {
+        let query = Query {
+            item_type: Some(SNIPPET_ITEM_TYPE.to_string()),
+            ..Default::default()
+        };
+        let existing = self.list_all_snippets(&query).await?;
+        let mut titles_by_content: HashMap<String, String> = existing
+            .iter()
+            .map(|snippet| (snippet.content.trim().to_string(), snippet.title.clone()))
+            .collect();
+        let mut aliases: HashSet<String> = existing
+            .iter()
+            .flat_map(|snippet| &snippet.tags)
+            .filter(|tag| tag.starts_with(ALIAS_TAG_PREFIX))
+            .cloned()
+            .collect();
+
+        let mut plan = ImportPlan::default();
+        for snippet in imported {
+            let reason = match validate_snippet_content(&snippet.title, &snippet.content) {
+                Err(e) => Some(e.to_string()),
+                Ok(()) => titles_by_content
+                    .get(snippet.content.trim())
+                    .map(|title| format!("Same content as '{}'", title)),
+            };
+            if let Some(reason) = reason {
+                plan.skipped.push(SkippedSnippet {
+                    title: snippet.title,
+                    source: snippet.source,
+                    reason,
+                });
+                continue;
+            }
+            titles_by_content.insert(snippet.content.trim().to_string(), snippet.title.clone());
+
+            let mut tags: Vec<String> = Vec::new();
+            for tag in snippet.tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            for alias in snippet.aliases {
+                match alias_tag(&alias) {
+                    Ok(tag) if aliases.insert(tag.clone()) => tags.push(tag),
+                    _ => plan.dropped_aliases.push(alias),
+                }
+            }
+            plan.new.push(SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                snippet.title,
+                snippet.content,
+                tags,
+            ));
+        }
+        Ok(plan)
+    }
+
+    /// Add the new snippets of an import plan to the stash.
+    pub async fn import(&self, plan: &ImportPlan) -> Result<()> {
+        for snippet in &plan.new {
+            self.backend.save(snippet).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the snippets matching `query` in another tool's format, in
+    /// title order and with their includes inlined.
+    ///
+    /// Snippets whose includes cannot be resolved, and those the format
+    /// cannot express, are left out and listed in [`Export::skipped`].
+    pub async fn export(&self, query: &Query, exporter: &dyn Exporter) -> Result<Export> {
+        let query = Query {
+            item_type: Some(SNIPPET_ITEM_TYPE.to_string()),
+            ..query.clone()
+        };
+        let mut snippets = self.list_all_snippets(&query).await?;
+        snippets.sort_by(|a, b| a.title.cmp(&b.title).then(a.id.cmp(&b.id)));
+
+        let mut exported = Vec::new();
+        let mut skipped = Vec::new();
+        for snippet in snippets {
+            match self.compose(&snippet).await {
+                Ok(template) => exported.push(ExportedSnippet::new(snippet, template)),
+                Err(e) => skipped.push(SkippedExport {
+                    title: snippet.title,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+        let mut export = exporter.export(&exported);
+        export.skipped.extend(skipped);
+        export.skipped.sort_by(|a, b| a.title.cmp(&b.title));
+        Ok(export)
+    }
+
+    /// Reject alias tags in `tags` that are malformed or already taken by a
+    /// snippet other than `id`, so every alias resolves to one snippet.
+    pub async fn check_aliases(&self, id: &Uuid, tags: &[String]) -> Result<()> {
+        for tag in tags.iter().filter(|tag| tag.starts_with(ALIAS_TAG_PREFIX)) {
+            let tag = alias_tag(tag)?;
+            let query = Query {
+                tags: Some(vec![tag.clone()]),
+                item_type: Some(SNIPPET_ITEM_TYPE.to_string()),
+                ..Default::default()
+            };
+            let owners = self.list_all_snippets(&query).await?;
+            if let Some(owner) = owners.iter().find(|owner| owner.id != *id) {
+                return Err(Error::duplicate(format!(
+                    "Alias '{}' of snippet '{}'",
+                    &tag[ALIAS_TAG_PREFIX.len()..],
+                    owner.title
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Inline the includes of `template`, which belongs to the last snippet in
+/// `chain`, failing if an include leads back to a snippet in the chain.
+fn inline_includes<'a>(
+    template: &Template,
+    loaded: &'a HashMap<String, SnippetWithTags>,
+    templates: &HashMap<Uuid, Template>,
+    chain: &mut Vec<&'a SnippetWithTags>,
+) -> Result<Template> {
+    template.inline(&mut |reference| {
+        let included = &loaded[reference];
+        if chain.iter().any(|snippet| snippet.id == included.id) {
+            let cycle: Vec<&str> = chain
+                .iter()
+                .chain([&included])
+                .map(|snippet| snippet.title.as_str())
+                .collect();
+            return Err(Error::validation(format!(
+                "Snippet includes form a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        chain.push(included);
+        let inlined = inline_includes(&templates[&included.id], loaded, templates, chain);
+        chain.pop();
+        inlined
+    })
+}
+
+/// Pick the only snippet in `matches`, if there is exactly one.
+fn single_match(reference: &str, matches: Vec<SnippetWithTags>) -> Result<Option<SnippetWithTags>> {
+    if matches.len() > 1 {
+        return Err(Error::ambiguous(
+            reference,
+            matches
+                .iter()
+                .map(|snippet| format!("{}  {}", snippet.id, snippet.title)),
+        ));
+    }
+    Ok(matches.into_iter().next())
 }
 
 #[cfg(test)]
@@ -110,6 +627,136 @@ mod tests {
         assert_eq!(expanded_missing, "Hello Alice, how is {{location}}?");
     }
 
+    #[tokio::test]
+    async fn test_restore_version() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Deploy".to_string(),
+            "make deploy".to_string(),
+            vec!["ops".to_string()],
+        );
+        backend.save(&snippet).await.unwrap();
+        snippet.content = "make deploy --force".to_string();
+        backend.save(&snippet).await.unwrap();
+
+        let diff = service.diff(&snippet.id, 1, 2).await.unwrap();
+        assert!(!diff.is_empty());
+
+        let restored = service.restore_version(&snippet.id, 1).await.unwrap();
+        assert_eq!(restored.content, "make deploy");
+
+        let history = service.history(&snippet.id).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].content, "make deploy");
+
+        assert!(service
+            .restore_version(&snippet.id, 9)
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[test]
+    fn test_front_matter_round_trip() {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Say \"hi\"".to_string(),
+            "+++\necho hi\n".to_string(),
+            vec!["shell".to_string()],
+        );
+
+        let document = to_front_matter(&snippet).unwrap();
+        assert!(document.starts_with("+++\ntitle = "));
+
+        let edited = document.replace("echo hi", "echo hello");
+        parse_front_matter(&edited)
+            .unwrap()
+            .apply(&mut snippet)
+            .unwrap();
+        assert_eq!(snippet.title, "Say \"hi\"");
+        assert_eq!(snippet.content, "+++\necho hello\n");
+        assert_eq!(snippet.tags, vec!["shell"]);
+
+        assert!(parse_front_matter("echo hi").is_err());
+        assert!(parse_front_matter("+++\ntitle = \"x\"\necho hi").is_err());
+        assert!(parse_front_matter("+++\ntags = []\n+++\necho hi").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_snippet() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+
+        let snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Deploy".to_string(),
+            "make deploy".to_string(),
+            vec!["ops".to_string()],
+        );
+        backend.save(&snippet).await.unwrap();
+
+        let update = UpdateSnippet::new().with_tags(vec!["ops".to_string(), "ci".to_string()]);
+        let updated = service.update_snippet(&snippet.id, &update).await.unwrap();
+        assert_eq!(updated.title, "Deploy");
+        assert_eq!(updated.tags, vec!["ops", "ci"]);
+        assert_eq!(service.history(&snippet.id).await.unwrap().len(), 2);
+
+        let blank = UpdateSnippet::new().with_content("  ".to_string());
+        assert!(service.update_snippet(&snippet.id, &blank).await.is_err());
+        assert!(service
+            .update_snippet(&Uuid::new_v4(), &update)
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_trash() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+
+        let snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Deploy".to_string(),
+            "make deploy".to_string(),
+            vec![],
+        );
+        backend.save(&snippet).await.unwrap();
+
+        let deleted = service.delete_snippet(&snippet.id).await.unwrap();
+        assert_eq!(deleted.title, "Deploy");
+        assert!(service
+            .get_snippet_by_id(&snippet.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(service.trash().await.unwrap().len(), 1);
+        assert!(service
+            .delete_snippet(&snippet.id)
+            .await
+            .unwrap_err()
+            .is_not_found());
+
+        let restored = service.restore_snippet(&snippet.id).await.unwrap();
+        assert_eq!(restored.content, "make deploy");
+        assert!(service.trash().await.unwrap().is_empty());
+
+        service.delete_snippet(&snippet.id).await.unwrap();
+        assert_eq!(service.empty_trash(None).await.unwrap(), 1);
+        assert!(service
+            .restore_snippet(&snippet.id)
+            .await
+            .unwrap_err()
+            .is_not_found());
+        assert!(service.history(&snippet.id).await.unwrap().is_empty());
+    }
+
     #[test]
     fn test_validate_snippet_content() {
         assert!(validate_snippet_content("Title", "Content").is_ok());
@@ -125,4 +772,177 @@ mod tests {
         let long_content = "a".repeat(100_001);
         assert!(validate_snippet_content("Title", &long_content).is_err());
     }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+
+        let deploy = SnippetWithTags::with_uuid(
+            Uuid::parse_str("3c4a6123-ac9e-4527-81c8-be8ebac5f455").unwrap(),
+            "Deploy".to_string(),
+            "make deploy".to_string(),
+            vec!["alias:ship".to_string()],
+        );
+        let build = SnippetWithTags::with_uuid(
+            Uuid::parse_str("3c4b0000-0000-4000-8000-000000000000").unwrap(),
+            "Build".to_string(),
+            "make".to_string(),
+            vec![],
+        );
+        backend.save(&deploy).await.unwrap();
+        backend.save(&build).await.unwrap();
+
+        let resolve = |reference: &'static str| {
+            let service = &service;
+            async move { service.resolve(reference).await.map(|s| s.title) }
+        };
+        assert_eq!(
+            resolve("3c4a6123-ac9e-4527-81c8-be8ebac5f455")
+                .await
+                .unwrap(),
+            "Deploy"
+        );
+        assert_eq!(resolve("3C4A").await.unwrap(), "Deploy");
+        assert_eq!(resolve("Build").await.unwrap(), "Build");
+        assert_eq!(resolve("ship").await.unwrap(), "Deploy");
+        assert!(resolve("3c4").await.unwrap_err().is_ambiguous());
+        assert!(resolve("deploy").await.unwrap_err().is_not_found());
+
+        let taken = UpdateSnippet::new().with_tags(vec!["alias:ship".to_string()]);
+        assert!(service
+            .update_snippet(&build.id, &taken)
+            .await
+            .unwrap_err()
+            .is_duplicate());
+        assert!(alias_tag("two words").is_err());
+        assert_eq!(alias_tag("alias:ship").unwrap(), "alias:ship");
+    }
+
+    #[tokio::test]
+    async fn test_compose() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+
+        let snippet = |title: &str, content: &str| {
+            SnippetWithTags::with_uuid(Uuid::new_v4(), title.into(), content.into(), vec![])
+        };
+        let auth = snippet("Auth", "-H 'Authorization: Bearer {{token}}'");
+        let get = snippet("Get", "curl {{> Auth}} {{url}}");
+        let post = snippet(
+            "Post",
+            "{{> Get}} -d '{{body}}'{{#if retry}} {{> Auth}}{{/if}}",
+        );
+        for item in [&auth, &get, &post] {
+            backend.save(item).await.unwrap();
+        }
+
+        let template = service.compose(&post).await.unwrap();
+        let names: Vec<_> = template.placeholders().iter().map(|p| &p.name).collect();
+        assert_eq!(names, ["body", "retry", "url", "token"]);
+        let missing = template.render(&HashMap::new()).unwrap_err().to_string();
+        assert!(missing.contains("'token', 'url', 'body'"));
+
+        let values = HashMap::from([
+            ("token".to_string(), "t".to_string()),
+            ("url".to_string(), "x".to_string()),
+            ("body".to_string(), "{}".to_string()),
+        ]);
+        assert_eq!(
+            template.render(&values).unwrap(),
+            "curl -H 'Authorization: Bearer t' x -d '{}'"
+        );
+
+        let cycle = snippet("Loop", "{{> Loop2}}");
+        backend.save(&cycle).await.unwrap();
+        backend.save(&snippet("Loop2", "{{> Loop}}")).await.unwrap();
+        let error = service.compose(&cycle).await.unwrap_err().to_string();
+        assert!(error.contains("Loop -> Loop2 -> Loop"));
+
+        let dangling = snippet("Dangling", "{{> Nowhere}}");
+        assert!(service.compose(&dangling).await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_plan_import() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+        let existing = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Status".to_string(),
+            "git status".to_string(),
+            vec!["alias:st".to_string()],
+        );
+        backend.save(&existing).await.unwrap();
+
+        let imported = |title: &str, content: &str, aliases: &[&str]| ImportedSnippet {
+            title: title.to_string(),
+            content: content.to_string(),
+            tags: vec!["git".to_string(), "git".to_string()],
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            source: "git.cheat".into(),
+        };
+        let plan = service
+            .plan_import(vec![
+                imported("Show status", "git status\n", &[]),
+                imported("Log", "git log", &["st", "lg"]),
+                imported("Log again", "git log", &[]),
+                imported("Empty", " ", &[]),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(plan.new.len(), 1);
+        assert_eq!(plan.new[0].title, "Log");
+        assert_eq!(plan.new[0].tags, vec!["git", "alias:lg"]);
+        assert_eq!(plan.dropped_aliases, vec!["st"]);
+        let reasons: Vec<_> = plan.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(
+            reasons[..2],
+            ["Same content as 'Status'", "Same content as 'Log'"]
+        );
+        assert!(reasons[2].contains("content cannot be empty"));
+
+        service.import(&plan).await.unwrap();
+        assert_eq!(service.resolve("lg").await.unwrap().title, "Log");
+    }
+
+    #[tokio::test]
+    async fn test_export() {
+        let backend: Arc<Box<dyn StorageBackend>> =
+            Arc::new(Box::new(crate::storage::InMemoryBackend::new()));
+        let service = SnippetService::new(backend.clone());
+        for (title, content, tags) in [
+            ("Remote", "ssh {{host}}", vec!["net", "alias:remote"]),
+            ("Copy", "scp {{file}} {{> remote}}:", vec!["net"]),
+            ("Broken", "{{> missing}}", vec!["net"]),
+            ("List", "ls", vec![]),
+        ] {
+            let snippet = SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                title.to_string(),
+                content.to_string(),
+                tags.into_iter().map(str::to_string).collect(),
+            );
+            backend.save(&snippet).await.unwrap();
+        }
+
+        let query = Query {
+            tags: Some(vec!["net".to_string()]),
+            ..Default::default()
+        };
+        let export = service
+            .export(&query, &crate::export::PetExporter)
+            .await
+            .unwrap();
+        assert_eq!(export.count, 2);
+        assert_eq!(export.skipped.len(), 1);
+        assert_eq!(export.skipped[0].title, "Broken");
+        let copy = export.text.find("scp <file> ssh <host>:").unwrap();
+        assert!(copy < export.text.find("description = \"Remote\"").unwrap());
+        assert!(!export.text.contains("ls"));
+    }
 }
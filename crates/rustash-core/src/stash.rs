@@ -2,6 +2,7 @@
 
 use crate::storage::StorageBackend;
 use crate::Result;
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -16,6 +17,10 @@ pub enum ServiceType {
 pub struct StashConfig {
     pub service_type: ServiceType,
     pub database_url: String,
+    /// Days a deleted item stays in the trash before it is purged for good.
+    /// Without one, trashed items are kept until the trash is emptied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_retention_days: Option<u32>,
 }
 
 /// Represents a live, initialized Stash with a name, config, and active backend.
@@ -27,12 +32,28 @@ pub struct Stash {
 
 impl Stash {
     /// Creates a new, initialized Stash by setting up its backend.
+    ///
+    /// Items that have outlived the trash retention period are purged.
     pub async fn new(name: &str, config: StashConfig) -> Result<Self> {
         let backend = Arc::new(crate::create_backend(&config.database_url).await?);
-        Ok(Self {
+        let stash = Self {
             name: name.to_string(),
             config,
             backend,
-        })
+        };
+        stash.purge_expired().await?;
+        Ok(stash)
+    }
+
+    /// Permanently remove trashed items older than the retention period,
+    /// returning how many were removed.
+    pub async fn purge_expired(&self) -> Result<usize> {
+        match self.config.trash_retention_days {
+            Some(days) => {
+                let cutoff = Utc::now() - Duration::days(i64::from(days));
+                self.backend.purge(Some(cutoff)).await
+            }
+            None => Ok(0),
+        }
     }
 }
@@ -1,10 +1,16 @@
 //! In-memory storage backend for Rustash.
 
 use crate::error::{Error, Result};
-use crate::memory::MemoryItem;
+use crate::history::{next_version, Version};
+use crate::memory::{title_and_tags, MemoryItem};
+use crate::models::{Page, Query, Relation, Run, SearchHit, TrashedItem};
+use crate::storage::{rank::query_ranked, validate_relation_type};
+use crate::usage::{Usage, UsageStats};
+use crate::vector::{decode_embedding, DistanceMetric, Scorer, TopK};
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use uuid::Uuid;
 
 /// A trait defining the contract for storage backends.
@@ -12,14 +18,35 @@ use uuid::Uuid;
 #[async_trait]
 pub trait StorageBackend: Send + Sync + std::fmt::Debug {
     /// Save a memory item to the storage.
+    ///
+    /// The item's `created_at` and `updated_at` are stored as they are, so
+    /// callers stamp their own changes. Saving an item that is in the trash
+    /// restores it.
     async fn save(&self, item: &(dyn MemoryItem + Send + Sync)) -> Result<()>;
 
     /// Retrieve a memory item by its ID.
     async fn get(&self, id: &Uuid) -> Result<Option<Box<dyn MemoryItem + Send + Sync>>>;
 
-    /// Delete a memory item by its ID.
+    /// Move a memory item to the trash.
+    ///
+    /// Trashed items are hidden from every read until they are restored or
+    /// purged. Deleting a missing or already trashed item does nothing.
     async fn delete(&self, id: &Uuid) -> Result<()>;
 
+    /// List the items in the trash, most recently deleted first.
+    async fn trash(&self) -> Result<Vec<TrashedItem>>;
+
+    /// Take an item back out of the trash.
+    ///
+    /// The item's `updated_at` is set to the current time, so a restore
+    /// counts as a change, for instance to sync.
+    async fn restore(&self, id: &Uuid) -> Result<()>;
+
+    /// Permanently remove trashed items deleted before `deleted_before`, or
+    /// every trashed item when it is `None`, along with their history and
+    /// relations, runs and uses. Returns how many items were removed.
+    async fn purge(&self, deleted_before: Option<DateTime<Utc>>) -> Result<usize>;
+
     /// Perform a vector similarity search.
     async fn vector_search(
         &self,
@@ -47,95 +74,545 @@ pub trait StorageBackend: Send + Sync + std::fmt::Debug {
         id: &Uuid,
         relation_type: Option<&str>,
     ) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>>;
+
+    /// List every relation between items that are not in the trash, in
+    /// order.
+    async fn relations(&self) -> Result<Vec<Relation>>;
+
+    /// List the recorded versions of an item, oldest first.
+    ///
+    /// A version is recorded whenever a save creates the item or changes its
+    /// title, content or tags. Purging the item drops its history.
+    async fn versions(&self, id: &Uuid) -> Result<Vec<Version>>;
+
+    /// Fetch one recorded version of an item.
+    async fn version(&self, id: &Uuid, number: u32) -> Result<Option<Version>> {
+        Ok(self
+            .versions(id)
+            .await?
+            .into_iter()
+            .find(|version| version.number == number))
+    }
+
+    /// Record that an item was run.
+    async fn record_run(&self, run: &Run) -> Result<()>;
+
+    /// List the recorded runs of an item, oldest first.
+    async fn runs(&self, id: &Uuid) -> Result<Vec<Run>>;
+
+    /// Record that an item was used.
+    async fn record_usage(&self, usage: &Usage) -> Result<()>;
+
+    /// Summarise the recorded uses of every item that has any, counting only
+    /// uses made in `place` (see [`Usage::place`]) when given.
+    async fn usage_stats(&self, place: Option<&str>) -> Result<Vec<UsageStats>>;
+
+    /// Run a ranked full-text search, most relevant first.
+    ///
+    /// Backends without a full-text index fall back to `query`, keeping its
+    /// order and reporting no scores or highlights.
+    async fn search(&self, query: &crate::models::Query) -> Result<Vec<SearchHit>> {
+        Ok(self
+            .query(query)
+            .await?
+            .into_iter()
+            .map(SearchHit::unranked)
+            .collect())
+    }
+
+    /// Fetch one page of `query` results plus a cursor for the next page.
+    ///
+    /// Backends only need to honour `Query::after` and `Query::offset` in
+    /// `query`; this fetches one extra item to tell whether more remain.
+    async fn query_page(&self, query: &crate::models::Query) -> Result<Page> {
+        let Some(limit) = query.limit else {
+            let items = self.query(query).await?;
+            return Ok(Page {
+                items,
+                next_cursor: None,
+            });
+        };
+
+        let mut probe = query.clone();
+        probe.limit = Some(limit + 1);
+        let mut items = self.query(&probe).await?;
+
+        let next_cursor = match query.sort_spec()? {
+            Some(spec) if items.len() > limit => {
+                items.truncate(limit);
+                items
+                    .last()
+                    .map(|last| spec.cursor_after(last.as_ref()))
+                    .transpose()?
+            }
+            _ => {
+                items.truncate(limit);
+                None
+            }
+        };
+        Ok(Page { items, next_cursor })
+    }
+}
+
+/// A relation stored in the in-memory adjacency list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edge {
+    to: Uuid,
+    relation_type: String,
 }
 
-/// A simple in-memory implementation for testing and development.
+/// A complete in-memory backend.
+///
+/// Nothing is persisted, which makes it suitable for tests and throwaway
+/// stashes (`memory://` URLs). It is also the reference implementation of the
+/// `StorageBackend` contract: text filters match every term case-insensitively,
+/// tags are matched with [`TagFilter::matches`](crate::models::TagFilter::matches),
+/// and similarity is computed by brute force.
 #[derive(Debug, Default)]
 pub struct InMemoryBackend {
     items: RwLock<HashMap<Uuid, Box<dyn MemoryItem + Send + Sync>>>,
+    relations: RwLock<HashMap<Uuid, Vec<Edge>>>,
+    versions: RwLock<HashMap<Uuid, Vec<Version>>>,
+    runs: RwLock<HashMap<Uuid, Vec<Run>>>,
+    usage: RwLock<HashMap<Uuid, Vec<Usage>>>,
+    /// When each trashed item was deleted
+    trashed: RwLock<HashMap<Uuid, DateTime<Utc>>>,
+    metric: DistanceMetric,
+}
+
+impl InMemoryBackend {
+    /// Create an empty backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given metric for `vector_search` instead of cosine similarity.
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    fn read_items(
+        &self,
+    ) -> Result<RwLockReadGuard<'_, HashMap<Uuid, Box<dyn MemoryItem + Send + Sync>>>> {
+        self.items
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_items(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, Box<dyn MemoryItem + Send + Sync>>>> {
+        self.items
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn read_relations(&self) -> Result<RwLockReadGuard<'_, HashMap<Uuid, Vec<Edge>>>> {
+        self.relations
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_relations(&self) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, Vec<Edge>>>> {
+        self.relations
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn read_versions(&self) -> Result<RwLockReadGuard<'_, HashMap<Uuid, Vec<Version>>>> {
+        self.versions
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_versions(&self) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, Vec<Version>>>> {
+        self.versions
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn read_runs(&self) -> Result<RwLockReadGuard<'_, HashMap<Uuid, Vec<Run>>>> {
+        self.runs
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_runs(&self) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, Vec<Run>>>> {
+        self.runs
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn read_usage(&self) -> Result<RwLockReadGuard<'_, HashMap<Uuid, Vec<Usage>>>> {
+        self.usage
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_usage(&self) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, Vec<Usage>>>> {
+        self.usage
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn read_trashed(&self) -> Result<RwLockReadGuard<'_, HashMap<Uuid, DateTime<Utc>>>> {
+        self.trashed
+            .read()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    fn write_trashed(&self) -> Result<RwLockWriteGuard<'_, HashMap<Uuid, DateTime<Utc>>>> {
+        self.trashed
+            .write()
+            .map_err(|_| Error::other("RwLock poisoned"))
+    }
+
+    /// Score and filter items against `query`, in result order.
+    fn matching(&self, query: &Query) -> Result<Vec<(Box<dyn MemoryItem + Send + Sync>, f32)>> {
+        let terms: Vec<String> = query
+            .text_filter
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        let tag_filter = query.effective_tag_filter();
+
+        let items = self.read_items()?;
+        let trashed = self.read_trashed()?;
+        let mut matches = Vec::new();
+        for item in items.values() {
+            if trashed.contains_key(&item.id()) {
+                continue;
+            }
+            if let Some(item_type) = &query.item_type {
+                if item.item_type() != item_type {
+                    continue;
+                }
+            }
+
+            let (title, tags) = title_and_tags(item.as_ref());
+            if !tag_filter.matches(&tags) {
+                continue;
+            }
+
+            let score = match text_score(&terms, &title, item.content(), &tags) {
+                Some(score) => score,
+                None => continue,
+            };
+            matches.push((item.clone_dyn_send_sync(), score));
+        }
+        drop(items);
+        drop(trashed);
+
+        match query.sort_spec()? {
+            Some(spec) => matches.sort_by(|a, b| spec.compare(a.0.as_ref(), b.0.as_ref())),
+            // Relevance first when searching, mirroring the FTS backends
+            None => matches.sort_by(|a, b| {
+                b.1.total_cmp(&a.1)
+                    .then_with(|| a.0.created_at().cmp(&b.0.created_at()))
+                    .then_with(|| a.0.id().cmp(&b.0.id()))
+            }),
+        }
+
+        if let Some((spec, cursor)) = query.keyset_spec()? {
+            matches.retain(|(item, _)| spec.is_after(&cursor, item.as_ref()));
+        }
+        if let Some(offset) = query.offset {
+            matches.drain(..offset.min(matches.len()));
+        }
+        if let Some(limit) = query.limit {
+            matches.truncate(limit);
+        }
+        Ok(matches)
+    }
+}
+
+/// Relevance of an item for the lower-cased search `terms`, or `None` if any
+/// term is missing. Title and tag hits weigh more than content hits.
+fn text_score(terms: &[String], title: &str, content: &str, tags: &[String]) -> Option<f32> {
+    let title = title.to_lowercase();
+    let content = content.to_lowercase();
+    let tags: Vec<String> = tags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    let mut score = 0.0;
+    for term in terms {
+        let mut term_score = 0.0;
+        if title.contains(term.as_str()) {
+            term_score += 10.0;
+        }
+        if tags.iter().any(|tag| tag.contains(term.as_str())) {
+            term_score += 5.0;
+        }
+        term_score += content.matches(term.as_str()).count() as f32;
+        if term_score == 0.0 {
+            return None;
+        }
+        score += term_score;
+    }
+    Some(score)
 }
 
 #[async_trait]
 impl StorageBackend for InMemoryBackend {
     async fn save(&self, item: &(dyn MemoryItem + Send + Sync)) -> Result<()> {
-        let mut items = self
-            .items
-            .write()
-            .map_err(|_| Error::other("RwLock poisoned"))?;
+        let mut items = self.write_items()?;
+        let mut versions = self.write_versions()?;
+        let history = versions.entry(item.id()).or_default();
+        if let Some(version) = next_version(history.last(), item) {
+            history.push(version);
+        }
         items.insert(item.id(), item.clone_dyn_send_sync());
+        self.write_trashed()?.remove(&item.id());
         Ok(())
     }
 
     async fn get(&self, id: &Uuid) -> Result<Option<Box<dyn MemoryItem + Send + Sync>>> {
-        let items = self
-            .items
-            .read()
-            .map_err(|_| Error::other("RwLock poisoned"))?;
-        Ok(items.get(id).map(|item| item.clone_dyn_send_sync()))
+        if self.read_trashed()?.contains_key(id) {
+            return Ok(None);
+        }
+        Ok(self
+            .read_items()?
+            .get(id)
+            .map(|item| item.clone_dyn_send_sync()))
     }
 
     async fn delete(&self, id: &Uuid) -> Result<()> {
-        let mut items = self
-            .items
-            .write()
-            .map_err(|_| Error::other("RwLock poisoned"))?;
-        items.remove(id);
+        let items = self.read_items()?;
+        if items.contains_key(id) {
+            self.write_trashed()?.entry(*id).or_insert_with(Utc::now);
+        }
+        Ok(())
+    }
+
+    async fn trash(&self) -> Result<Vec<TrashedItem>> {
+        let items = self.read_items()?;
+        let mut trashed: Vec<TrashedItem> = self
+            .read_trashed()?
+            .iter()
+            .filter_map(|(id, deleted_at)| {
+                items.get(id).map(|item| TrashedItem {
+                    item: item.clone_dyn_send_sync(),
+                    deleted_at: *deleted_at,
+                })
+            })
+            .collect();
+        trashed.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+        Ok(trashed)
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<()> {
+        let mut items = self.write_items()?;
+        let mut trashed = self.write_trashed()?;
+        if !trashed.contains_key(id) {
+            return Err(Error::not_found(format!("Trashed item {}", id)));
+        }
+        if let Some(item) = items.get_mut(id) {
+            item.set_updated_at(Utc::now());
+        }
+        trashed.remove(id);
         Ok(())
     }
 
+    async fn purge(&self, deleted_before: Option<DateTime<Utc>>) -> Result<usize> {
+        let mut items = self.write_items()?;
+        let mut trashed = self.write_trashed()?;
+        let expired: Vec<Uuid> = trashed
+            .iter()
+            .filter(|(_, deleted_at)| deleted_before.map_or(true, |cutoff| **deleted_at < cutoff))
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut versions = self.write_versions()?;
+        let mut runs = self.write_runs()?;
+        let mut usage = self.write_usage()?;
+        let mut relations = self.write_relations()?;
+        for id in &expired {
+            trashed.remove(id);
+            items.remove(id);
+            versions.remove(id);
+            runs.remove(id);
+            usage.remove(id);
+            relations.remove(id);
+        }
+        for edges in relations.values_mut() {
+            edges.retain(|edge| !expired.contains(&edge.to));
+        }
+        Ok(expired.len())
+    }
+
     async fn vector_search(
         &self,
-        _embedding: &[f32],
-        _limit: usize,
+        embedding: &[f32],
+        limit: usize,
     ) -> Result<Vec<(Box<dyn MemoryItem + Send + Sync>, f32)>> {
-        // Simple implementation that just returns all items with a score of 1.0
-        let items = self
-            .items
-            .read()
-            .map_err(|_| Error::other("RwLock poisoned"))?;
-        let results = items
-            .values()
-            .map(|item| (item.clone_dyn_send_sync(), 1.0))
-            .collect();
-        Ok(results)
+        let scorer = Scorer::new(self.metric, embedding);
+        let mut best = TopK::new(limit);
+
+        let items = self.read_items()?;
+        let trashed = self.read_trashed()?;
+        for item in items.values() {
+            if trashed.contains_key(&item.id()) {
+                continue;
+            }
+            // Items without a comparable embedding cannot be ranked
+            let score = item
+                .embedding()
+                .and_then(|bytes| decode_embedding(bytes).ok())
+                .and_then(|candidate| scorer.score(&candidate));
+            if let Some(score) = score {
+                best.push(item.id(), score);
+            }
+        }
+
+        Ok(best
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|(id, score)| {
+                items
+                    .get(&id)
+                    .map(|item| (item.clone_dyn_send_sync(), score))
+            })
+            .collect())
     }
 
-    async fn add_relation(
-        &self,
-        _from: &Uuid,
-        _to: &Uuid,
-        _relation_type: &str,
-    ) -> Result<()> {
-        // No-op for in-memory implementation
+    async fn add_relation(&self, from: &Uuid, to: &Uuid, relation_type: &str) -> Result<()> {
+        validate_relation_type(relation_type)?;
+        {
+            let items = self.read_items()?;
+            let trashed = self.read_trashed()?;
+            for id in [from, to] {
+                if !items.contains_key(id) || trashed.contains_key(id) {
+                    return Err(Error::not_found(format!("Item {}", id)));
+                }
+            }
+        }
+
+        let edge = Edge {
+            to: *to,
+            relation_type: relation_type.to_string(),
+        };
+        let mut relations = self.write_relations()?;
+        let edges = relations.entry(*from).or_default();
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
         Ok(())
     }
 
-    async fn query(
-        &self,
-        _query: &crate::models::Query,
-    ) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
-        // Simple implementation that returns all items
-        let items = self
-            .items
-            .read()
-            .map_err(|_| Error::other("RwLock poisoned"))?;
-        let results = items.values().map(|item| item.clone_dyn_send_sync()).collect();
-        Ok(results)
+    async fn query(&self, query: &Query) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
+        if query.needs_ranking() {
+            return query_ranked(self, query).await;
+        }
+        Ok(self
+            .matching(query)?
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect())
+    }
+
+    async fn search(&self, query: &Query) -> Result<Vec<SearchHit>> {
+        Ok(self
+            .matching(query)?
+            .into_iter()
+            .map(|(item, score)| SearchHit {
+                score,
+                ..SearchHit::unranked(item)
+            })
+            .collect())
+    }
+
+    async fn versions(&self, id: &Uuid) -> Result<Vec<Version>> {
+        Ok(self.read_versions()?.get(id).cloned().unwrap_or_default())
+    }
+
+    async fn record_run(&self, run: &Run) -> Result<()> {
+        let mut runs = self.write_runs()?;
+        let history = runs.entry(run.item_id).or_default();
+        history.push(run.clone());
+        history.sort_by_key(|run| run.started_at);
+        Ok(())
+    }
+
+    async fn runs(&self, id: &Uuid) -> Result<Vec<Run>> {
+        Ok(self.read_runs()?.get(id).cloned().unwrap_or_default())
+    }
+
+    async fn record_usage(&self, usage: &Usage) -> Result<()> {
+        self.write_usage()?
+            .entry(usage.item_id)
+            .or_default()
+            .push(usage.clone());
+        Ok(())
+    }
+
+    async fn usage_stats(&self, place: Option<&str>) -> Result<Vec<UsageStats>> {
+        let now = Utc::now();
+        Ok(self
+            .read_usage()?
+            .values()
+            .filter_map(|uses| {
+                let uses = uses
+                    .iter()
+                    .filter(|usage| place.is_none() || usage.place() == place);
+                UsageStats::from_uses(uses, now)
+            })
+            .collect())
     }
 
     async fn get_related(
         &self,
-        _id: &Uuid,
-        _relation_type: Option<&str>,
+        id: &Uuid,
+        relation_type: Option<&str>,
     ) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
-        // Simple implementation that returns an empty vector
-        Ok(Vec::new())
+        let items = self.read_items()?;
+        let trashed = self.read_trashed()?;
+        let relations = self.read_relations()?;
+
+        let mut seen = HashSet::new();
+        Ok(relations
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter(|edge| relation_type.map_or(true, |rel| edge.relation_type == rel))
+            .filter(|edge| !trashed.contains_key(&edge.to))
+            .filter(|edge| seen.insert(edge.to))
+            .filter_map(|edge| items.get(&edge.to).map(|item| item.clone_dyn_send_sync()))
+            .collect())
+    }
+
+    async fn relations(&self) -> Result<Vec<Relation>> {
+        let items = self.read_items()?;
+        let trashed = self.read_trashed()?;
+        let live = |id: &Uuid| items.contains_key(id) && !trashed.contains_key(id);
+
+        let mut relations: Vec<Relation> = self
+            .read_relations()?
+            .iter()
+            .filter(|(from, _)| live(from))
+            .flat_map(|(from, edges)| {
+                edges
+                    .iter()
+                    .filter(|edge| live(&edge.to))
+                    .map(|edge| Relation {
+                        from: *from,
+                        to: edge.to,
+                        relation_type: edge.relation_type.clone(),
+                    })
+            })
+            .collect();
+        relations.sort();
+        Ok(relations)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::SnippetWithTags;
+    use crate::vector::encode_embedding;
     use chrono::Utc;
     use std::collections::HashMap;
 
@@ -154,6 +631,7 @@ mod tests {
         fn metadata(&self) -> HashMap<String, serde_json::Value> { HashMap::new() }
         fn created_at(&self) -> chrono::DateTime<Utc> { self.created_at }
         fn updated_at(&self) -> chrono::DateTime<Utc> { self.updated_at }
+        fn set_updated_at(&mut self, updated_at: chrono::DateTime<Utc>) { self.updated_at = updated_at; }
         
         fn clone_dyn(&self) -> Box<dyn MemoryItem> {
             Box::new(self.clone())
@@ -194,9 +672,9 @@ mod tests {
         assert_eq!(retrieved.id(), test_id);
         assert_eq!(retrieved.content(), "test content");
         
-        // Test vector search (dummy implementation, just checks it doesn't panic)
+        // Items without embeddings never match a similarity search
         let results = backend.vector_search(&[], 10).await.unwrap();
-        assert!(!results.is_empty());
+        assert!(results.is_empty());
         
         // Test delete
         backend.delete(&test_id).await.unwrap();
@@ -210,4 +688,170 @@ mod tests {
         let retrieved2 = backend.get(&test_id2).await.unwrap().unwrap();
         assert_eq!(retrieved2.id(), test_id2);
     }
+
+    fn titles(items: &[Box<dyn MemoryItem + Send + Sync>]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| {
+                item.as_any()
+                    .downcast_ref::<SnippetWithTags>()
+                    .unwrap()
+                    .title
+                    .clone()
+            })
+            .collect()
+    }
+
+    fn snippet(title: &str, content: &str, tags: &[&str]) -> SnippetWithTags {
+        SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            content.to_string(),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_and_sorting() {
+        let backend = InMemoryBackend::new();
+        for (title, content, tags) in [
+            (
+                "Docker cleanup",
+                "docker system prune",
+                &["docker", "ops"][..],
+            ),
+            ("Build image", "docker build -t app .", &["docker"][..]),
+            ("List files", "ls -la", &["shell"][..]),
+        ] {
+            backend.save(&snippet(title, content, tags)).await.unwrap();
+        }
+        backend
+            .save(&TestMemory::new("docker notes"))
+            .await
+            .unwrap();
+
+        let query = Query {
+            item_type: Some("snippet".to_string()),
+            tags: Some(vec!["docker".to_string(), "ops".to_string()]),
+            ..Default::default()
+        };
+        let results = backend.query(&query).await.unwrap();
+        assert_eq!(titles(&results), vec!["Docker cleanup"]);
+
+        let query = Query {
+            item_type: Some("snippet".to_string()),
+            sort_by: Some("title".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let results = backend.query(&query).await.unwrap();
+        assert_eq!(titles(&results), vec!["Build image", "Docker cleanup"]);
+
+        // Title matches outrank content-only matches
+        let query = Query {
+            item_type: Some("snippet".to_string()),
+            text_filter: Some("DOCKER".to_string()),
+            ..Default::default()
+        };
+        let hits = backend.search(&query).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].score > hits[1].score);
+        assert_eq!(hits[0].item.content(), "docker system prune");
+
+        let query = Query {
+            sort_by: Some("popularity".to_string()),
+            ..Default::default()
+        };
+        assert!(backend.query(&query).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_ranks_by_similarity() {
+        let backend = InMemoryBackend::new();
+        for (title, embedding) in [
+            ("east", [1.0, 0.0]),
+            ("north", [0.0, 1.0]),
+            ("north-east", [0.7, 0.7]),
+        ] {
+            let mut item = snippet(title, title, &[]);
+            item.embedding = Some(encode_embedding(&embedding).unwrap());
+            backend.save(&item).await.unwrap();
+        }
+        backend
+            .save(&snippet("no embedding", "", &[]))
+            .await
+            .unwrap();
+
+        let results = backend.vector_search(&[1.0, 0.1], 2).await.unwrap();
+        let ranked: Vec<_> = results.into_iter().map(|(item, _)| item).collect();
+        assert_eq!(titles(&ranked), vec!["east", "north-east"]);
+
+        // A query of a different dimension matches nothing
+        assert!(backend.vector_search(&[1.0], 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_relations() {
+        let backend = InMemoryBackend::new();
+        let a = snippet("a", "", &[]);
+        let b = snippet("b", "", &[]);
+        let c = snippet("c", "", &[]);
+        for item in [&a, &b, &c] {
+            backend.save(item).await.unwrap();
+        }
+
+        backend
+            .add_relation(&a.id(), &b.id(), "DEPENDS_ON")
+            .await
+            .unwrap();
+        backend
+            .add_relation(&a.id(), &b.id(), "DEPENDS_ON")
+            .await
+            .unwrap();
+        backend
+            .add_relation(&a.id(), &c.id(), "SEE_ALSO")
+            .await
+            .unwrap();
+        assert!(backend
+            .add_relation(&a.id(), &Uuid::new_v4(), "SEE_ALSO")
+            .await
+            .is_err());
+
+        let related = backend.get_related(&a.id(), None).await.unwrap();
+        assert_eq!(titles(&related), vec!["b", "c"]);
+        let related = backend
+            .get_related(&a.id(), Some("SEE_ALSO"))
+            .await
+            .unwrap();
+        assert_eq!(titles(&related), vec!["c"]);
+
+        // Trashed items drop out of the neighbours until restored
+        backend.delete(&c.id()).await.unwrap();
+        let related = backend.get_related(&a.id(), None).await.unwrap();
+        assert_eq!(titles(&related), vec!["b"]);
+        backend.restore(&c.id()).await.unwrap();
+        let related = backend.get_related(&a.id(), None).await.unwrap();
+        assert_eq!(titles(&related), vec!["b", "c"]);
+
+        // Purging drops the edges pointing at it for good
+        backend.delete(&c.id()).await.unwrap();
+        assert_eq!(backend.purge(None).await.unwrap(), 1);
+        backend.save(&c).await.unwrap();
+        let related = backend.get_related(&a.id(), None).await.unwrap();
+        assert_eq!(titles(&related), vec!["b"]);
+    }
+    #[tokio::test]
+    async fn test_restore_unregistered_item_type() {
+        let backend = InMemoryBackend::default();
+        let mut item = TestMemory::new("never registered");
+        item.updated_at = Utc::now() - chrono::Duration::hours(1);
+        backend.save(&item).await.unwrap();
+        backend.delete(&item.id).await.unwrap();
+
+        backend.restore(&item.id).await.unwrap();
+        assert!(backend.trash().await.unwrap().is_empty());
+        let restored = backend.get(&item.id).await.unwrap().unwrap();
+        assert!(restored.updated_at() > item.updated_at);
+        assert_eq!(restored.content(), "never registered");
+    }
 }
@@ -1,8 +1,15 @@
 //! Storage backends for Rustash.
 
+use crate::error::{Error, Result};
+
 mod in_memory;
 pub use in_memory::InMemoryBackend;
 
+pub(crate) mod rank;
+
+pub mod sort;
+pub use sort::{SortField, SortKey, SortSpec};
+
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
@@ -11,3 +18,38 @@ pub mod postgres;
 
 // Re-export the StorageBackend trait
 pub use crate::storage::in_memory::StorageBackend;
+
+/// Check that a relation type is an identifier: letters, digits and
+/// underscores, not starting with a digit.
+///
+/// Relation types become Apache AGE edge labels on Postgres, so every backend
+/// holds them to that rule and relations can move between stashes.
+pub(crate) fn validate_relation_type(relation_type: &str) -> Result<()> {
+    let mut chars = relation_type.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::validation(format!(
+            "Invalid relation type '{}': use letters, digits and underscores",
+            relation_type
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_relation_type() {
+        assert!(validate_relation_type("RELATED_TO").is_ok());
+        assert!(validate_relation_type("_depends_on2").is_ok());
+        assert!(validate_relation_type("").is_err());
+        assert!(validate_relation_type("2nd").is_err());
+        assert!(validate_relation_type("x]->(n) DETACH DELETE n //").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Orderings the backends cannot express in their own queries.
+//!
+//! Frecency, boosted tags and project scoping all depend on data outside
+//! the items themselves, so backends hand such queries to [`query_ranked`],
+//! which runs the query unranked and reorders the results in memory.
+
+use crate::context::PROJECT_TAG_PREFIX;
+use crate::error::{Error, Result};
+use crate::memory::{title_and_tags, MemoryItem};
+use crate::models::{Query, TagFilter};
+use crate::storage::StorageBackend;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Run a query that [needs ranking](Query::needs_ranking).
+///
+/// Items scoped to another project are dropped, then results are ordered by
+/// frecency when asked for, and finally items carrying a boosted tag move
+/// ahead of the rest. Every step keeps the previous order among ties: the
+/// query's own sort keys, relevance when searching, or most recently
+/// updated first under the `frecency` sort.
+pub(crate) async fn query_ranked<B: StorageBackend + ?Sized>(
+    backend: &B,
+    query: &Query,
+) -> Result<Vec<Box<dyn MemoryItem + Send + Sync>>> {
+    if query.after.is_some() {
+        return Err(Error::validation(
+            "Ranked results cannot be paged with a cursor; use an offset",
+        ));
+    }
+
+    let frecency_sorted = query.is_frecency_sorted();
+    let searching = query
+        .text_filter
+        .as_deref()
+        .is_some_and(|text| !text.trim().is_empty());
+    let sort_by = match &query.sort_by {
+        Some(_) if frecency_sorted => (!searching).then(|| "updated_at:desc".to_string()),
+        sort_by => sort_by.clone(),
+    };
+    let unranked = Query {
+        sort_by,
+        boost_tags: Vec::new(),
+        project: None,
+        limit: None,
+        offset: None,
+        ..query.clone()
+    };
+    let mut items = backend.query(&unranked).await?;
+
+    if let Some(project) = &query.project {
+        let own_tag = format!("{}{}", PROJECT_TAG_PREFIX, project);
+        items.retain(|item| {
+            let (_, tags) = title_and_tags(item.as_ref());
+            let scoped = tags.iter().any(|tag| tag.starts_with(PROJECT_TAG_PREFIX));
+            !scoped || tags.contains(&own_tag)
+        });
+    }
+
+    // Stable sorts keep the tie order
+    if frecency_sorted {
+        let frecency: HashMap<Uuid, u64> = backend
+            .usage_stats(None)
+            .await?
+            .into_iter()
+            .map(|stats| (stats.item_id, stats.frecency))
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(frecency.get(&item.id()).copied().unwrap_or(0)));
+    }
+    if !query.boost_tags.is_empty() {
+        let boosted = TagFilter {
+            any: query.boost_tags.clone(),
+            ..Default::default()
+        };
+        items.sort_by_key(|item| !boosted.matches(&title_and_tags(item.as_ref()).1));
+    }
+
+    let offset = query.offset.unwrap_or(0).min(items.len());
+    items.drain(..offset);
+    if let Some(limit) = query.limit {
+        items.truncate(limit);
+    }
+    Ok(items)
+}
@@ -0,0 +1,326 @@
+//! Result ordering and keyset pagination shared by the storage backends.
+//!
+//! A sort specification is a comma-separated list of keys, each a field name
+//! optionally followed by `:asc` or `:desc`, for example `title` or
+//! `updated_at:desc,title`. Every ordering ends with the item id so that it is
+//! total, which is what makes keyset cursors stable.
+
+use crate::error::{Error, Result};
+use crate::memory::{title_and_tags, MemoryItem};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use uuid::Uuid;
+
+/// A field that query results can be ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Title,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField {
+    /// Parse a field name.
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "title" => Ok(Self::Title),
+            "created_at" => Ok(Self::CreatedAt),
+            "updated_at" => Ok(Self::UpdatedAt),
+            other => Err(Error::validation(format!(
+                "Unknown sort field '{}'. Use: title, created_at, updated_at",
+                other
+            ))),
+        }
+    }
+
+    /// The field name, which is also its column name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+        }
+    }
+
+    /// Titles read naturally A to Z; timestamps are most useful newest first.
+    fn descending_by_default(self) -> bool {
+        !matches!(self, Self::Title)
+    }
+
+    fn value_of(self, item: &(dyn MemoryItem + Send + Sync)) -> SortValue {
+        match self {
+            Self::Title => SortValue::Text(title_and_tags(item).0),
+            Self::CreatedAt => SortValue::Time(item.created_at()),
+            Self::UpdatedAt => SortValue::Time(item.updated_at()),
+        }
+    }
+}
+
+/// One key of a sort specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+/// An ordered list of sort keys, as named by `Query::sort_by`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortSpec {
+    keys: Vec<SortKey>,
+}
+
+impl Default for SortSpec {
+    /// Oldest first, the order items were added in.
+    fn default() -> Self {
+        Self {
+            keys: vec![SortKey {
+                field: SortField::CreatedAt,
+                descending: false,
+            }],
+        }
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let direction = if key.descending { "desc" } else { "asc" };
+            write!(f, "{}:{}", key.field.name(), direction)?;
+        }
+        Ok(())
+    }
+}
+
+impl SortSpec {
+    /// Parse a specification such as `updated_at:desc,title`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let keys = spec
+            .split(',')
+            .map(|key| {
+                let key = key.trim();
+                let (name, direction) = key.split_once(':').unwrap_or((key, ""));
+                let field = SortField::parse(name.trim())?;
+                let descending = match direction.trim() {
+                    "" => field.descending_by_default(),
+                    "asc" => false,
+                    "desc" => true,
+                    other => {
+                        return Err(Error::validation(format!(
+                            "Unknown sort direction '{}'. Use: asc, desc",
+                            other
+                        )))
+                    }
+                };
+                Ok(SortKey { field, descending })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { keys })
+    }
+
+    /// The keys in priority order, not including the id tie-breaker.
+    pub fn keys(&self) -> &[SortKey] {
+        &self.keys
+    }
+
+    /// The same keys with every direction flipped.
+    pub fn reversed(mut self) -> Self {
+        for key in &mut self.keys {
+            key.descending = !key.descending;
+        }
+        self
+    }
+
+    /// Compare two items under this ordering.
+    pub(crate) fn compare(
+        &self,
+        a: &(dyn MemoryItem + Send + Sync),
+        b: &(dyn MemoryItem + Send + Sync),
+    ) -> Ordering {
+        self.keys
+            .iter()
+            .map(|key| key.apply(key.field.value_of(a).cmp(&key.field.value_of(b))))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.id().cmp(&b.id()))
+    }
+
+    /// `ORDER BY` terms for this ordering on the snippets table aliased `alias`.
+    pub(crate) fn order_sql(&self, alias: &str) -> String {
+        let mut terms: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let direction = if key.descending { "DESC" } else { "ASC" };
+                format!("{alias}.{} {direction}", key.field.name())
+            })
+            .collect();
+        terms.push(format!("{alias}.uuid ASC"));
+        terms.join(", ")
+    }
+
+    /// An opaque cursor pointing just past `item`.
+    pub(crate) fn cursor_after(&self, item: &(dyn MemoryItem + Send + Sync)) -> Result<String> {
+        let cursor = Cursor {
+            sort: self.to_string(),
+            values: self
+                .keys
+                .iter()
+                .map(|key| key.field.value_of(item))
+                .collect(),
+            id: item.id(),
+        };
+        let json = serde_json::to_vec(&cursor)?;
+        Ok(json.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+
+    /// Decode a cursor produced by [`SortSpec::cursor_after`] for this ordering.
+    pub(crate) fn decode_cursor(&self, cursor: &str) -> Result<Cursor> {
+        let invalid = || Error::validation(format!("Invalid cursor '{}'", cursor));
+        if cursor.len() % 2 != 0 || !cursor.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let decoded: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+        let matches_keys = decoded.values.len() == self.keys.len()
+            && self
+                .keys
+                .iter()
+                .zip(&decoded.values)
+                .all(|(key, value)| value.is_time() == (key.field != SortField::Title));
+        if decoded.sort != self.to_string() || !matches_keys {
+            return Err(Error::validation(format!(
+                "Cursor was created for sort '{}', not '{}'",
+                decoded.sort, self
+            )));
+        }
+        Ok(decoded)
+    }
+
+    /// Whether `item` comes strictly after `cursor` in this ordering.
+    pub(crate) fn is_after(&self, cursor: &Cursor, item: &(dyn MemoryItem + Send + Sync)) -> bool {
+        self.keys
+            .iter()
+            .zip(&cursor.values)
+            .map(|(key, value)| key.apply(key.field.value_of(item).cmp(value)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| item.id().cmp(&cursor.id))
+            .is_gt()
+    }
+
+    /// SQL condition selecting rows of `alias` that come after `cursor`.
+    ///
+    /// `time_literal` renders a timestamp as a literal comparable with the
+    /// backend's timestamp columns.
+    pub(crate) fn keyset_sql(
+        &self,
+        cursor: &Cursor,
+        alias: &str,
+        time_literal: fn(&NaiveDateTime) -> String,
+    ) -> String {
+        let literal = |value: &SortValue| match value {
+            SortValue::Text(text) => format!("'{}'", text.replace('\'', "''")),
+            SortValue::Time(time) => time_literal(&time.naive_utc()),
+        };
+
+        // (k1 > v1) OR (k1 = v1 AND k2 > v2) OR ... OR (all equal AND uuid > id)
+        let mut branches = Vec::new();
+        let mut equal_prefix: Vec<String> = Vec::new();
+        for (key, value) in self.keys.iter().zip(&cursor.values) {
+            let column = format!("{alias}.{}", key.field.name());
+            let operator = if key.descending { "<" } else { ">" };
+            let mut branch = equal_prefix.clone();
+            branch.push(format!("{column} {operator} {}", literal(value)));
+            branches.push(format!("({})", branch.join(" AND ")));
+            equal_prefix.push(format!("{column} = {}", literal(value)));
+        }
+        equal_prefix.push(format!("{alias}.uuid > '{}'", cursor.id));
+        branches.push(format!("({})", equal_prefix.join(" AND ")));
+
+        format!("({})", branches.join(" OR "))
+    }
+}
+
+impl SortKey {
+    fn apply(&self, ordering: Ordering) -> Ordering {
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// The value of one sort key for a particular item.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub(crate) enum SortValue {
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+impl SortValue {
+    fn is_time(&self) -> bool {
+        matches!(self, Self::Time(_))
+    }
+}
+
+/// The position of the last item on a page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Cursor {
+    sort: String,
+    values: Vec<SortValue>,
+    id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SnippetWithTags;
+
+    #[test]
+    fn test_parse_and_display() {
+        let spec = SortSpec::parse("updated_at, title:desc").unwrap();
+        assert_eq!(spec.to_string(), "updated_at:desc,title:desc");
+        assert_eq!(spec.reversed().to_string(), "updated_at:asc,title:asc");
+        assert_eq!(SortSpec::default().to_string(), "created_at:asc");
+
+        assert!(SortSpec::parse("popularity").is_err());
+        assert!(SortSpec::parse("title:sideways").is_err());
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let spec = SortSpec::parse("title").unwrap();
+        let snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "It's here".to_string(),
+            "content".to_string(),
+            vec![],
+        );
+
+        let cursor = spec
+            .decode_cursor(&spec.cursor_after(&snippet).unwrap())
+            .unwrap();
+        assert!(!spec.is_after(&cursor, &snippet));
+        assert_eq!(
+            spec.keyset_sql(&cursor, "s", |_| unreachable!()),
+            format!(
+                "((s.title > 'It''s here') OR (s.title = 'It''s here' AND s.uuid > '{}'))",
+                snippet.uuid
+            )
+        );
+
+        let other = SortSpec::parse("updated_at").unwrap();
+        let encoded = spec.cursor_after(&snippet).unwrap();
+        assert!(other.decode_cursor(&encoded).is_err());
+        assert!(spec.decode_cursor("not a cursor").is_err());
+    }
+}
@@ -1,17 +1,26 @@
 //! SQLite backend implementation for Rustash storage.
 
-use super::StorageBackend;
+use super::{rank::query_ranked, validate_relation_type, SortField, StorageBackend};
 use crate::{
     error::{Error, Result},
-    models::{DbSnippet, NewDbSnippet, Query, Snippet, SnippetWithTags},
-    schema::{relations, snippets},
+    history::{next_version, Version},
+    memory::{decode_item, encode_item},
+    models::{
+        DbRelation, DbRun, DbSnippet, DbUsage, DbUsageStats, DbVersion, NewDbSnippet, NewDbVersion,
+        Query, Relation, Run, SearchHit, TagFilter, TagPattern, TrashedItem, HIGHLIGHT_END,
+        HIGHLIGHT_START,
+    },
+    schema::{relations, snippet_runs, snippet_usage, snippet_versions, snippets},
+    usage::{stats_sql, Usage, UsageStats},
+    vector::{decode_embedding, DistanceMetric, Scorer, TopK},
 };
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::{
     prelude::*,
+    result::Error as DieselError,
     sql_query,
-    sql_types::{Binary as SqlBinary, Double, Integer as SqlInteger, Nullable, Text, Timestamp},
+    sql_types::{BigInt, Double, Text},
     SqliteConnection,
 };
 use diesel_async::{
@@ -19,19 +28,42 @@ use diesel_async::{
         bb8::PooledConnection, AsyncDieselConnectionManager,
     },
     sync_connection_wrapper::SyncConnectionWrapper,
-    RunQueryDsl,
+    AsyncConnection, RunQueryDsl,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Number of embeddings loaded per round trip during a vector scan.
+const VECTOR_SCAN_BATCH_SIZE: i64 = 512;
+
+/// BM25 weights for the `snippets_fts` columns (title, content, tags).
+const FTS_WEIGHTS: &str = "10.0, 1.0, 5.0";
+
+/// Maximum number of tokens in a search excerpt.
+const EXCERPT_TOKENS: i32 = 16;
+
+/// A row returned by a full-text search over `snippets_fts`.
+#[derive(QueryableByName)]
+struct FtsRow {
+    #[diesel(embed)]
+    snippet: DbSnippet,
+    #[diesel(sql_type = Double)]
+    rank: f64,
+    #[diesel(sql_type = Text)]
+    highlighted_title: String,
+    #[diesel(sql_type = Text)]
+    excerpt: String,
+}
+
 type SqlitePool = crate::database::sqlite_pool::SqlitePool;
-type SqlitePooledConnection<'a> =
-    PooledConnection<'a, AsyncDieselConnectionManager<SyncConnectionWrapper<SqliteConnection>>>;
+type SqlitePooledConnection<'a> = PooledConnection<'a, SyncConnectionWrapper<SqliteConnection>>;
 
 /// A SQLite-backed storage implementation.
 #[derive(Debug, Clone)]
 pub struct SqliteBackend {
     pool: Arc<SqlitePool>,
+    metric: DistanceMetric,
 }
 
 impl SqliteBackend {
@@ -39,9 +71,16 @@ impl SqliteBackend {
     pub fn new(pool: SqlitePool) -> Self {
         Self {
             pool: Arc::new(pool),
+            metric: DistanceMetric::default(),
         }
     }
 
+    /// Use the given metric for `vector_search` instead of cosine similarity.
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
     /// Get a connection from the pool.
     async fn get_conn(&self) -> Result<SqlitePooledConnection<'_>> {
         self.pool
@@ -49,42 +88,164 @@ impl SqliteBackend {
             .await
             .map_err(|e| Error::Pool(e.to_string()))
     }
+
+    /// Rank items matching an FTS5 expression with BM25.
+    ///
+    /// Plain text that is not a valid FTS5 expression (for example
+    /// `git push --force`) is retried with every term quoted, so callers can
+    /// pass user input straight through.
+    async fn full_text_search(&self, query: &Query, expression: &str) -> Result<Vec<SearchHit>> {
+        match self.run_fts_query(query, expression).await {
+            Err(Error::Database(DieselError::DatabaseError(_, info)))
+                if is_fts_syntax_error(info.message()) =>
+            {
+                self.run_fts_query(query, &quote_fts_terms(expression))
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn run_fts_query(&self, query: &Query, expression: &str) -> Result<Vec<SearchHit>> {
+        let mut sql = format!(
+            "SELECT s.*, bm25(snippets_fts, {FTS_WEIGHTS}) AS rank, \
+             highlight(snippets_fts, 0, ?, ?) AS highlighted_title, \
+             snippet(snippets_fts, 1, ?, ?, '…', {EXCERPT_TOKENS}) AS excerpt \
+             FROM snippets_fts JOIN snippets s ON s.rowid = snippets_fts.rowid \
+             WHERE snippets_fts MATCH ? AND s.deleted_at IS NULL"
+        );
+        if query.item_type.is_some() {
+            sql.push_str(" AND s.item_type = ?");
+        }
+        if let Some(condition) = tag_condition(&query.effective_tag_filter(), "s") {
+            sql.push_str(&format!(" AND {condition}"));
+        }
+        if let Some((spec, cursor)) = query.keyset_spec()? {
+            let condition = spec.keyset_sql(&cursor, "s", time_literal);
+            sql.push_str(&format!(" AND {condition}"));
+        }
+        let order = match query.sort_spec()? {
+            Some(spec) => spec.order_sql("s"),
+            None => "rank".to_string(),
+        };
+        sql.push_str(&format!(" ORDER BY {order} LIMIT ? OFFSET ?"));
+
+        let mut fts_query = sql_query(sql)
+            .into_boxed()
+            .bind::<Text, _>(HIGHLIGHT_START)
+            .bind::<Text, _>(HIGHLIGHT_END)
+            .bind::<Text, _>(HIGHLIGHT_START)
+            .bind::<Text, _>(HIGHLIGHT_END)
+            .bind::<Text, _>(expression.to_string());
+        if let Some(type_filter) = &query.item_type {
+            fts_query = fts_query.bind::<Text, _>(type_filter.clone());
+        }
+        // SQLite treats a negative limit as "no limit".
+        let limit = query.limit.map_or(-1, |limit| limit as i64);
+        fts_query = fts_query
+            .bind::<BigInt, _>(limit)
+            .bind::<BigInt, _>(query.offset.unwrap_or(0) as i64);
+
+        let mut conn = self.get_conn().await?;
+        let rows = fts_query.load::<FtsRow>(&mut conn).await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(SearchHit {
+                    item: decode_item(row.snippet.into())?,
+                    // BM25 is lower-is-better; flip it so higher is more relevant.
+                    score: -row.rank as f32,
+                    highlighted_title: Some(row.highlighted_title),
+                    excerpt: Some(row.excerpt),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Whether SQLite rejected a `MATCH` expression as malformed.
+fn is_fts_syntax_error(message: &str) -> bool {
+    message.starts_with("fts5:")
+        || message.starts_with("no such column")
+        || message.starts_with("unterminated string")
+}
+
+/// A timestamp literal in the text format diesel stores SQLite timestamps in.
+fn time_literal(time: &NaiveDateTime) -> String {
+    format!("'{}'", time.format("%F %T%.f"))
+}
+
+/// Turn arbitrary text into an FTS5 expression matching all of its terms.
+fn quote_fts_terms(text: &str) -> String {
+    text.split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// SQL condition applying a tag filter to the snippets table aliased `alias`.
+fn tag_condition(filter: &TagFilter, alias: &str) -> Option<String> {
+    filter.to_sql(|pattern| {
+        let test = match pattern {
+            TagPattern::Exact(tag) => {
+                format!("json_each.value = '{}'", tag.replace('\'', "''"))
+            }
+            TagPattern::Prefix(prefix) => format!(
+                "substr(json_each.value, 1, {}) = '{}'",
+                prefix.chars().count(),
+                prefix.replace('\'', "''")
+            ),
+        };
+        format!("EXISTS (SELECT 1 FROM json_each({alias}.tags) WHERE {test})")
+    })
 }
 
 #[async_trait]
 impl StorageBackend for SqliteBackend {
     async fn save(&self, item: &(dyn crate::memory::MemoryItem + Send + Sync)) -> Result<()> {
-        let snippet = item
-            .as_any()
-            .downcast_ref::<SnippetWithTags>()
-            .ok_or_else(|| Error::other("Invalid item type: Expected SnippetWithTags"))?;
-
-        let tags_json = serde_json::to_string(&snippet.tags)?;
+        let db_snippet = NewDbSnippet::from(&encode_item(item)?);
+        let created_at = item.created_at().naive_utc();
+        let updated_at = item.updated_at().naive_utc();
         let mut conn = self.get_conn().await?;
-        let now = chrono::Utc::now().naive_utc();
-
-        let db_snippet = NewDbSnippet {
-            uuid: snippet.uuid.clone(),
-            title: snippet.title.clone(),
-            content: snippet.content.clone(),
-            tags: tags_json,
-            embedding: snippet.embedding.clone(),
-        };
 
         conn.transaction(|conn| {
             Box::pin(async move {
                 diesel::insert_into(crate::schema::snippets::table)
-                    .values(&db_snippet)
+                    .values((
+                        &db_snippet,
+                        crate::schema::snippets::created_at.eq(created_at),
+                        crate::schema::snippets::updated_at.eq(updated_at),
+                    ))
                     .on_conflict(crate::schema::snippets::uuid)
                     .do_update()
                     .set((
                         crate::schema::snippets::title.eq(&db_snippet.title),
                         crate::schema::snippets::content.eq(&db_snippet.content),
                         crate::schema::snippets::tags.eq(&db_snippet.tags),
-                        crate::schema::snippets::updated_at.eq(now),
+                        crate::schema::snippets::embedding.eq(&db_snippet.embedding),
+                        crate::schema::snippets::item_type.eq(&db_snippet.item_type),
+                        crate::schema::snippets::metadata.eq(&db_snippet.metadata),
+                        crate::schema::snippets::created_at.eq(created_at),
+                        crate::schema::snippets::updated_at.eq(updated_at),
+                        crate::schema::snippets::deleted_at.eq(None::<NaiveDateTime>),
                     ))
                     .execute(conn)
                     .await?;
+
+                let latest = snippet_versions::table
+                    .filter(snippet_versions::snippet_uuid.eq(&db_snippet.uuid))
+                    .order(snippet_versions::version.desc())
+                    .select(DbVersion::as_select())
+                    .first(conn)
+                    .await
+                    .optional()?
+                    .map(Version::from);
+                if let Some(version) = next_version(latest.as_ref(), item) {
+                    diesel::insert_into(snippet_versions::table)
+                        .values(NewDbVersion::from(&version))
+                        .execute(conn)
+                        .await?;
+                }
                 Ok::<_, Error>(())
             })
         })
@@ -103,106 +264,189 @@ impl StorageBackend for SqliteBackend {
 
         let result: Option<DbSnippet> = snippets
             .filter(uuid.eq(&id_str))
+            .filter(deleted_at.is_null())
             .first::<DbSnippet>(&mut conn)
             .await
             .optional()
             .map_err(Error::from)?;
 
-        match result {
-            Some(snippet) => {
-                let with_tags: SnippetWithTags = snippet.into();
-                Ok(Some(
-                    Box::new(with_tags) as Box<dyn crate::memory::MemoryItem + Send + Sync>,
-                ))
-            }
-            None => Ok(None),
-        }
+        result
+            .map(|snippet| decode_item(snippet.into()))
+            .transpose()
     }
 
     async fn delete(&self, id: &Uuid) -> Result<()> {
         use crate::schema::snippets::dsl::*;
 
-        let id_str = id.to_string();
+        let now = chrono::Utc::now().naive_utc();
         let mut conn = self.get_conn().await?;
 
-        diesel::delete(snippets.filter(uuid.eq(id_str)))
-            .execute(&mut conn)
-            .await
-            .map_err(Error::from)?;
+        diesel::update(
+            snippets
+                .filter(uuid.eq(id.to_string()))
+                .filter(deleted_at.is_null()),
+        )
+        .set(deleted_at.eq(now))
+        .execute(&mut conn)
+        .await?;
 
         Ok(())
     }
 
+    async fn trash(&self) -> Result<Vec<TrashedItem>> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<DbSnippet> = snippets::table
+            .filter(snippets::deleted_at.is_not_null())
+            .order(snippets::deleted_at.desc())
+            .load(&mut conn)
+            .await?;
+        rows.into_iter().map(TrashedItem::try_from).collect()
+    }
+
+    async fn restore(&self, id: &Uuid) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+        let restored = diesel::update(
+            snippets::table
+                .filter(snippets::uuid.eq(id.to_string()))
+                .filter(snippets::deleted_at.is_not_null()),
+        )
+        .set((
+            snippets::deleted_at.eq(None::<NaiveDateTime>),
+            snippets::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+        if restored == 0 {
+            return Err(Error::not_found(format!("Trashed item {}", id)));
+        }
+        Ok(())
+    }
+
+    async fn purge(&self, deleted_before: Option<DateTime<Utc>>) -> Result<usize> {
+        let mut conn = self.get_conn().await?;
+
+        conn.transaction(|conn| {
+            Box::pin(async move {
+                let mut expired = snippets::table
+                    .select(snippets::uuid)
+                    .filter(snippets::deleted_at.is_not_null())
+                    .into_boxed();
+                if let Some(cutoff) = deleted_before {
+                    expired = expired.filter(snippets::deleted_at.lt(cutoff.naive_utc()));
+                }
+                let ids: Vec<String> = expired.load(conn).await?;
+
+                diesel::delete(
+                    snippet_versions::table.filter(snippet_versions::snippet_uuid.eq_any(&ids)),
+                )
+                .execute(conn)
+                .await?;
+                diesel::delete(snippet_runs::table.filter(snippet_runs::snippet_uuid.eq_any(&ids)))
+                    .execute(conn)
+                    .await?;
+                diesel::delete(
+                    snippet_usage::table.filter(snippet_usage::snippet_uuid.eq_any(&ids)),
+                )
+                .execute(conn)
+                .await?;
+                diesel::delete(
+                    relations::table.filter(
+                        relations::from_uuid
+                            .eq_any(&ids)
+                            .or(relations::to_uuid.eq_any(&ids)),
+                    ),
+                )
+                .execute(conn)
+                .await?;
+                diesel::delete(snippets::table.filter(snippets::uuid.eq_any(&ids)))
+                    .execute(conn)
+                    .await?;
+                Ok::<_, Error>(ids.len())
+            })
+        })
+        .await
+    }
+
     async fn vector_search(
         &self,
         embedding: &[f32],
         limit: usize,
     ) -> Result<Vec<(Box<dyn crate::memory::MemoryItem + Send + Sync>, f32)>> {
-        // SQLite VSS requires a bincode-serialized, f32 little-endian vector.
-        let embedding_bytes = bincode::serialize(embedding)?;
-
-        // Define a custom type that matches the structure of our query result
-        #[derive(QueryableByName)]
-        struct SnippetWithDistance {
-            #[diesel(sql_type = Text)]
-            pub uuid: String,
-            #[diesel(sql_type = Text)]
-            pub title: String,
-            #[diesel(sql_type = Text)]
-            pub content: String,
-            #[diesel(sql_type = Text)]
-            pub tags: String,
-            #[diesel(sql_type = Nullable<SqlBinary>)]
-            pub embedding: Option<Vec<u8>>,
-            #[diesel(sql_type = Timestamp)]
-            pub created_at: NaiveDateTime,
-            #[diesel(sql_type = Timestamp)]
-            pub updated_at: NaiveDateTime,
-            #[diesel(sql_type = Double)]
-            pub distance: f64,
+        if limit == 0 {
+            return Ok(Vec::new());
         }
 
+        // Scan stored embeddings in keyset-ordered batches so memory stays
+        // bounded, keeping only the best `limit` candidates.
+        let scorer = Scorer::new(self.metric, embedding);
+        let mut best = TopK::new(limit);
         let mut conn = self.get_conn().await?;
+        let mut last_uuid = String::new();
+
+        loop {
+            let batch: Vec<(String, Option<Vec<u8>>)> = snippets::table
+                .select((snippets::uuid, snippets::embedding))
+                .filter(snippets::embedding.is_not_null())
+                .filter(snippets::deleted_at.is_null())
+                .filter(snippets::uuid.gt(&last_uuid))
+                .order(snippets::uuid.asc())
+                .limit(VECTOR_SCAN_BATCH_SIZE)
+                .load(&mut conn)
+                .await?;
+
+            let batch_len = batch.len();
+            for (id, bytes) in batch {
+                // Rows with undecodable blobs or a different dimension are
+                // not comparable with the query and are skipped.
+                let score = bytes
+                    .and_then(|bytes| decode_embedding(&bytes).ok())
+                    .and_then(|candidate| scorer.score(&candidate));
+                if let Some(score) = score {
+                    best.push(id.clone(), score);
+                }
+                last_uuid = id;
+            }
 
-        // Build and execute the raw SQL query with parameters
-        let query = "SELECT s.*, vs.distance \
-                     FROM snippets s \
-                     JOIN vss_snippets vs ON s.rowid = vs.rowid \
-                     WHERE vss_search(vs.embedding, vss_search_params(?, ?))";
-
-        let results = sql_query(query)
-            .bind::<SqlBinary, _>(&embedding_bytes)
-            .bind::<SqlInteger, _>(limit as i32)
-            .load::<SnippetWithDistance>(&mut conn)
-            .await?;
+            if batch_len < VECTOR_SCAN_BATCH_SIZE as usize {
+                break;
+            }
+        }
 
-        // Convert the results to the expected format
-        let items = results
+        let ranked = best.into_sorted_vec();
+        let ids: Vec<&str> = ranked.iter().map(|(id, _)| id.as_str()).collect();
+        let mut rows: HashMap<String, DbSnippet> = snippets::table
+            .filter(snippets::uuid.eq_any(ids))
+            .filter(snippets::deleted_at.is_null())
+            .load::<DbSnippet>(&mut conn)
+            .await?
             .into_iter()
-            .map(|row| {
-                let with_tags: SnippetWithTags = Snippet {
-                    uuid: row.uuid,
-                    title: row.title,
-                    content: row.content,
-                    tags: row.tags,
-                    embedding: row.embedding,
-                    created_at: row.created_at,
-                    updated_at: row.updated_at,
-                }
-                .into();
-
-                (
-                    Box::new(with_tags) as Box<dyn crate::memory::MemoryItem + Send + Sync>,
-                    row.distance as f32,
-                )
-            })
+            .map(|row| (row.uuid.clone(), row))
             .collect();
 
-        Ok(items)
+        ranked
+            .into_iter()
+            .filter_map(|(id, score)| rows.remove(&id).map(|row| (row, score)))
+            .map(|(row, score)| Ok((decode_item(row.into())?, score)))
+            .collect()
     }
 
     async fn add_relation(&self, from: &Uuid, to: &Uuid, relation_type: &str) -> Result<()> {
+        validate_relation_type(relation_type)?;
         let mut conn = self.get_conn().await?;
+        for id in [from, to] {
+            let exists = snippets::table
+                .filter(snippets::uuid.eq(id.to_string()))
+                .filter(snippets::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(&mut conn)
+                .await?
+                > 0;
+            if !exists {
+                return Err(Error::not_found(format!("Item {}", id)));
+            }
+        }
+
         diesel::insert_into(relations::table)
             .values((
                 relations::from_uuid.eq(from.to_string()),
@@ -220,32 +464,50 @@ impl StorageBackend for SqliteBackend {
         query: &Query,
     ) -> Result<Vec<Box<dyn crate::memory::MemoryItem + Send + Sync>>> {
         use crate::schema::snippets::dsl::*;
-        let mut conn = self.get_conn().await?;
-        let mut query_builder = snippets.into_boxed();
+
+        if query.needs_ranking() {
+            return query_ranked(self, query).await;
+        }
 
         if let Some(text_filter) = &query.text_filter {
-            let search_term = format!("%{}%", text_filter);
-            query_builder = query_builder
-                .filter(title.like(search_term.clone()))
-                .or_filter(content.like(search_term));
+            let hits = self.full_text_search(query, text_filter).await?;
+            return Ok(hits.into_iter().map(|hit| hit.item).collect());
+        }
+
+        let mut conn = self.get_conn().await?;
+        let mut query_builder = snippets.filter(deleted_at.is_null()).into_boxed();
+
+        if let Some(type_filter) = &query.item_type {
+            query_builder = query_builder.filter(item_type.eq(type_filter.clone()));
+        }
+
+        if let Some(tag_conditions) = tag_condition(&query.effective_tag_filter(), "snippets") {
+            use diesel::dsl::sql;
+            query_builder = query_builder.filter(sql::<diesel::sql_types::Bool>(&tag_conditions));
         }
 
-        if let Some(tag_list) = &query.tags {
-            if !tag_list.is_empty() {
-                use diesel::dsl::sql;
-                let mut tag_conditions = tag_list
-                    .iter()
-                    .map(|tag| format!("json_each.value = '{}'", tag.replace('\'', "''")))
-                    .collect::<Vec<_>>()
-                    .join(" OR ");
-                tag_conditions = format!(
-                    "uuid IN (SELECT s.uuid FROM snippets s, json_each(s.tags) WHERE {})",
-                    tag_conditions
-                );
-
-                query_builder =
-                    query_builder.filter(sql::<diesel::sql_types::Bool>(&tag_conditions));
+        if let Some(spec) = query.sort_spec()? {
+            for key in spec.keys() {
+                query_builder = match (key.field, key.descending) {
+                    (SortField::Title, false) => query_builder.then_order_by(title.asc()),
+                    (SortField::Title, true) => query_builder.then_order_by(title.desc()),
+                    (SortField::CreatedAt, false) => query_builder.then_order_by(created_at.asc()),
+                    (SortField::CreatedAt, true) => query_builder.then_order_by(created_at.desc()),
+                    (SortField::UpdatedAt, false) => query_builder.then_order_by(updated_at.asc()),
+                    (SortField::UpdatedAt, true) => query_builder.then_order_by(updated_at.desc()),
+                };
             }
+            query_builder = query_builder.then_order_by(uuid.asc());
+        }
+
+        if let Some((spec, cursor)) = query.keyset_spec()? {
+            use diesel::dsl::sql;
+            let condition = spec.keyset_sql(&cursor, "snippets", time_literal);
+            query_builder = query_builder.filter(sql::<diesel::sql_types::Bool>(&condition));
+        }
+
+        if let Some(offset) = query.offset {
+            query_builder = query_builder.offset(offset as i64);
         }
 
         if let Some(limit) = query.limit {
@@ -257,15 +519,81 @@ impl StorageBackend for SqliteBackend {
             .await
             .map_err(Error::from)?;
 
-        let items = results
-            .into_iter()
-            .map(|s| {
-                let with_tags: SnippetWithTags = s.into();
-                Box::new(with_tags) as Box<dyn crate::memory::MemoryItem + Send + Sync>
-            })
-            .collect();
+        results.into_iter().map(|s| decode_item(s.into())).collect()
+    }
+
+    async fn search(&self, query: &Query) -> Result<Vec<SearchHit>> {
+        match query.text_filter.as_deref() {
+            Some(expression) if !expression.trim().is_empty() => {
+                self.full_text_search(query, expression).await
+            }
+            _ => Ok(self
+                .query(query)
+                .await?
+                .into_iter()
+                .map(SearchHit::unranked)
+                .collect()),
+        }
+    }
+
+    async fn versions(&self, id: &Uuid) -> Result<Vec<Version>> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<DbVersion> = snippet_versions::table
+            .filter(snippet_versions::snippet_uuid.eq(id.to_string()))
+            .order(snippet_versions::version.asc())
+            .select(DbVersion::as_select())
+            .load(&mut conn)
+            .await?;
+        Ok(rows.into_iter().map(Version::from).collect())
+    }
+
+    async fn version(&self, id: &Uuid, number: u32) -> Result<Option<Version>> {
+        let mut conn = self.get_conn().await?;
+        let row: Option<DbVersion> = snippet_versions::table
+            .filter(snippet_versions::snippet_uuid.eq(id.to_string()))
+            .filter(snippet_versions::version.eq(number as i32))
+            .select(DbVersion::as_select())
+            .first(&mut conn)
+            .await
+            .optional()?;
+        Ok(row.map(Version::from))
+    }
+
+    async fn record_run(&self, run: &Run) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+        diesel::insert_into(snippet_runs::table)
+            .values(DbRun::from(run))
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn runs(&self, id: &Uuid) -> Result<Vec<Run>> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<DbRun> = snippet_runs::table
+            .filter(snippet_runs::snippet_uuid.eq(id.to_string()))
+            .order(snippet_runs::started_at.asc())
+            .select(DbRun::as_select())
+            .load(&mut conn)
+            .await?;
+        Ok(rows.into_iter().map(Run::from).collect())
+    }
+
+    async fn record_usage(&self, usage: &Usage) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+        diesel::insert_into(snippet_usage::table)
+            .values(DbUsage::from(usage))
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
 
-        Ok(items)
+    async fn usage_stats(&self, place: Option<&str>) -> Result<Vec<UsageStats>> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<DbUsageStats> = sql_query(stats_sql(place, Utc::now(), time_literal))
+            .load(&mut conn)
+            .await?;
+        Ok(rows.into_iter().map(UsageStats::from).collect())
     }
 
     async fn get_related(
@@ -277,6 +605,7 @@ impl StorageBackend for SqliteBackend {
         let mut query = relations::table
             .inner_join(snippets::table.on(relations::to_uuid.eq(snippets::uuid)))
             .filter(relations::from_uuid.eq(id.to_string()))
+            .filter(snippets::deleted_at.is_null())
             .select(DbSnippet::as_select())
             .into_boxed();
 
@@ -286,13 +615,211 @@ impl StorageBackend for SqliteBackend {
 
         let results: Vec<DbSnippet> = query.load(&mut conn).await?;
 
-        let items = results
-            .into_iter()
-            .map(|s| {
-                let with_tags: SnippetWithTags = s.into();
-                Box::new(with_tags) as Box<dyn crate::memory::MemoryItem + Send + Sync>
+        results.into_iter().map(|s| decode_item(s.into())).collect()
+    }
+
+    async fn relations(&self) -> Result<Vec<Relation>> {
+        let live = || {
+            snippets::table
+                .filter(snippets::deleted_at.is_null())
+                .select(snippets::uuid)
+        };
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<DbRelation> = relations::table
+            .filter(relations::from_uuid.eq_any(live()))
+            .filter(relations::to_uuid.eq_any(live()))
+            .select(DbRelation::as_select())
+            .load(&mut conn)
+            .await?;
+
+        let mut relations: Vec<Relation> = rows.into_iter().map(Relation::from).collect();
+        relations.sort();
+        Ok(relations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::create_test_pool, models::SnippetWithTags, vector::encode_embedding};
+
+    fn titles(items: &[Box<dyn crate::memory::MemoryItem + Send + Sync>]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| {
+                item.as_any()
+                    .downcast_ref::<SnippetWithTags>()
+                    .unwrap()
+                    .title
+                    .clone()
+            })
+            .collect()
+    }
+
+    async fn backend_with(snippets: &[(&str, &str, &[&str])]) -> SqliteBackend {
+        let backend = SqliteBackend::new(create_test_pool().await.unwrap());
+        for (title, content, tags) in snippets {
+            let snippet = SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                title.to_string(),
+                content.to_string(),
+                tags.iter().map(|tag| tag.to_string()).collect(),
+            );
+            backend.save(&snippet).await.unwrap();
+        }
+        backend
+    }
+
+    fn snippet_with_embedding(title: &str, embedding: &[f32]) -> SnippetWithTags {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            format!("{title} content"),
+            vec![],
+        );
+        snippet.embedding = Some(encode_embedding(embedding).unwrap());
+        snippet
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_ranks_by_similarity() {
+        let backend = SqliteBackend::new(create_test_pool().await.unwrap());
+
+        for (title, embedding) in [
+            ("exact", vec![1.0, 0.0, 0.0]),
+            ("close", vec![0.9, 0.1, 0.0]),
+            ("orthogonal", vec![0.0, 1.0, 0.0]),
+            ("other-dimension", vec![1.0, 0.0]),
+        ] {
+            backend
+                .save(&snippet_with_embedding(title, &embedding))
+                .await
+                .unwrap();
+        }
+        backend
+            .save(&SnippetWithTags::with_uuid(
+                Uuid::new_v4(),
+                "no-embedding".to_string(),
+                "content".to_string(),
+                vec![],
+            ))
+            .await
+            .unwrap();
+
+        let results = backend.vector_search(&[1.0, 0.0, 0.0], 2).await.unwrap();
+        let titles: Vec<_> = results
+            .iter()
+            .map(|(item, _)| {
+                item.as_any()
+                    .downcast_ref::<SnippetWithTags>()
+                    .unwrap()
+                    .title
+                    .clone()
             })
             .collect();
-        Ok(items)
+
+        assert_eq!(titles, vec!["exact", "close"]);
+        assert!((results[0].1 - 1.0).abs() < 1e-6);
+        assert!(results[0].1 > results[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_query_ranks_full_text_matches() {
+        let backend = backend_with(&[
+            ("List containers", "docker ps --all", &["docker"]),
+            ("Docker cleanup", "docker system prune", &["docker"]),
+            ("Git status", "git status --short", &["git"]),
+        ])
+        .await;
+
+        let hits = backend.search(&Query::with_text("docker")).await.unwrap();
+        let items: Vec<_> = hits.into_iter().map(|hit| hit.item).collect();
+        assert_eq!(titles(&items), vec!["Docker cleanup", "List containers"]);
+
+        // Prefix, phrase and boolean syntax are passed through to FTS5
+        let items = backend.query(&Query::with_text("cont*")).await.unwrap();
+        assert_eq!(titles(&items), vec!["List containers"]);
+        let items = backend
+            .query(&Query::with_text("\"system prune\" OR title:git"))
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 2);
+
+        // Filters still apply on top of the match
+        let items = backend
+            .query(&Query::with_text("docker").with_limit(1))
+            .await
+            .unwrap();
+        assert_eq!(titles(&items), vec!["Docker cleanup"]);
+        let items = backend
+            .query(&Query {
+                text_filter: Some("status".to_string()),
+                tags: Some(vec!["docker".to_string()]),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(items.is_empty());
+    }
+
+    /// Rows of `snippets_fts` matching `term`, read from the index alone.
+    async fn indexed(backend: &SqliteBackend, term: &str) -> i64 {
+        #[derive(QueryableByName)]
+        struct Count {
+            #[diesel(sql_type = BigInt)]
+            count: i64,
+        }
+
+        let mut conn = backend.get_conn().await.unwrap();
+        sql_query("SELECT COUNT(*) AS count FROM snippets_fts WHERE snippets_fts MATCH ?")
+            .bind::<Text, _>(term)
+            .get_result::<Count>(&mut conn)
+            .await
+            .unwrap()
+            .count
+    }
+
+    #[tokio::test]
+    async fn test_full_text_index_follows_updates_and_deletes() {
+        let backend = SqliteBackend::new(create_test_pool().await.unwrap());
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            "Restart".to_string(),
+            "systemctl restart nginx".to_string(),
+            vec![],
+        );
+        backend.save(&snippet).await.unwrap();
+
+        snippet.content = "systemctl restart caddy".to_string();
+        backend.save(&snippet).await.unwrap();
+        let items = backend.query(&Query::with_text("nginx")).await.unwrap();
+        assert!(items.is_empty());
+        assert_eq!(indexed(&backend, "nginx").await, 0);
+        let items = backend.query(&Query::with_text("caddy")).await.unwrap();
+        assert_eq!(titles(&items), vec!["Restart"]);
+
+        backend.delete(&snippet.id).await.unwrap();
+        backend.purge(None).await.unwrap();
+        assert_eq!(indexed(&backend, "caddy").await, 0);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_search_highlights_and_tolerates_plain_text() {
+        let backend = backend_with(&[("Force push", "git push --force-with-lease", &[])]).await;
+
+        let hits = backend
+            .search(&Query::with_text("git push --force-with-lease"))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].score > 0.0);
+        assert_eq!(
+            hits[0].title_with("[", "]").as_deref(),
+            Some("Force [push]")
+        );
+        assert!(hits[0]
+            .excerpt_with("[", "]")
+            .unwrap()
+            .starts_with("[git] [push]"));
+    }
+}
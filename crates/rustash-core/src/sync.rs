@@ -0,0 +1,556 @@
+//! Two-way sync between a local stash and a remote one, for example a
+//! SQLite stash on a laptop and the team's Postgres stash.
+//!
+//! An item's change version is its `updated_at`. A [`SyncState`] remembers,
+//! for every item the last sync left the same on both sides, the version
+//! each side had then, so a sync can tell which side changed an item since.
+//! Deletions travel through the trash, whose `deleted_at` is the item's
+//! tombstone; an item purged from one side after it was synced counts as
+//! deleted there.
+//!
+//! An item changed on one side is copied to the other, and an item deleted
+//! on one side and untouched on the other is deleted there too. When both
+//! sides changed an item, or one changed it and the other deleted it, that
+//! is a [`SyncConflict`], settled by the [`ConflictStrategy`]. Relations are
+//! merged: a relation on either side is added to the other when both of its
+//! ends are there. Version history, runs and uses stay where they are.
+
+use crate::error::Result;
+use crate::memory::{encode_item, MemoryItem, StoredItem};
+use crate::models::Query;
+use crate::storage::StorageBackend;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use uuid::Uuid;
+
+type Item = Box<dyn MemoryItem + Send + Sync>;
+
+/// What the last sync between two stashes left behind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// When the last sync finished; `None` before the first one
+    pub synced_at: Option<DateTime<Utc>>,
+    /// The items the last sync left the same on both sides
+    pub items: BTreeMap<Uuid, SyncedItem>,
+}
+
+/// The versions an item had on each side after the last sync.
+///
+/// The two can differ when a backend stores timestamps less precisely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedItem {
+    pub local: DateTime<Utc>,
+    pub remote: DateTime<Utc>,
+}
+
+/// One side of a sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Local,
+    Remote,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Side::Local => Side::Remote,
+            Side::Remote => Side::Local,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Local => "local",
+            Side::Remote => "remote",
+        })
+    }
+}
+
+/// How a sync settles conflicting changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictStrategy {
+    /// Keep the most recent change, a deletion included (last writer wins)
+    #[default]
+    Newest,
+    /// Leave both sides as they are and report the conflict
+    Record,
+    /// Keep the local side
+    Local,
+    /// Keep the remote side
+    Remote,
+}
+
+/// What conflicted about an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both sides changed the item in different ways
+    BothChanged,
+    /// One side changed the item and the other, given here, deleted it
+    ChangedAndDeleted(Side),
+}
+
+/// An item changed on both sides since the last sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    pub id: Uuid,
+    pub title: String,
+    pub kind: ConflictKind,
+    /// The side whose version was kept, or `None` when the conflict was
+    /// recorded and both sides left as they are
+    pub kept: Option<Side>,
+}
+
+/// What a sync did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Items copied from the remote side to the local one
+    pub pulled: usize,
+    /// Items copied from the local side to the remote one
+    pub pushed: usize,
+    /// Items deleted locally because they were deleted remotely
+    pub deleted_local: usize,
+    /// Items deleted remotely because they were deleted locally
+    pub deleted_remote: usize,
+    /// Relations added to either side
+    pub relations: usize,
+    /// Items both sides changed, settled or not
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// How one side of a sync has an item.
+enum Presence {
+    Live(Item),
+    Deleted(DateTime<Utc>),
+    Missing,
+}
+
+/// Everything one side has, live or in the trash.
+struct Snapshot {
+    live: HashMap<Uuid, Item>,
+    trash: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl Snapshot {
+    async fn read(backend: &dyn StorageBackend) -> Result<Self> {
+        let live = backend
+            .query(&Query::default())
+            .await?
+            .into_iter()
+            .map(|item| (item.id(), item))
+            .collect();
+        let trash = backend
+            .trash()
+            .await?
+            .into_iter()
+            .map(|trashed| (trashed.item.id(), trashed.deleted_at))
+            .collect();
+        Ok(Self { live, trash })
+    }
+
+    fn take(&mut self, id: &Uuid) -> Presence {
+        match (self.live.remove(id), self.trash.get(id)) {
+            (Some(item), _) => Presence::Live(item),
+            (None, Some(deleted_at)) => Presence::Deleted(*deleted_at),
+            (None, None) => Presence::Missing,
+        }
+    }
+}
+
+/// The two stashes being synced.
+struct Pair<'a> {
+    local: &'a dyn StorageBackend,
+    remote: &'a dyn StorageBackend,
+}
+
+impl Pair<'_> {
+    fn backend(&self, side: Side) -> &dyn StorageBackend {
+        match side {
+            Side::Local => self.local,
+            Side::Remote => self.remote,
+        }
+    }
+
+    /// Copy `item` to `to`, returning the version `to` stored.
+    async fn write(
+        &self,
+        to: Side,
+        item: &(dyn MemoryItem + Send + Sync),
+        report: &mut SyncReport,
+    ) -> Result<DateTime<Utc>> {
+        let backend = self.backend(to);
+        backend.save(item).await?;
+        match to {
+            Side::Local => report.pulled += 1,
+            Side::Remote => report.pushed += 1,
+        }
+        Ok(backend
+            .get(&item.id())
+            .await?
+            .map_or(item.updated_at(), |stored| stored.updated_at()))
+    }
+
+    async fn delete(&self, on: Side, id: &Uuid, report: &mut SyncReport) -> Result<()> {
+        self.backend(on).delete(id).await?;
+        match on {
+            Side::Local => report.deleted_local += 1,
+            Side::Remote => report.deleted_remote += 1,
+        }
+        Ok(())
+    }
+}
+
+/// Sync `local` and `remote` in both directions, starting from what
+/// `state` says the last sync left, and update `state` to match.
+pub async fn sync(
+    local: &dyn StorageBackend,
+    remote: &dyn StorageBackend,
+    state: &mut SyncState,
+    strategy: ConflictStrategy,
+) -> Result<SyncReport> {
+    let pair = Pair { local, remote };
+    let mut local_snapshot = Snapshot::read(local).await?;
+    let mut remote_snapshot = Snapshot::read(remote).await?;
+    let ids: BTreeSet<Uuid> = local_snapshot
+        .live
+        .keys()
+        .chain(remote_snapshot.live.keys())
+        .chain(state.items.keys())
+        .copied()
+        .collect();
+
+    let mut report = SyncReport::default();
+    for id in ids {
+        let base = state.items.remove(&id);
+        let synced = match (local_snapshot.take(&id), remote_snapshot.take(&id)) {
+            (Presence::Live(local_item), Presence::Live(remote_item)) => {
+                sync_live(&pair, base, local_item, remote_item, strategy, &mut report).await?
+            }
+            (Presence::Live(item), other) => {
+                sync_one_sided(&pair, Side::Local, base, item, other, strategy, &mut report).await?
+            }
+            (other, Presence::Live(item)) => {
+                sync_one_sided(
+                    &pair,
+                    Side::Remote,
+                    base,
+                    item,
+                    other,
+                    strategy,
+                    &mut report,
+                )
+                .await?
+            }
+            // Gone from both sides
+            _ => None,
+        };
+        if let Some(synced) = synced {
+            state.items.insert(id, synced);
+        }
+    }
+    report.relations += merge_relations(local, remote).await?;
+    report.relations += merge_relations(remote, local).await?;
+    state.synced_at = Some(Utc::now());
+    Ok(report)
+}
+
+/// Sync an item live on both sides, returning its versions if it ends up
+/// the same on both.
+async fn sync_live(
+    pair: &Pair<'_>,
+    base: Option<SyncedItem>,
+    local_item: Item,
+    remote_item: Item,
+    strategy: ConflictStrategy,
+    report: &mut SyncReport,
+) -> Result<Option<SyncedItem>> {
+    let local_changed = base.map_or(true, |base| local_item.updated_at() != base.local);
+    let remote_changed = base.map_or(true, |base| remote_item.updated_at() != base.remote);
+    let keep = match (local_changed, remote_changed) {
+        (false, false) => None,
+        (true, false) => Some(Side::Local),
+        (false, true) => Some(Side::Remote),
+        (true, true) if same_item(local_item.as_ref(), remote_item.as_ref())? => None,
+        (true, true) => {
+            let kept = match strategy {
+                ConflictStrategy::Newest if remote_item.updated_at() > local_item.updated_at() => {
+                    Some(Side::Remote)
+                }
+                ConflictStrategy::Newest | ConflictStrategy::Local => Some(Side::Local),
+                ConflictStrategy::Remote => Some(Side::Remote),
+                ConflictStrategy::Record => None,
+            };
+            report.conflicts.push(SyncConflict {
+                id: local_item.id(),
+                title: title_of(local_item.as_ref()),
+                kind: ConflictKind::BothChanged,
+                kept,
+            });
+            match kept {
+                Some(side) => Some(side),
+                // Keep the old versions so the conflict shows up again
+                None => return Ok(base),
+            }
+        }
+    };
+
+    let (local, remote) = match keep {
+        None => (local_item.updated_at(), remote_item.updated_at()),
+        Some(Side::Local) => {
+            let remote = pair
+                .write(Side::Remote, local_item.as_ref(), report)
+                .await?;
+            (local_item.updated_at(), remote)
+        }
+        Some(Side::Remote) => {
+            let local = pair
+                .write(Side::Local, remote_item.as_ref(), report)
+                .await?;
+            (local, remote_item.updated_at())
+        }
+    };
+    Ok(Some(SyncedItem { local, remote }))
+}
+
+/// Sync an item live only on side `live`, returning its versions if it
+/// ends up on both sides.
+async fn sync_one_sided(
+    pair: &Pair<'_>,
+    live: Side,
+    base: Option<SyncedItem>,
+    item: Item,
+    other: Presence,
+    strategy: ConflictStrategy,
+    report: &mut SyncReport,
+) -> Result<Option<SyncedItem>> {
+    let version = |base: SyncedItem| match live {
+        Side::Local => base.local,
+        Side::Remote => base.remote,
+    };
+    let changed = base.map_or(true, |base| item.updated_at() != version(base));
+    let deleted_at = match other {
+        Presence::Deleted(deleted_at) => Some(deleted_at),
+        _ => None,
+    };
+
+    let keep = match (base, deleted_at) {
+        // New on this side
+        (None, None) => true,
+        // Deleted on the other side and untouched here
+        (Some(_), _) if !changed => false,
+        // Changed here and deleted on the other side, or deleted there
+        // with no sync in between
+        (_, deleted_at) => {
+            let kept = match strategy {
+                ConflictStrategy::Newest => match deleted_at {
+                    Some(deleted_at) if deleted_at > item.updated_at() => Some(live.other()),
+                    _ => Some(live),
+                },
+                ConflictStrategy::Local => Some(Side::Local),
+                ConflictStrategy::Remote => Some(Side::Remote),
+                ConflictStrategy::Record => None,
+            };
+            report.conflicts.push(SyncConflict {
+                id: item.id(),
+                title: title_of(item.as_ref()),
+                kind: ConflictKind::ChangedAndDeleted(live.other()),
+                kept,
+            });
+            match kept {
+                Some(side) => side == live,
+                None => return Ok(base),
+            }
+        }
+    };
+
+    if !keep {
+        pair.delete(live, &item.id(), report).await?;
+        return Ok(None);
+    }
+    let written = pair.write(live.other(), item.as_ref(), report).await?;
+    let synced = match live {
+        Side::Local => SyncedItem {
+            local: item.updated_at(),
+            remote: written,
+        },
+        Side::Remote => SyncedItem {
+            local: written,
+            remote: item.updated_at(),
+        },
+    };
+    Ok(Some(synced))
+}
+
+/// Add the relations `from` has to `to`, where both ends are live there.
+async fn merge_relations(from: &dyn StorageBackend, to: &dyn StorageBackend) -> Result<usize> {
+    let existing: BTreeSet<_> = to.relations().await?.into_iter().collect();
+    let mut added = 0;
+    for relation in from.relations().await? {
+        if existing.contains(&relation)
+            || to.get(&relation.from).await?.is_none()
+            || to.get(&relation.to).await?.is_none()
+        {
+            continue;
+        }
+        to.add_relation(&relation.from, &relation.to, &relation.relation_type)
+            .await?;
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// Whether two copies of an item hold the same thing, whenever they were
+/// last changed.
+fn same_item(
+    a: &(dyn MemoryItem + Send + Sync),
+    b: &(dyn MemoryItem + Send + Sync),
+) -> Result<bool> {
+    let comparable = |item| -> Result<StoredItem> {
+        let mut stored = encode_item(item)?;
+        stored.tags.sort();
+        Ok(stored)
+    };
+    let (a, b) = (comparable(a)?, comparable(b)?);
+    Ok(a.item_type == b.item_type
+        && a.title == b.title
+        && a.content == b.content
+        && a.tags == b.tags
+        && a.metadata == b.metadata
+        && a.embedding == b.embedding)
+}
+
+fn title_of(item: &(dyn MemoryItem + Send + Sync)) -> String {
+    crate::memory::title_and_tags(item).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SnippetWithTags;
+    use crate::InMemoryBackend;
+    use chrono::Duration;
+
+    fn snippet(title: &str, at: DateTime<Utc>) -> SnippetWithTags {
+        let mut snippet = SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            format!("echo {}", title),
+            vec![],
+        );
+        snippet.created_at = at;
+        snippet.updated_at = at;
+        snippet
+    }
+
+    async fn content(backend: &InMemoryBackend, id: &Uuid) -> Option<String> {
+        let item = backend.get(id).await.unwrap()?;
+        Some(item.content().to_string())
+    }
+
+    async fn edit(
+        backend: &InMemoryBackend,
+        snippet: &SnippetWithTags,
+        content: &str,
+        at: DateTime<Utc>,
+    ) {
+        let mut edited = snippet.clone();
+        edited.content = content.to_string();
+        edited.updated_at = at;
+        backend.save(&edited).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_both_ways() {
+        let start = Utc::now() - Duration::days(1);
+        let local = InMemoryBackend::new();
+        let remote = InMemoryBackend::new();
+        let a = snippet("a", start);
+        let b = snippet("b", start);
+        let c = snippet("c", start);
+        local.save(&a).await.unwrap();
+        local.save(&c).await.unwrap();
+        remote.save(&b).await.unwrap();
+        local.add_relation(&a.id, &c.id, "SEE_ALSO").await.unwrap();
+
+        let mut state = SyncState::default();
+        let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+            .await
+            .unwrap();
+        assert_eq!((report.pushed, report.pulled, report.relations), (2, 1, 1));
+        assert!(report.conflicts.is_empty());
+        assert_eq!(state.items.len(), 3);
+
+        // Nothing changed, nothing to do
+        let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+            .await
+            .unwrap();
+        assert_eq!(report, SyncReport::default());
+
+        // An edit on one side and a deletion on the other both travel
+        edit(&remote, &a, "echo edited", start + Duration::hours(1)).await;
+        local.delete(&b.id).await.unwrap();
+        let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+            .await
+            .unwrap();
+        assert_eq!((report.pulled, report.deleted_remote), (1, 1));
+        assert_eq!(content(&local, &a.id).await.as_deref(), Some("echo edited"));
+        assert_eq!(content(&remote, &b.id).await, None);
+        assert_eq!(state.items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflicts() {
+        let start = Utc::now() - Duration::days(1);
+        let local = InMemoryBackend::new();
+        let remote = InMemoryBackend::new();
+        let a = snippet("a", start);
+        local.save(&a).await.unwrap();
+        let mut state = SyncState::default();
+        sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+            .await
+            .unwrap();
+
+        edit(&local, &a, "echo local", start + Duration::hours(1)).await;
+        edit(&remote, &a, "echo remote", start + Duration::hours(2)).await;
+
+        // A recorded conflict leaves both sides alone, and stays recorded
+        for _ in 0..2 {
+            let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+                .await
+                .unwrap();
+            assert_eq!(
+                report.conflicts,
+                vec![SyncConflict {
+                    id: a.id,
+                    title: "a".to_string(),
+                    kind: ConflictKind::BothChanged,
+                    kept: None,
+                }]
+            );
+            assert_eq!(content(&local, &a.id).await.as_deref(), Some("echo local"));
+        }
+
+        // The last writer wins
+        let report = sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+            .await
+            .unwrap();
+        assert_eq!(report.conflicts[0].kept, Some(Side::Remote));
+        assert_eq!(content(&local, &a.id).await.as_deref(), Some("echo remote"));
+
+        // An edit against a deletion, settled for the local side
+        edit(&local, &a, "echo again", Utc::now()).await;
+        remote.delete(&a.id).await.unwrap();
+        let report = sync(&local, &remote, &mut state, ConflictStrategy::Local)
+            .await
+            .unwrap();
+        assert_eq!(
+            report.conflicts[0].kind,
+            ConflictKind::ChangedAndDeleted(Side::Remote)
+        );
+        assert_eq!(report.pushed, 1);
+        assert_eq!(content(&remote, &a.id).await.as_deref(), Some("echo again"));
+    }
+}
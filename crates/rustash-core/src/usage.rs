@@ -0,0 +1,186 @@
+//! Usage tracking and frecency ranking.
+//!
+//! `snippets use` and `snippets run` record a [`Usage`] each time they use an
+//! item. Backends summarise them into [`UsageStats`], whose frecency score
+//! adds up a weight per use that shrinks as the use gets older, so items used
+//! often and recently rank first under the `frecency` sort.
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The `Query::sort_by` value that orders results by frecency.
+pub const FRECENCY_SORT: &str = "frecency";
+
+/// Weights of uses no older than the given number of days, newest first.
+pub const FRECENCY_BUCKETS: [(i64, u64); 4] = [(4, 100), (14, 70), (31, 50), (90, 30)];
+
+/// Weight of uses older than every bucket.
+pub const FRECENCY_OLD_WEIGHT: u64 = 10;
+
+/// One use of an item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// The item that was used
+    pub item_id: Uuid,
+    /// When it was used
+    pub used_at: DateTime<Utc>,
+    /// The working directory it was used in, if known
+    pub cwd: Option<String>,
+    /// The root of the git repository containing `cwd`, if any
+    pub repo: Option<String>,
+}
+
+impl Usage {
+    /// A use of `item_id` now, in the current working directory.
+    pub fn now(item_id: Uuid) -> Self {
+        let cwd = std::env::current_dir().ok();
+        Self {
+            item_id,
+            used_at: Utc::now(),
+            repo: cwd
+                .as_deref()
+                .and_then(repository_root)
+                .map(|root| root.display().to_string()),
+            cwd: cwd.map(|dir| dir.display().to_string()),
+        }
+    }
+
+    /// Where the item was used: its repository, or else its directory.
+    pub fn place(&self) -> Option<&str> {
+        self.repo.as_deref().or(self.cwd.as_deref())
+    }
+}
+
+/// How often and how recently an item was used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub item_id: Uuid,
+    /// Number of recorded uses
+    pub count: u64,
+    /// The most recent use
+    pub last_used_at: DateTime<Utc>,
+    /// The sum of [`frecency_weight`] over every use
+    pub frecency: u64,
+}
+
+impl UsageStats {
+    /// Summarise the uses of one item, or `None` when there are none.
+    pub fn from_uses<'a>(
+        uses: impl IntoIterator<Item = &'a Usage>,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let mut uses = uses.into_iter();
+        let first = uses.next()?;
+        let mut stats = Self {
+            item_id: first.item_id,
+            count: 1,
+            last_used_at: first.used_at,
+            frecency: frecency_weight(first.used_at, now),
+        };
+        for usage in uses {
+            stats.count += 1;
+            stats.last_used_at = stats.last_used_at.max(usage.used_at);
+            stats.frecency += frecency_weight(usage.used_at, now);
+        }
+        Some(stats)
+    }
+}
+
+/// The weight a use made at `used_at` adds to an item's frecency at `now`.
+pub fn frecency_weight(used_at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    FRECENCY_BUCKETS
+        .iter()
+        .find(|(days, _)| used_at >= now - Duration::days(*days))
+        .map_or(FRECENCY_OLD_WEIGHT, |(_, weight)| *weight)
+}
+
+/// The root of the git repository containing `dir`, if any.
+pub fn repository_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// The place of uses made now: the current repository, or else the
+/// current directory.
+pub fn current_place() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    let place = repository_root(&cwd).unwrap_or(cwd);
+    Some(place.display().to_string())
+}
+
+/// SQL summarising `snippet_usage` into rows of
+/// [`DbUsageStats`](crate::models::DbUsageStats) as of `now`, counting only
+/// uses made in `place` when given.
+///
+/// `time_literal` renders a timestamp as a literal comparable with the
+/// backend's timestamp columns.
+pub(crate) fn stats_sql(
+    place: Option<&str>,
+    now: DateTime<Utc>,
+    time_literal: fn(&NaiveDateTime) -> String,
+) -> String {
+    let weights: String = FRECENCY_BUCKETS
+        .iter()
+        .map(|(days, weight)| {
+            let cutoff = (now - Duration::days(*days)).naive_utc();
+            format!("WHEN used_at >= {} THEN {} ", time_literal(&cutoff), weight)
+        })
+        .collect();
+    let filter = place
+        .map(|place| {
+            format!(
+                " WHERE COALESCE(repo, cwd) = '{}'",
+                place.replace('\'', "''")
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "SELECT snippet_uuid, COUNT(*) AS uses, MAX(used_at) AS last_used_at, \
+         SUM(CASE {weights}ELSE {FRECENCY_OLD_WEIGHT} END) AS frecency \
+         FROM snippet_usage{filter} GROUP BY snippet_uuid"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn used(days_ago: i64, now: DateTime<Utc>) -> Usage {
+        Usage {
+            item_id: Uuid::nil(),
+            used_at: now - Duration::days(days_ago),
+            cwd: None,
+            repo: None,
+        }
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = Utc::now();
+        assert_eq!(frecency_weight(now, now), 100);
+        assert_eq!(frecency_weight(now - Duration::days(4), now), 100);
+        assert_eq!(frecency_weight(now - Duration::days(5), now), 70);
+        assert_eq!(frecency_weight(now - Duration::days(60), now), 30);
+        assert_eq!(frecency_weight(now - Duration::days(365), now), 10);
+
+        let uses = [used(1, now), used(20, now), used(400, now)];
+        let stats = UsageStats::from_uses(&uses, now).unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.last_used_at, uses[0].used_at);
+        assert_eq!(stats.frecency, 100 + 50 + 10);
+        assert!(UsageStats::from_uses(&[], now).is_none());
+    }
+
+    #[test]
+    fn test_place() {
+        let mut usage = used(0, Utc::now());
+        assert_eq!(usage.place(), None);
+        usage.cwd = Some("/work/app/src".to_string());
+        assert_eq!(usage.place(), Some("/work/app/src"));
+        usage.repo = Some("/work/app".to_string());
+        assert_eq!(usage.place(), Some("/work/app"));
+    }
+}
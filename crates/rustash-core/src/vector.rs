@@ -0,0 +1,242 @@
+//! In-process vector similarity helpers.
+//!
+//! Embeddings are stored as bincode-serialized `Vec<f32>` blobs. Backends that
+//! cannot delegate similarity search to the database (SQLite without an
+//! extension, the in-memory backend) decode those blobs and rank them here.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Number of accumulator lanes used by the distance kernels.
+///
+/// Eight `f32` lanes fill a 256-bit register, which lets the compiler
+/// auto-vectorise the inner loops without any `unsafe` intrinsics.
+const LANES: usize = 8;
+
+/// The metric used to compare two embeddings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    /// Cosine similarity, in `[-1, 1]`
+    #[default]
+    Cosine,
+    /// Euclidean distance, reported as `1 / (1 + distance)` so that higher is closer
+    L2,
+    /// Raw dot product
+    Dot,
+}
+
+/// Serialize an embedding into the blob format stored by the backends.
+pub fn encode_embedding(embedding: &[f32]) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(embedding)?)
+}
+
+/// Deserialize an embedding blob written by [`encode_embedding`].
+pub fn decode_embedding(bytes: &[u8]) -> Result<Vec<f32>> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+/// Dot product of two vectors of equal length.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let a_chunks = a.chunks_exact(LANES);
+    let b_chunks = b.chunks_exact(LANES);
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| x * y)
+        .sum();
+
+    let mut acc = [0.0f32; LANES];
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for ((sum, x), y) in acc.iter_mut().zip(x).zip(y) {
+            *sum += x * y;
+        }
+    }
+    acc.iter().sum::<f32>() + tail
+}
+
+/// Squared Euclidean distance between two vectors of equal length.
+pub fn squared_l2(a: &[f32], b: &[f32]) -> f32 {
+    let a_chunks = a.chunks_exact(LANES);
+    let b_chunks = b.chunks_exact(LANES);
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum();
+
+    let mut acc = [0.0f32; LANES];
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for ((sum, x), y) in acc.iter_mut().zip(x).zip(y) {
+            let d = x - y;
+            *sum += d * d;
+        }
+    }
+    acc.iter().sum::<f32>() + tail
+}
+
+/// Scores candidate embeddings against a fixed query vector.
+#[derive(Debug, Clone)]
+pub struct Scorer<'a> {
+    metric: DistanceMetric,
+    query: &'a [f32],
+    query_norm: f32,
+}
+
+impl<'a> Scorer<'a> {
+    /// Create a scorer for the given query vector.
+    pub fn new(metric: DistanceMetric, query: &'a [f32]) -> Self {
+        Self {
+            metric,
+            query,
+            query_norm: dot(query, query).sqrt(),
+        }
+    }
+
+    /// Score a candidate; higher is more similar.
+    ///
+    /// Returns `None` when the candidate has a different dimension than the query.
+    pub fn score(&self, candidate: &[f32]) -> Option<f32> {
+        if candidate.len() != self.query.len() {
+            return None;
+        }
+
+        let score = match self.metric {
+            DistanceMetric::Cosine => {
+                let norms = self.query_norm * dot(candidate, candidate).sqrt();
+                if norms == 0.0 {
+                    0.0
+                } else {
+                    dot(self.query, candidate) / norms
+                }
+            }
+            DistanceMetric::L2 => 1.0 / (1.0 + squared_l2(self.query, candidate).sqrt()),
+            DistanceMetric::Dot => dot(self.query, candidate),
+        };
+        Some(score)
+    }
+}
+
+struct Scored<T> {
+    score: f32,
+    item: T,
+}
+
+impl<T> PartialEq for Scored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.score.total_cmp(&other.score) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Scored<T> {}
+
+impl<T> PartialOrd for Scored<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scored<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score)
+    }
+}
+
+/// Keeps the `k` highest-scoring items seen so far.
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<Scored<T>>>,
+}
+
+impl<T> TopK<T> {
+    /// Create an empty collector that retains at most `k` items.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k.saturating_add(1)),
+        }
+    }
+
+    /// Offer an item; it is kept only if it ranks among the best `k`.
+    pub fn push(&mut self, item: T, score: f32) {
+        if self.k == 0 || score.is_nan() {
+            return;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(Scored { score, item }));
+        } else if let Some(Reverse(worst)) = self.heap.peek() {
+            if score > worst.score {
+                self.heap.pop();
+                self.heap.push(Reverse(Scored { score, item }));
+            }
+        }
+    }
+
+    /// Consume the collector, returning items ordered from best to worst.
+    pub fn into_sorted_vec(self) -> Vec<(T, f32)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| (scored.item, scored.score))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernels_handle_remainders() {
+        let a: Vec<f32> = (0..11).map(|i| i as f32).collect();
+        let b: Vec<f32> = (0..11).map(|i| (i * 2) as f32).collect();
+
+        let expected_dot: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        let expected_l2: f32 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+
+        assert_eq!(dot(&a, &b), expected_dot);
+        assert_eq!(squared_l2(&a, &b), expected_l2);
+    }
+
+    #[test]
+    fn test_scorer_metrics() {
+        let query = [1.0, 0.0, 0.0];
+
+        let cosine = Scorer::new(DistanceMetric::Cosine, &query);
+        assert!((cosine.score(&[2.0, 0.0, 0.0]).unwrap() - 1.0).abs() < 1e-6);
+        assert!(cosine.score(&[0.0, 1.0, 0.0]).unwrap().abs() < 1e-6);
+        assert_eq!(cosine.score(&[0.0, 0.0, 0.0]), Some(0.0));
+        assert_eq!(cosine.score(&[1.0, 0.0]), None);
+
+        let l2 = Scorer::new(DistanceMetric::L2, &query);
+        assert_eq!(l2.score(&[1.0, 0.0, 0.0]), Some(1.0));
+        assert!((l2.score(&[4.0, 4.0, 0.0]).unwrap() - 1.0 / 6.0).abs() < 1e-6);
+
+        let dot_scorer = Scorer::new(DistanceMetric::Dot, &query);
+        assert_eq!(dot_scorer.score(&[3.0, 5.0, 7.0]), Some(3.0));
+    }
+
+    #[test]
+    fn test_top_k_keeps_best_scores() {
+        let mut top = TopK::new(2);
+        top.push("low", 0.1);
+        top.push("high", 0.9);
+        top.push("nan", f32::NAN);
+        top.push("mid", 0.5);
+
+        assert_eq!(top.into_sorted_vec(), vec![("high", 0.9), ("mid", 0.5)]);
+        assert!(TopK::<u8>::new(0).into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn test_embedding_round_trip() {
+        let embedding = vec![0.25, -1.5, 3.0];
+        let bytes = encode_embedding(&embedding).unwrap();
+        assert_eq!(bytes, bincode::serialize(&embedding).unwrap());
+        assert_eq!(decode_embedding(&bytes).unwrap(), embedding);
+    }
+}
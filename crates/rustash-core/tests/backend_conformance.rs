@@ -0,0 +1,1122 @@
+//! Behaviour every `StorageBackend` must share.
+//!
+//! Each case below is run against every backend through `conformance_tests!`.
+//! SQLite and the in-memory backend always run; PostgreSQL runs when
+//! `DATABASE_URL` points at a postgres server.
+//!
+//! Cases tag everything they create with a unique scope tag and filter on it,
+//! so they can share a database with other data and run concurrently.
+
+use chrono::{DateTime, TimeZone, Utc};
+use rustash_core::{
+    copy::{self, CopyCheckpoint, CopyOptions},
+    dump::{self, Record},
+    memory::register_item_type,
+    models::{Page, Query, Relation, Run, TagFilter, TrashedItem},
+    storage::StorageBackend,
+    vector::encode_embedding,
+    Error, InMemoryBackend, MemoryItem, SnippetWithTags, Usage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A backend under test, plus whatever must outlive it.
+struct Fixture {
+    backend: Box<dyn StorageBackend>,
+    _guard: Option<tempfile::TempDir>,
+}
+
+async fn in_memory() -> Option<Fixture> {
+    Some(Fixture {
+        backend: Box::new(InMemoryBackend::new()),
+        _guard: None,
+    })
+}
+
+#[cfg(feature = "sqlite")]
+async fn sqlite() -> Option<Fixture> {
+    use rustash_core::{database::sqlite_pool, SqliteBackend};
+
+    // A file rather than `:memory:`, so every pooled connection sees the same data.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("conformance.db");
+    let pool = sqlite_pool::create_pool(path.to_str().unwrap())
+        .await
+        .unwrap();
+    Some(Fixture {
+        backend: Box::new(SqliteBackend::new(pool)),
+        _guard: Some(dir),
+    })
+}
+
+#[cfg(feature = "postgres")]
+async fn postgres() -> Option<Fixture> {
+    use rustash_core::{database::postgres_pool, PostgresBackend};
+
+    let url = std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|url| url.starts_with("postgres"))?;
+    let pool = postgres_pool::create_pool(&url).await.unwrap();
+    // The database is shared by every case, and whole-stash reads such as
+    // dumps decode the notes other cases left behind.
+    register_item_type::<Note>("conformance_note").unwrap();
+    Some(Fixture {
+        backend: Box::new(PostgresBackend::new(pool)),
+        _guard: None,
+    })
+}
+
+/// Generate one test per conformance case for the backend built by `$setup`.
+macro_rules! conformance_tests {
+    ($backend:ident, $setup:ident) => {
+        mod $backend {
+            conformance_tests!(@cases $setup;
+                save_and_get,
+                save_upserts,
+                save_keeps_timestamps,
+                delete_removes_item,
+                query_filters_by_item_type,
+                query_requires_all_tags,
+                query_tag_filter,
+                query_limit,
+                query_sort_by_title,
+                query_rejects_unknown_sort_field,
+                query_sort_directions_and_keys,
+                query_offset,
+                query_page_cursors,
+                query_page_cursor_on_text_search,
+                text_filter_matches_all_terms,
+                relations,
+                add_relation_requires_both_items,
+                add_relation_rejects_invalid_types,
+                relations_are_listed,
+                vector_search_ranks_by_similarity,
+                versions_track_changes,
+                runs_are_recorded,
+                usage_stats_and_frecency,
+                query_boosts_and_scopes,
+                trash_restore_and_purge,
+                restore_counts_as_a_change,
+                dump_round_trip,
+                copy_into_stash,
+            );
+        }
+    };
+    (@cases $setup:ident; $($case:ident),* $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $case() {
+                // Backends without a configured server are skipped.
+                if let Some(fixture) = super::$setup().await {
+                    super::$case(fixture.backend.as_ref(), &super::Scope::new()).await;
+                }
+            }
+        )*
+    };
+}
+
+conformance_tests!(in_memory_backend, in_memory);
+
+#[cfg(feature = "sqlite")]
+conformance_tests!(sqlite_backend, sqlite);
+
+#[cfg(feature = "postgres")]
+conformance_tests!(postgres_backend, postgres);
+
+/// A unique tag isolating the items created by one test case.
+struct Scope {
+    tag: String,
+}
+
+impl Scope {
+    fn new() -> Self {
+        Self {
+            tag: format!("scope-{}", Uuid::new_v4().simple()),
+        }
+    }
+
+    fn snippet(&self, title: &str, content: &str, tags: &[&str]) -> SnippetWithTags {
+        let mut all_tags = vec![self.tag.clone()];
+        all_tags.extend(tags.iter().map(|tag| tag.to_string()));
+        SnippetWithTags::with_uuid(
+            Uuid::new_v4(),
+            title.to_string(),
+            content.to_string(),
+            all_tags,
+        )
+    }
+
+    /// A query restricted to this scope.
+    fn query(&self) -> Query {
+        Query {
+            tags: Some(vec![self.tag.clone()]),
+            ..Default::default()
+        }
+    }
+
+    fn query_tags(&self, tags: &[&str]) -> Query {
+        let mut query = self.query();
+        if let Some(all_tags) = query.tags.as_mut() {
+            all_tags.extend(tags.iter().map(|tag| tag.to_string()));
+        }
+        query
+    }
+}
+
+/// A non-snippet item type, stored through its serde representation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Note {
+    id: Uuid,
+    title: String,
+    body: String,
+    tags: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl MemoryItem for Note {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+    fn item_type(&self) -> &'static str {
+        "conformance_note"
+    }
+    fn content(&self) -> &str {
+        &self.body
+    }
+    fn metadata(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            ("title".to_string(), Value::from(self.title.clone())),
+            ("tags".to_string(), Value::from(self.tags.clone())),
+        ])
+    }
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+    fn set_updated_at(&mut self, updated_at: DateTime<Utc>) {
+        self.updated_at = updated_at;
+    }
+    fn clone_dyn(&self) -> Box<dyn MemoryItem> {
+        Box::new(self.clone())
+    }
+    fn clone_dyn_send_sync(&self) -> Box<dyn MemoryItem + Send + Sync> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+fn titles(items: &[Box<dyn MemoryItem + Send + Sync>]) -> Vec<String> {
+    items
+        .iter()
+        .map(
+            |item| match item.as_any().downcast_ref::<SnippetWithTags>() {
+                Some(snippet) => snippet.title.clone(),
+                None => item.metadata()["title"].as_str().unwrap().to_string(),
+            },
+        )
+        .collect()
+}
+
+fn sorted(mut values: Vec<String>) -> Vec<String> {
+    values.sort();
+    values
+}
+
+async fn save_and_get(backend: &dyn StorageBackend, scope: &Scope) {
+    let snippet = scope.snippet("Greeting", "echo hello", &["shell"]);
+    backend.save(&snippet).await.unwrap();
+
+    let item = backend.get(&snippet.id()).await.unwrap().unwrap();
+    let stored = item.as_any().downcast_ref::<SnippetWithTags>().unwrap();
+    assert_eq!(stored.uuid, snippet.uuid);
+    assert_eq!(stored.title, "Greeting");
+    assert_eq!(stored.content, "echo hello");
+    assert_eq!(sorted(stored.tags.clone()), sorted(snippet.tags.clone()));
+    assert_eq!(item.item_type(), "snippet");
+
+    assert!(backend.get(&Uuid::new_v4()).await.unwrap().is_none());
+}
+
+async fn save_upserts(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut snippet = scope.snippet("Draft", "first", &[]);
+    backend.save(&snippet).await.unwrap();
+
+    snippet.title = "Final".to_string();
+    snippet.content = "second".to_string();
+    backend.save(&snippet).await.unwrap();
+
+    let results = backend.query(&scope.query()).await.unwrap();
+    assert_eq!(titles(&results), vec!["Final"]);
+    assert_eq!(results[0].content(), "second");
+    assert!(results[0].updated_at() >= results[0].created_at());
+}
+
+async fn save_keeps_timestamps(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut snippet = scope.snippet("Old", "echo old", &[]);
+    snippet.created_at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+    snippet.updated_at = Utc.with_ymd_and_hms(2021, 6, 7, 8, 9, 10).unwrap();
+    backend.save(&snippet).await.unwrap();
+
+    let item = backend.get(&snippet.id()).await.unwrap().unwrap();
+    assert_eq!(item.created_at(), snippet.created_at);
+    assert_eq!(item.updated_at(), snippet.updated_at);
+
+    snippet.content = "echo new".to_string();
+    snippet.updated_at = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+    backend.save(&snippet).await.unwrap();
+    let item = backend.get(&snippet.id()).await.unwrap().unwrap();
+    assert_eq!(item.created_at(), snippet.created_at);
+    assert_eq!(item.updated_at(), snippet.updated_at);
+}
+
+async fn delete_removes_item(backend: &dyn StorageBackend, scope: &Scope) {
+    let snippet = scope.snippet("Temporary", "rm -rf build", &[]);
+    backend.save(&snippet).await.unwrap();
+    backend.delete(&snippet.id()).await.unwrap();
+
+    assert!(backend.get(&snippet.id()).await.unwrap().is_none());
+    assert!(backend.query(&scope.query()).await.unwrap().is_empty());
+
+    // Deleting something that does not exist is not an error
+    backend.delete(&Uuid::new_v4()).await.unwrap();
+}
+
+async fn query_filters_by_item_type(backend: &dyn StorageBackend, scope: &Scope) {
+    register_item_type::<Note>("conformance_note").unwrap();
+
+    backend
+        .save(&scope.snippet("A snippet", "ls", &[]))
+        .await
+        .unwrap();
+    let now = Utc::now();
+    let note = Note {
+        id: Uuid::new_v4(),
+        title: "A note".to_string(),
+        body: "remember the milk".to_string(),
+        tags: vec![scope.tag.clone()],
+        created_at: now,
+        updated_at: now,
+    };
+    backend.save(&note).await.unwrap();
+
+    let mut query = scope.query();
+    query.item_type = Some("conformance_note".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["A note"]);
+    assert!(results[0].as_any().downcast_ref::<Note>().is_some());
+
+    query.item_type = Some("snippet".to_string());
+    assert_eq!(
+        titles(&backend.query(&query).await.unwrap()),
+        vec!["A snippet"]
+    );
+
+    let all = backend.query(&scope.query()).await.unwrap();
+    assert_eq!(sorted(titles(&all)), vec!["A note", "A snippet"]);
+}
+
+async fn query_requires_all_tags(backend: &dyn StorageBackend, scope: &Scope) {
+    for (title, tags) in [
+        ("both", &["docker", "ops"][..]),
+        ("docker only", &["docker"][..]),
+        ("ops only", &["ops"][..]),
+    ] {
+        backend
+            .save(&scope.snippet(title, "content", tags))
+            .await
+            .unwrap();
+    }
+
+    let results = backend
+        .query(&scope.query_tags(&["docker", "ops"]))
+        .await
+        .unwrap();
+    assert_eq!(titles(&results), vec!["both"]);
+
+    let results = backend.query(&scope.query_tags(&["docker"])).await.unwrap();
+    assert_eq!(sorted(titles(&results)), vec!["both", "docker only"]);
+
+    let results = backend.query(&scope.query_tags(&["k8s"])).await.unwrap();
+    assert!(results.is_empty());
+}
+
+async fn query_tag_filter(backend: &dyn StorageBackend, scope: &Scope) {
+    for (title, tags) in [
+        ("rust cli", &["lang/rust", "cli"][..]),
+        ("go cli", &["lang/go", "cli"][..]),
+        ("rust web", &["lang/rust", "web"][..]),
+        ("notes", &["language"][..]),
+    ] {
+        backend
+            .save(&scope.snippet(title, "content", tags))
+            .await
+            .unwrap();
+    }
+    let strings = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    let filtered = |all: &[&str], any: &[&str], none: &[&str]| {
+        let mut query = scope.query();
+        query.tag_filter = TagFilter {
+            all: strings(all),
+            any: strings(any),
+            none: strings(none),
+        };
+        query
+    };
+
+    let results = backend
+        .query(&filtered(&[], &["web", "lang/go"], &[]))
+        .await
+        .unwrap();
+    assert_eq!(sorted(titles(&results)), vec!["go cli", "rust web"]);
+
+    let results = backend
+        .query(&filtered(&["cli"], &[], &["lang/go"]))
+        .await
+        .unwrap();
+    assert_eq!(titles(&results), vec!["rust cli"]);
+
+    // Prefix patterns match hierarchical tags but not look-alikes
+    let results = backend
+        .query(&filtered(&["lang/*"], &[], &[]))
+        .await
+        .unwrap();
+    assert_eq!(
+        sorted(titles(&results)),
+        vec!["go cli", "rust cli", "rust web"]
+    );
+    let results = backend
+        .query(&filtered(&[], &[], &["lang/*"]))
+        .await
+        .unwrap();
+    assert_eq!(titles(&results), vec!["notes"]);
+
+    // `tags` and the tag filter combine
+    let mut query = filtered(&[], &["cli", "web"], &["lang/go"]);
+    query.tags.as_mut().unwrap().push("lang/rust".to_string());
+    query.text_filter = Some("rust".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(sorted(titles(&results)), vec!["rust cli", "rust web"]);
+}
+
+async fn query_limit(backend: &dyn StorageBackend, scope: &Scope) {
+    for title in ["one", "two", "three"] {
+        backend
+            .save(&scope.snippet(title, "content", &[]))
+            .await
+            .unwrap();
+    }
+
+    let mut query = scope.query();
+    query.limit = Some(2);
+    assert_eq!(backend.query(&query).await.unwrap().len(), 2);
+
+    query.limit = None;
+    assert_eq!(backend.query(&query).await.unwrap().len(), 3);
+}
+
+async fn query_sort_by_title(backend: &dyn StorageBackend, scope: &Scope) {
+    for title in ["banana", "cherry", "apple"] {
+        backend
+            .save(&scope.snippet(title, "fruit", &[]))
+            .await
+            .unwrap();
+    }
+
+    let mut query = scope.query();
+    query.sort_by = Some("title".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["apple", "banana", "cherry"]);
+
+    // Sorting also applies to text searches
+    query.text_filter = Some("fruit".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["apple", "banana", "cherry"]);
+}
+
+async fn query_rejects_unknown_sort_field(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut query = scope.query();
+    query.sort_by = Some("popularity".to_string());
+    assert!(backend.query(&query).await.is_err());
+}
+
+async fn text_filter_matches_all_terms(backend: &dyn StorageBackend, scope: &Scope) {
+    for (title, content) in [
+        ("Docker cleanup", "docker system prune"),
+        ("Docker build", "docker build -t app ."),
+        ("List files", "ls -la"),
+    ] {
+        backend
+            .save(&scope.snippet(title, content, &[]))
+            .await
+            .unwrap();
+    }
+
+    let mut query = scope.query();
+    query.text_filter = Some("docker".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(
+        sorted(titles(&results)),
+        vec!["Docker build", "Docker cleanup"]
+    );
+
+    query.text_filter = Some("docker prune".to_string());
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["Docker cleanup"]);
+
+    // Ranked search returns the same items, best first
+    let hits = backend.search(&query).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].item.content(), "docker system prune");
+
+    query.text_filter = Some("kubernetes".to_string());
+    assert!(backend.query(&query).await.unwrap().is_empty());
+}
+
+async fn relations(backend: &dyn StorageBackend, scope: &Scope) {
+    let a = scope.snippet("a", "", &[]);
+    let b = scope.snippet("b", "", &[]);
+    let c = scope.snippet("c", "", &[]);
+    for item in [&a, &b, &c] {
+        backend.save(item).await.unwrap();
+    }
+
+    backend
+        .add_relation(&a.id(), &b.id(), "DEPENDS_ON")
+        .await
+        .unwrap();
+    // Adding the same relation twice is a no-op
+    backend
+        .add_relation(&a.id(), &b.id(), "DEPENDS_ON")
+        .await
+        .unwrap();
+    backend
+        .add_relation(&a.id(), &c.id(), "SEE_ALSO")
+        .await
+        .unwrap();
+
+    let related = backend.get_related(&a.id(), None).await.unwrap();
+    assert_eq!(sorted(titles(&related)), vec!["b", "c"]);
+
+    let related = backend
+        .get_related(&a.id(), Some("SEE_ALSO"))
+        .await
+        .unwrap();
+    assert_eq!(titles(&related), vec!["c"]);
+
+    // Relations are directed
+    assert!(backend.get_related(&b.id(), None).await.unwrap().is_empty());
+
+    // Deleted items are no longer reachable
+    backend.delete(&c.id()).await.unwrap();
+    let related = backend.get_related(&a.id(), None).await.unwrap();
+    assert_eq!(titles(&related), vec!["b"]);
+}
+
+async fn add_relation_requires_both_items(backend: &dyn StorageBackend, scope: &Scope) {
+    let a = scope.snippet("a", "", &[]);
+    backend.save(&a).await.unwrap();
+
+    assert!(backend
+        .add_relation(&a.id(), &Uuid::new_v4(), "SEE_ALSO")
+        .await
+        .is_err());
+    assert!(backend
+        .add_relation(&Uuid::new_v4(), &a.id(), "SEE_ALSO")
+        .await
+        .is_err());
+}
+
+async fn add_relation_rejects_invalid_types(backend: &dyn StorageBackend, scope: &Scope) {
+    let a = scope.snippet("a", "", &[]);
+    let b = scope.snippet("b", "", &[]);
+    backend.save(&a).await.unwrap();
+    backend.save(&b).await.unwrap();
+
+    for relation_type in [
+        "",
+        "SEE ALSO",
+        "see-also",
+        "1ST",
+        "X]->(n) DETACH DELETE n //",
+    ] {
+        let result = backend.add_relation(&a.id(), &b.id(), relation_type).await;
+        assert!(
+            matches!(result, Err(Error::Validation(_))),
+            "{relation_type:?} was accepted"
+        );
+    }
+    assert!(relations_from(backend, &[a.id()]).await.is_empty());
+
+    backend
+        .add_relation(&a.id(), &b.id(), "_see_also2")
+        .await
+        .unwrap();
+}
+
+/// The listed relations leaving any of `ids`; other cases may be adding
+/// relations concurrently.
+async fn relations_from(backend: &dyn StorageBackend, ids: &[Uuid]) -> Vec<Relation> {
+    let relations = backend.relations().await.unwrap();
+    relations
+        .into_iter()
+        .filter(|relation| ids.contains(&relation.from))
+        .collect()
+}
+
+async fn relations_are_listed(backend: &dyn StorageBackend, scope: &Scope) {
+    let a = scope.snippet("a", "", &[]);
+    let b = scope.snippet("b", "", &[]);
+    let c = scope.snippet("c", "", &[]);
+    for item in [&a, &b, &c] {
+        backend.save(item).await.unwrap();
+    }
+    for (from, to, relation_type) in [
+        (&a, &c, "SEE_ALSO"),
+        (&a, &b, "DEPENDS_ON"),
+        (&c, &a, "SEE_ALSO"),
+    ] {
+        backend
+            .add_relation(&from.id(), &to.id(), relation_type)
+            .await
+            .unwrap();
+    }
+
+    let relation = |from: &SnippetWithTags, to: &SnippetWithTags, relation_type: &str| Relation {
+        from: from.id(),
+        to: to.id(),
+        relation_type: relation_type.to_string(),
+    };
+    let mut expected = vec![
+        relation(&a, &b, "DEPENDS_ON"),
+        relation(&a, &c, "SEE_ALSO"),
+        relation(&c, &a, "SEE_ALSO"),
+    ];
+    expected.sort();
+    assert_eq!(relations_from(backend, &[a.id(), c.id()]).await, expected);
+
+    // Relations touching trashed items are not listed
+    backend.delete(&c.id()).await.unwrap();
+    assert_eq!(
+        relations_from(backend, &[a.id(), c.id()]).await,
+        vec![relation(&a, &b, "DEPENDS_ON")]
+    );
+}
+
+async fn vector_search_ranks_by_similarity(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut ids = HashMap::new();
+    for (title, embedding) in [
+        ("east", [1.0, 0.0, 0.0]),
+        ("north", [0.0, 1.0, 0.0]),
+        ("north-east", [0.7, 0.7, 0.0]),
+    ] {
+        let mut snippet = scope.snippet(title, title, &[]);
+        snippet.embedding = Some(encode_embedding(&embedding).unwrap());
+        backend.save(&snippet).await.unwrap();
+        ids.insert(snippet.id(), title);
+    }
+    backend
+        .save(&scope.snippet("no embedding", "", &[]))
+        .await
+        .unwrap();
+
+    // Other data may share the database, so only this scope's items are compared
+    let results = backend.vector_search(&[1.0, 0.1, 0.0], 100).await.unwrap();
+    let ranked: Vec<&str> = results
+        .iter()
+        .filter_map(|(item, _)| ids.get(&item.id()).copied())
+        .collect();
+    assert_eq!(ranked, vec!["east", "north-east", "north"]);
+
+    let scores: Vec<f32> = results.iter().map(|(_, score)| *score).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    assert!(backend
+        .vector_search(&[1.0, 0.0, 0.0], 0)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn save_titles(backend: &dyn StorageBackend, scope: &Scope, titles: &[&str]) {
+    for title in titles {
+        backend
+            .save(&scope.snippet(title, "fruit", &[]))
+            .await
+            .unwrap();
+    }
+}
+
+async fn query_sort_directions_and_keys(backend: &dyn StorageBackend, scope: &Scope) {
+    save_titles(backend, scope, &["banana", "apple", "cherry", "apple"]).await;
+
+    let query = scope.query().with_sort("title:desc");
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["cherry", "banana", "apple", "apple"]);
+
+    // Equal titles fall through to the next key, then to the id
+    let query = scope.query().with_sort("title,created_at:asc");
+    let results = backend.query(&query).await.unwrap();
+    assert_eq!(titles(&results), vec!["apple", "apple", "banana", "cherry"]);
+    let key = |item: &dyn MemoryItem| (item.created_at(), item.id());
+    assert!(key(results[0].as_ref()) < key(results[1].as_ref()));
+
+    assert!(backend
+        .query(&scope.query().with_sort("title:sideways"))
+        .await
+        .is_err());
+}
+
+async fn query_offset(backend: &dyn StorageBackend, scope: &Scope) {
+    save_titles(backend, scope, &["a", "b", "c", "d", "e"]).await;
+
+    let query = scope
+        .query()
+        .with_sort("title")
+        .with_offset(2)
+        .with_limit(2);
+    assert_eq!(
+        titles(&backend.query(&query).await.unwrap()),
+        vec!["c", "d"]
+    );
+
+    let query = scope.query().with_sort("title").with_offset(4);
+    assert_eq!(titles(&backend.query(&query).await.unwrap()), vec!["e"]);
+
+    // Offsets apply to text searches too
+    let mut query = scope.query().with_sort("title").with_offset(3);
+    query.text_filter = Some("fruit".to_string());
+    assert_eq!(
+        titles(&backend.query(&query).await.unwrap()),
+        vec!["d", "e"]
+    );
+}
+
+/// Follow cursors from the first page to the last, collecting every page.
+async fn all_pages(backend: &dyn StorageBackend, query: Query) -> Vec<Vec<String>> {
+    let mut pages = Vec::new();
+    let mut query = query;
+    loop {
+        let Page { items, next_cursor } = backend.query_page(&query).await.unwrap();
+        pages.push(titles(&items));
+        match next_cursor {
+            Some(cursor) => query.after = Some(cursor),
+            None => return pages,
+        }
+    }
+}
+
+async fn query_page_cursors(backend: &dyn StorageBackend, scope: &Scope) {
+    save_titles(backend, scope, &["d", "b", "e", "a", "c"]).await;
+
+    let pages = all_pages(backend, scope.query().with_sort("title").with_limit(2)).await;
+    assert_eq!(pages, vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+
+    let pages = all_pages(backend, scope.query().with_sort("title:desc").with_limit(3)).await;
+    assert_eq!(pages, vec![vec!["e", "d", "c"], vec!["b", "a"]]);
+
+    // The default order is stable even when timestamps tie
+    let pages = all_pages(backend, scope.query().with_limit(2)).await;
+    assert_eq!(pages.len(), 3);
+    assert_eq!(sorted(pages.concat()), vec!["a", "b", "c", "d", "e"]);
+
+    // An exact final page has no next cursor
+    let page = backend
+        .query_page(&scope.query().with_limit(5))
+        .await
+        .unwrap();
+    assert_eq!(page.items.len(), 5);
+    assert!(page.next_cursor.is_none());
+
+    // Cursors are tied to the order they were created for
+    let page = backend
+        .query_page(&scope.query().with_sort("title").with_limit(1))
+        .await
+        .unwrap();
+    let cursor = page.next_cursor.unwrap();
+    let query = scope.query().with_sort("updated_at").with_cursor(&cursor);
+    assert!(backend.query(&query).await.is_err());
+    assert!(backend
+        .query(&scope.query().with_cursor("garbage"))
+        .await
+        .is_err());
+}
+
+async fn query_page_cursor_on_text_search(backend: &dyn StorageBackend, scope: &Scope) {
+    save_titles(backend, scope, &["c", "a", "b"]).await;
+
+    let mut query = scope.query().with_sort("title").with_limit(2);
+    query.text_filter = Some("fruit".to_string());
+    assert_eq!(
+        all_pages(backend, query.clone()).await,
+        vec![vec!["a", "b"], vec!["c"]]
+    );
+
+    // Relevance order has no stable position to resume from
+    let page = backend.query_page(&query).await.unwrap();
+    query.sort_by = None;
+    query.after = page.next_cursor;
+    assert!(backend.query(&query).await.is_err());
+}
+
+async fn versions_track_changes(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut snippet = scope.snippet("Deploy", "make deploy", &["ops"]);
+    backend.save(&snippet).await.unwrap();
+    // Saving without changes records nothing
+    backend.save(&snippet).await.unwrap();
+
+    snippet.content = "make deploy ENV=prod".to_string();
+    backend.save(&snippet).await.unwrap();
+    snippet.tags.push("prod".to_string());
+    backend.save(&snippet).await.unwrap();
+
+    let versions = backend.versions(&snippet.id()).await.unwrap();
+    let numbers: Vec<u32> = versions.iter().map(|version| version.number).collect();
+    assert_eq!(numbers, vec![1, 2, 3]);
+    assert_eq!(versions[0].content, "make deploy");
+    assert_eq!(versions[1].content, "make deploy ENV=prod");
+    assert_eq!(
+        sorted(versions[2].tags.clone()),
+        sorted(snippet.tags.clone())
+    );
+    assert!(versions
+        .iter()
+        .all(|version| version.item_id == snippet.id()));
+
+    let first = backend.version(&snippet.id(), 1).await.unwrap().unwrap();
+    assert_eq!(first, versions[0]);
+    assert!(backend.version(&snippet.id(), 4).await.unwrap().is_none());
+
+    // History survives the trash
+    backend.delete(&snippet.id()).await.unwrap();
+    assert_eq!(backend.versions(&snippet.id()).await.unwrap(), versions);
+}
+
+async fn runs_are_recorded(backend: &dyn StorageBackend, scope: &Scope) {
+    let snippet = scope.snippet("Build", "cargo build", &[]);
+    backend.save(&snippet).await.unwrap();
+    assert!(backend.runs(&snippet.id()).await.unwrap().is_empty());
+
+    // Whole seconds, which every backend stores exactly
+    let started_at = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+    let failed = Run {
+        item_id: snippet.id(),
+        started_at,
+        cwd: Some("/tmp/project".to_string()),
+        exit_code: Some(101),
+    };
+    let killed = Run {
+        started_at: started_at + chrono::Duration::seconds(5),
+        cwd: None,
+        exit_code: None,
+        ..failed.clone()
+    };
+    backend.record_run(&killed).await.unwrap();
+    backend.record_run(&failed).await.unwrap();
+    assert_eq!(
+        backend.runs(&snippet.id()).await.unwrap(),
+        vec![failed, killed]
+    );
+
+    // Purging the item drops its runs
+    backend.delete(&snippet.id()).await.unwrap();
+    backend.purge(None).await.unwrap();
+    assert!(backend.runs(&snippet.id()).await.unwrap().is_empty());
+}
+
+async fn usage_stats_and_frecency(backend: &dyn StorageBackend, scope: &Scope) {
+    let titles_by_frecency = |offset: usize| {
+        let query = scope.query().with_sort("frecency").with_offset(offset);
+        async move { titles(&backend.query(&query).await.unwrap()) }
+    };
+    let often = scope.snippet("Often", "git status", &[]);
+    let lately = scope.snippet("Lately", "git log", &[]);
+    let never = scope.snippet("Never", "git gc", &[]);
+    for snippet in [&often, &lately, &never] {
+        backend.save(snippet).await.unwrap();
+    }
+
+    // Whole seconds, which every backend stores exactly
+    let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+    let place = format!("/work/{}", scope.tag);
+    let use_of = |snippet: &SnippetWithTags, days_ago: i64, repo: Option<&str>| Usage {
+        item_id: snippet.id(),
+        used_at: now - chrono::Duration::days(days_ago),
+        cwd: Some(format!("{place}/src")),
+        repo: repo.map(str::to_string),
+    };
+    // Three older uses (3 x 50) outweigh one recent use (100)
+    for days_ago in [20, 25, 30] {
+        backend
+            .record_usage(&use_of(&often, days_ago, Some(&place)))
+            .await
+            .unwrap();
+    }
+    backend
+        .record_usage(&use_of(&lately, 1, None))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        titles_by_frecency(0).await,
+        vec!["Often", "Lately", "Never"]
+    );
+    assert_eq!(titles_by_frecency(1).await, vec!["Lately", "Never"]);
+    let cursor = scope.query().with_sort("frecency").with_cursor("00");
+    assert!(backend.query(&cursor).await.is_err());
+
+    let mut stats = backend.usage_stats(None).await.unwrap();
+    stats.retain(|stats| stats.item_id == often.id() || stats.item_id == lately.id());
+    stats.sort_by_key(|stats| stats.count);
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].item_id, lately.id());
+    assert_eq!(stats[0].last_used_at, now - chrono::Duration::days(1));
+    assert_eq!((stats[0].count, stats[0].frecency), (1, 100));
+    assert_eq!((stats[1].count, stats[1].frecency), (3, 150));
+
+    // A place is a use's repository, or else its directory
+    let stats = backend.usage_stats(Some(&place)).await.unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].item_id, often.id());
+    let cwd = format!("{place}/src");
+    let stats = backend.usage_stats(Some(&cwd)).await.unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].item_id, lately.id());
+
+    // Purging an item drops its uses
+    backend.delete(&often.id()).await.unwrap();
+    backend.purge(None).await.unwrap();
+    assert!(backend.usage_stats(Some(&place)).await.unwrap().is_empty());
+}
+
+async fn query_boosts_and_scopes(backend: &dyn StorageBackend, scope: &Scope) {
+    for (title, tags) in [
+        ("Pip", &["python"][..]),
+        ("Cargo", &["lang/rust"]),
+        ("Deploy", &["project:billing"]),
+        ("Grep", &[]),
+        ("Payroll", &["project:payroll"]),
+    ] {
+        backend
+            .save(&scope.snippet(title, "echo", tags))
+            .await
+            .unwrap();
+    }
+    let ranked = |offset: usize| {
+        let mut query = scope.query().with_sort("title").with_offset(offset);
+        query.boost_tags = vec!["rust".to_string(), "lang/*".to_string()];
+        query.project = Some("billing".to_string());
+        async move { titles(&backend.query(&query).await.unwrap()) }
+    };
+
+    // Boosted items first, each group keeping the sort order, and other
+    // projects' items left out
+    assert_eq!(ranked(0).await, vec!["Cargo", "Deploy", "Grep", "Pip"]);
+    assert_eq!(ranked(2).await, vec!["Grep", "Pip"]);
+
+    let mut frecent = scope.query().with_sort("frecency");
+    frecent.boost_tags = vec!["project:payroll".to_string()];
+    assert_eq!(
+        titles(&backend.query(&frecent).await.unwrap())[0],
+        "Payroll"
+    );
+
+    let page = backend
+        .query_page(&scope.query().with_limit(2))
+        .await
+        .unwrap();
+    assert!(page.next_cursor.is_some());
+    let mut cursor = scope.query().with_cursor(&page.next_cursor.unwrap());
+    cursor.project = Some("billing".to_string());
+    assert!(backend.query(&cursor).await.is_err());
+}
+
+/// The trash entries for `id`; other cases may be trashing items concurrently.
+async fn trash_entries(backend: &dyn StorageBackend, id: Uuid) -> Vec<TrashedItem> {
+    let mut entries = backend.trash().await.unwrap();
+    entries.retain(|entry| entry.item.id() == id);
+    entries
+}
+
+async fn trash_restore_and_purge(backend: &dyn StorageBackend, scope: &Scope) {
+    let kept = scope.snippet("Kept", "echo kept", &[]);
+    let mut trashed = scope.snippet("Trashed", "echo trashed", &[]);
+    trashed.embedding = Some(encode_embedding(&[1.0, 0.0]).unwrap());
+    backend.save(&kept).await.unwrap();
+    backend.save(&trashed).await.unwrap();
+    backend
+        .add_relation(&kept.id(), &trashed.id(), "SEE_ALSO")
+        .await
+        .unwrap();
+
+    // Trashed items are hidden from every read
+    backend.delete(&trashed.id()).await.unwrap();
+    assert!(backend.get(&trashed.id()).await.unwrap().is_none());
+    let results = backend.query(&scope.query()).await.unwrap();
+    assert_eq!(titles(&results), vec!["Kept"]);
+    assert!(backend
+        .get_related(&kept.id(), None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(backend
+        .vector_search(&[1.0, 0.0], 100)
+        .await
+        .unwrap()
+        .iter()
+        .all(|(item, _)| item.id() != trashed.id()));
+    assert!(backend
+        .add_relation(&trashed.id(), &kept.id(), "SEE_ALSO")
+        .await
+        .is_err());
+
+    let entries = trash_entries(backend, trashed.id()).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        titles(&[entries[0].item.clone_dyn_send_sync()]),
+        vec!["Trashed"]
+    );
+    let deleted_at = entries[0].deleted_at;
+
+    // Deleting again keeps the original tombstone
+    backend.delete(&trashed.id()).await.unwrap();
+    let entries = trash_entries(backend, trashed.id()).await;
+    assert_eq!(entries[0].deleted_at, deleted_at);
+
+    // Restoring brings back the item and its relations
+    backend.restore(&trashed.id()).await.unwrap();
+    assert!(backend.get(&trashed.id()).await.unwrap().is_some());
+    let related = backend.get_related(&kept.id(), None).await.unwrap();
+    assert_eq!(titles(&related), vec!["Trashed"]);
+    assert!(trash_entries(backend, trashed.id()).await.is_empty());
+    let err = backend.restore(&trashed.id()).await.unwrap_err();
+    assert!(err.is_not_found());
+
+    // Purging only removes items deleted before the cutoff
+    backend.delete(&trashed.id()).await.unwrap();
+    let deleted_at = trash_entries(backend, trashed.id()).await[0].deleted_at;
+    backend
+        .purge(Some(deleted_at - chrono::Duration::minutes(1)))
+        .await
+        .unwrap();
+    assert_eq!(trash_entries(backend, trashed.id()).await.len(), 1);
+
+    assert!(backend.purge(None).await.unwrap() >= 1);
+    assert!(trash_entries(backend, trashed.id()).await.is_empty());
+    assert!(backend.versions(&trashed.id()).await.unwrap().is_empty());
+    assert!(backend
+        .restore(&trashed.id())
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    // A purged item saved again starts with no relations
+    backend.save(&trashed).await.unwrap();
+    assert!(backend
+        .get_related(&kept.id(), None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn restore_counts_as_a_change(backend: &dyn StorageBackend, scope: &Scope) {
+    let mut snippet = scope.snippet("Restored", "echo back", &[]);
+    snippet.created_at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+    snippet.updated_at = Utc.with_ymd_and_hms(2021, 6, 7, 8, 9, 10).unwrap();
+    backend.save(&snippet).await.unwrap();
+    backend.delete(&snippet.id()).await.unwrap();
+    let deleted_at = trash_entries(backend, snippet.id()).await[0].deleted_at;
+
+    // The restore is newer than the deletion it undoes
+    backend.restore(&snippet.id()).await.unwrap();
+    let item = backend.get(&snippet.id()).await.unwrap().unwrap();
+    assert!(item.updated_at() >= deleted_at);
+    assert_eq!(item.created_at(), snippet.created_at);
+    assert_eq!(item.content(), "echo back");
+}
+
+async fn dump_round_trip(backend: &dyn StorageBackend, scope: &Scope) {
+    let source = InMemoryBackend::new();
+    let mut a = scope.snippet("a", "echo a", &["shell"]);
+    a.created_at = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+    a.updated_at = Utc.with_ymd_and_hms(2021, 6, 7, 8, 9, 10).unwrap();
+    a.embedding = Some(encode_embedding(&[0.5, -0.25, 1.0]).unwrap());
+    // Postgres keeps microseconds, so neither item uses `Utc::now()`
+    let mut b = scope.snippet("b", "echo b", &[]);
+    b.created_at = a.created_at;
+    b.updated_at = a.created_at;
+    source.save(&a).await.unwrap();
+    source.save(&b).await.unwrap();
+    source
+        .add_relation(&a.id(), &b.id(), "SEE_ALSO")
+        .await
+        .unwrap();
+    let records = dump::dump(&source).await.unwrap();
+    let text = dump::write(&records, dump::DumpFormat::Ndjson).unwrap();
+
+    let summary = dump::load(backend, dump::read(&text).unwrap(), dump::LoadMode::Merge)
+        .await
+        .unwrap();
+    assert_eq!((summary.added, summary.relations), (2, 1));
+
+    // The stash gives back what it was given, whatever else it holds
+    let ids = [a.id(), b.id()];
+    let copied: Vec<Record> = dump::dump(backend)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|record| match record {
+            Record::Header { .. } => false,
+            Record::Item(item) => ids.contains(&item.id),
+            Record::Relation(relation) => ids.contains(&relation.from),
+        })
+        .collect();
+    assert_eq!(copied, records[1..]);
+
+    // Loading again changes nothing
+    let summary = dump::load(backend, dump::read(&text).unwrap(), dump::LoadMode::Merge)
+        .await
+        .unwrap();
+    assert_eq!(
+        (summary.added, summary.updated, summary.unchanged),
+        (0, 0, 2)
+    );
+}
+
+async fn copy_into_stash(backend: &dyn StorageBackend, scope: &Scope) {
+    let source = InMemoryBackend::new();
+    let snippets: Vec<_> = ["a", "b", "c"]
+        .iter()
+        .map(|title| scope.snippet(title, "echo", &[]))
+        .collect();
+    for snippet in &snippets {
+        source.save(snippet).await.unwrap();
+    }
+    source
+        .add_relation(&snippets[0].id(), &snippets[2].id(), "SEE_ALSO")
+        .await
+        .unwrap();
+    let options = CopyOptions {
+        batch_size: 2,
+        ..Default::default()
+    };
+
+    let mut batches = 0;
+    let summary = copy::copy(&source, backend, options, CopyCheckpoint::default(), |_| {
+        batches += 1;
+        Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!((batches, summary.added, summary.relations), (2, 3, 1));
+    for snippet in &snippets {
+        assert!(backend.get(&snippet.id()).await.unwrap().is_some());
+    }
+    assert_eq!(relations_from(backend, &[snippets[0].id()]).await.len(), 1);
+}
@@ -1,12 +1,13 @@
 use diesel::prelude::*;
 use diesel::sql_query;
+use diesel_async::RunQueryDsl;
 use rustash_core::database::create_test_pool;
 
 #[tokio::test]
 async fn check_applied_migrations() -> Result<(), Box<dyn std::error::Error>> {
     // Create a test pool
     let pool = create_test_pool().await?;
-    let mut conn = pool.get_connection().await?;
+    let mut conn = pool.get().await?;
 
     // Query the migrations table
     #[derive(QueryableByName, Debug)]
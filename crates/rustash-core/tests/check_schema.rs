@@ -1,5 +1,6 @@
 use diesel::prelude::*;
 use diesel::sql_query;
+use diesel_async::RunQueryDsl;
 use diesel::sql_types::*;
 use rustash_core::database::create_test_pool;
 
@@ -17,7 +18,7 @@ struct ColumnInfo {
     cid: i32,
     #[diesel(sql_type = Text)]
     name: String,
-    #[diesel(sql_type = Text)]
+    #[diesel(sql_type = Text, column_name = "type")]
     type_: String,
     #[diesel(sql_type = Integer)]
     notnull: i32,
@@ -0,0 +1,137 @@
+//! Two-way sync between two SQLite files, one standing in for the remote
+//! stash.
+
+#![cfg(feature = "sqlite")]
+
+use chrono::{Duration, Utc};
+use rustash_core::{
+    database::sqlite_pool,
+    sync::{sync, ConflictKind, ConflictStrategy, Side, SyncState},
+    SnippetWithTags, SqliteBackend, StorageBackend,
+};
+use uuid::Uuid;
+
+async fn sqlite(dir: &tempfile::TempDir, name: &str) -> SqliteBackend {
+    let path = dir.path().join(name);
+    let pool = sqlite_pool::create_pool(path.to_str().unwrap())
+        .await
+        .unwrap();
+    SqliteBackend::new(pool)
+}
+
+async fn content(backend: &SqliteBackend, id: &Uuid) -> Option<String> {
+    let item = backend.get(id).await.unwrap()?;
+    Some(item.content().to_string())
+}
+
+#[tokio::test]
+async fn test_sync_two_sqlite_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let local = sqlite(&dir, "local.db").await;
+    let remote = sqlite(&dir, "remote.db").await;
+    let mut state = SyncState::default();
+
+    let mut snippet = SnippetWithTags::with_uuid(
+        Uuid::new_v4(),
+        "Deploy".to_string(),
+        "make deploy".to_string(),
+        vec!["ops".to_string()],
+    );
+    snippet.updated_at = Utc::now() - Duration::hours(2);
+    local.save(&snippet).await.unwrap();
+    sync(&local, &remote, &mut state, ConflictStrategy::Record)
+        .await
+        .unwrap();
+    assert_eq!(
+        content(&remote, &snippet.id).await.as_deref(),
+        Some("make deploy")
+    );
+
+    // Whatever else the new files hold is the same on both sides now
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+        .await
+        .unwrap();
+    assert_eq!((report.pushed, report.pulled), (0, 0));
+    assert!(report.conflicts.is_empty());
+
+    // Offline edits on both sides conflict; the newest one wins
+    snippet.content = "make deploy-local".to_string();
+    snippet.updated_at = Utc::now() - Duration::hours(1);
+    local.save(&snippet).await.unwrap();
+    snippet.content = "make deploy-remote".to_string();
+    snippet.updated_at = Utc::now();
+    remote.save(&snippet).await.unwrap();
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+        .await
+        .unwrap();
+    assert_eq!(report.conflicts[0].kind, ConflictKind::BothChanged);
+    assert_eq!(report.conflicts[0].kept, None);
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+        .await
+        .unwrap();
+    assert_eq!(report.conflicts[0].kept, Some(Side::Remote));
+    assert_eq!(
+        content(&local, &snippet.id).await.as_deref(),
+        Some("make deploy-remote")
+    );
+
+    // A deletion travels through the trash
+    remote.delete(&snippet.id).await.unwrap();
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Record)
+        .await
+        .unwrap();
+    assert_eq!(report.deleted_local, 1);
+    assert_eq!(content(&local, &snippet.id).await, None);
+}
+
+#[tokio::test]
+async fn test_sync_restore_after_synced_delete() {
+    let dir = tempfile::tempdir().unwrap();
+    let local = sqlite(&dir, "local.db").await;
+    let remote = sqlite(&dir, "remote.db").await;
+    let mut state = SyncState::default();
+
+    let mut snippet = SnippetWithTags::with_uuid(
+        Uuid::new_v4(),
+        "Backup".to_string(),
+        "restic backup ~".to_string(),
+        vec![],
+    );
+    snippet.updated_at = Utc::now() - Duration::hours(1);
+    local.save(&snippet).await.unwrap();
+    sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+        .await
+        .unwrap();
+
+    // Deleted here, and the deletion synced to the other side
+    local.delete(&snippet.id).await.unwrap();
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+        .await
+        .unwrap();
+    assert_eq!(report.deleted_remote, 1);
+    assert_eq!(content(&remote, &snippet.id).await, None);
+
+    // Restoring is newer than the remote tombstone, so it travels back
+    local.restore(&snippet.id).await.unwrap();
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+        .await
+        .unwrap();
+    assert_eq!(report.conflicts[0].kept, Some(Side::Local));
+    assert_eq!(
+        content(&local, &snippet.id).await.as_deref(),
+        Some("restic backup ~")
+    );
+    assert_eq!(
+        content(&remote, &snippet.id).await.as_deref(),
+        Some("restic backup ~")
+    );
+
+    let report = sync(&local, &remote, &mut state, ConflictStrategy::Newest)
+        .await
+        .unwrap();
+    assert!(report.conflicts.is_empty());
+    assert_eq!(
+        content(&local, &snippet.id).await.as_deref(),
+        Some("restic backup ~")
+    );
+}